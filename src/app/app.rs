@@ -22,14 +22,47 @@ impl App {
         console_log::init_with_level(log::Level::Debug).expect("error initializing log");
         info!("Starting application...");
         let event_loop = EventLoop::new().unwrap();
-        match event_loop.run_app(&mut self.state) {
-            Ok(_) => {
-                info!("Run application...");
-            }
-            Err(err) => {
-                info!("err application {}", err);
+
+        // returns at once, the page can still call the app while the loop runs
+        #[cfg(target_arch = "wasm32")] {
+            use winit::platform::web::EventLoopExtWebSys;
+            event_loop.spawn_app(self.state.clone());
+        }
+
+        #[cfg(not(target_arch = "wasm32"))] {
+            match event_loop.run_app(&mut self.state) {
+                Ok(_) => {
+                    info!("Run application...");
+                }
+                Err(err) => {
+                    info!("err application {}", err);
+                }
             }
+            info!("Release application...");
         }
-        info!("Release application...");
+    }
+
+    /// Replaces the bookmarks with the text of a bookmarks file.
+    #[wasm_bindgen]
+    pub fn import_bookmarks(&mut self, text: &str) -> Result<(), String> {
+        self.state.import_bookmarks(text).map_err(|err| err.to_string())
+    }
+
+    /// The bookmarks as the text of a bookmarks file, to save or share.
+    #[wasm_bindgen]
+    pub fn export_bookmarks(&self) -> String {
+        self.state.export_bookmarks()
+    }
+
+    /// Bookmarks the shown view and layers, false before the first frame.
+    #[wasm_bindgen]
+    pub fn add_bookmark(&mut self, name: &str) -> bool {
+        self.state.add_bookmark(name)
+    }
+
+    /// Zooms to the cell `name`, false for an unknown cell or before the first frame.
+    #[wasm_bindgen]
+    pub fn zoom_to_cell(&mut self, name: &str) -> bool {
+        self.state.zoom_to_cell(name)
     }
 }
\ No newline at end of file
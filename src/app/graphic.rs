@@ -1,10 +1,14 @@
 use std::sync::Arc;
 use wgpu::{Adapter, Device, Instance, Queue, Surface, SurfaceConfiguration};
 use winit::window::Window;
+use crate::control::layers::LayerCommand;
+use crate::control::view::ViewCommand;
 use crate::control::navigation::NavigationEvent;
 use crate::draw::context::DrawContext;
+use crate::draw::document::Document;
 use crate::draw::geometry::GeometryPainter;
 use crate::draw::painter::{Painter, PainterLibrary};
+use crate::eye::bookmark::Bookmark;
 use crate::geometry::size::Size;
 
 pub(crate) struct GraphicContext {
@@ -17,7 +21,7 @@ pub(crate) struct GraphicContext {
 }
 
 impl GraphicContext {
-    pub async fn with_window(window: Window) -> GraphicContext {
+    pub async fn with_window(window: Window, document: Document) -> GraphicContext {
         let instance = Instance::default();
         let win = Arc::new(window);
         let surface = instance.create_surface(win.clone()).unwrap();
@@ -32,6 +36,7 @@ impl GraphicContext {
         size.height = size.height.max(100);
 
         let geometry_painter = GeometryPainter::create(
+            document,
             swapchain_format.into(),
             &device,
             size.width,
@@ -122,4 +127,32 @@ impl GraphicContext {
         self.painter_library.navigation_event(navigation_event);
     }
 
+    pub(crate) fn animate(&mut self) {
+        self.painter_library.animate();
+    }
+
+    pub(crate) fn camera_timestamp(&self) -> usize {
+        self.painter_library.camera_timestamp()
+    }
+
+    pub(crate) fn layer_command(&mut self, command: LayerCommand) -> bool {
+        self.painter_library.layer_command(command)
+    }
+
+    pub(crate) fn view_command(&mut self, command: ViewCommand) {
+        self.painter_library.view_command(command);
+    }
+
+    pub(crate) fn cell_index(&self, name: &str) -> Option<usize> {
+        self.painter_library.cell_index(name)
+    }
+
+    pub(crate) fn bookmark(&self, name: String) -> Bookmark {
+        self.painter_library.bookmark(name)
+    }
+
+    pub(crate) fn show_bookmark(&mut self, bookmark: &Bookmark) -> bool {
+        self.painter_library.show_bookmark(bookmark)
+    }
+
 }
\ No newline at end of file
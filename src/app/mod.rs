@@ -1,3 +1,5 @@
+#[allow(clippy::module_inception)]
 pub(crate) mod app;
 pub mod state;
-mod graphic;
\ No newline at end of file
+mod graphic;
+mod redraw;
\ No newline at end of file
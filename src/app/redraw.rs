@@ -0,0 +1,131 @@
+/// Decides when the window needs a new frame, so the event loop can sleep otherwise.
+pub(crate) struct RedrawTracker {
+    dirty: bool,
+    camera_timestamp: usize,
+    frames: usize,
+}
+
+impl RedrawTracker {
+    pub(crate) fn new() -> Self {
+        // the first frame is always needed
+        Self { dirty: true, camera_timestamp: usize::MAX, frames: 0 }
+    }
+
+    /// Resize, document change or anything else that changes the picture.
+    pub(crate) fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Navigation events only need a frame if they moved the camera.
+    pub(crate) fn update_camera(&mut self, timestamp: usize) {
+        if self.camera_timestamp != timestamp {
+            self.camera_timestamp = timestamp;
+            self.dirty = true;
+        }
+    }
+
+    /// Called once the pending events are handled, true if a frame must be requested.
+    /// Any number of changes between two calls results in a single frame.
+    pub(crate) fn request_frame(&mut self) -> bool {
+        if !self.dirty {
+            return false;
+        }
+        self.dirty = false;
+        self.frames += 1;
+        true
+    }
+
+    /// Number of frames requested so far, a hook for tests.
+    #[cfg(test)]
+    pub(crate) fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::redraw::RedrawTracker;
+    use crate::eye::camera::OrthoCamera;
+    use crate::geometry::point::Point;
+    use crate::geometry::rect::Rect;
+    use crate::geometry::size::Size;
+
+    enum Step {
+        Resize(Size),
+        Move(Point),
+        Zoom(f64),
+        // a navigation event that did not touch the camera, like a cursor move without a button
+        Hover,
+        Document,
+        Idle,
+    }
+
+    // replays the steps the way the event loop does: events first, then `about_to_wait`
+    fn frames(steps: &[Step]) -> usize {
+        let view_box = Rect::new(Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 });
+        let mut camera = OrthoCamera::new(Size { width: 800.0, height: 600.0 }, view_box);
+        let mut tracker = RedrawTracker::new();
+        tracker.update_camera(camera.timestamp());
+
+        for step in steps.iter() {
+            match step {
+                Step::Resize(size) => {
+                    camera.set_screen(*size);
+                    tracker.invalidate();
+                }
+                Step::Move(position) => {
+                    camera.move_to(*position);
+                    tracker.update_camera(camera.timestamp());
+                }
+                Step::Zoom(zoom) => {
+                    camera.set_zoom(*zoom, Point { x: 400.0, y: 300.0 });
+                    tracker.update_camera(camera.timestamp());
+                }
+                Step::Hover => tracker.update_camera(camera.timestamp()),
+                Step::Document => tracker.invalidate(),
+                Step::Idle => {
+                    tracker.request_frame();
+                }
+            }
+        }
+
+        tracker.frames()
+    }
+
+    #[test]
+    fn test_idle() {
+        assert_eq!(frames(&[Step::Idle, Step::Idle, Step::Idle]), 1);
+        assert_eq!(frames(&[Step::Idle, Step::Hover, Step::Idle, Step::Hover, Step::Hover, Step::Idle]), 1);
+    }
+
+    #[test]
+    fn test_drag() {
+        let steps = [
+            Step::Idle,
+            Step::Move(Point { x: 1.0, y: 0.0 }),
+            Step::Idle,
+            Step::Move(Point { x: 2.0, y: 0.0 }),
+            Step::Hover,
+            Step::Idle,
+            Step::Idle,
+        ];
+        assert_eq!(frames(&steps), 3);
+    }
+
+    #[test]
+    fn test_coalesced() {
+        // several changes before the loop gets idle give one frame
+        let steps = [
+            Step::Idle,
+            Step::Resize(Size { width: 400.0, height: 400.0 }),
+            Step::Zoom(2.0),
+            Step::Zoom(3.0),
+            Step::Document,
+            Step::Idle,
+            Step::Zoom(1.0),
+            Step::Idle,
+            Step::Idle,
+        ];
+        assert_eq!(frames(&steps), 3);
+    }
+}
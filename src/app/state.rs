@@ -1,19 +1,114 @@
 use std::sync::{Arc, Mutex};
-use log::info;
+use log::{info, warn};
 use winit::application::ApplicationHandler;
 use winit::event::{DeviceEvent, DeviceId, WindowEvent};
 use winit::event_loop::ActiveEventLoop;
 use winit::window::{Window, WindowId};
 use crate::app::graphic::GraphicContext;
-use crate::control::navigation::{NavigationControl};
+use crate::app::redraw::RedrawTracker;
+use crate::control::keymap::{KeyAction, KeyControl, Keymap};
+use crate::control::navigation::{NavigationControl, NavigationEvent};
+use crate::control::view::ViewCommand;
+use crate::draw::document::Document;
+use crate::eye::bookmark::{BookmarkError, Bookmarks};
+use crate::gds::error::GdsError;
+use crate::gds::reader::{read_library, ReadMode};
+use crate::lyp::error::LypError;
+use crate::lyp::properties::{apply_layer_properties, read_layer_properties};
 
+/// Clones share the same context, the web event loop keeps one while the page calls the other.
+#[derive(Clone)]
 pub struct AppState {
     context: Arc<Mutex<Context>>,
 }
 
 impl AppState {
     pub fn new() -> Self {
-        AppState { context: Arc::new(Mutex::from(Context::new())) }
+        Self::with_document(Document::polygons())
+    }
+
+    /// In lenient mode broken elements are skipped and reported to the log.
+    pub fn with_gds(data: &[u8], lenient: bool) -> Result<Self, GdsError> {
+        let mode = if lenient { ReadMode::Lenient } else { ReadMode::Strict };
+        let (library, warnings) = read_library(data, mode)?;
+        for warning in warnings.iter() {
+            warn!("skipped: {warning}");
+        }
+        info!("library {}: {} structures", library.name, library.structures.len());
+        Ok(Self::with_document(Document::with_library(&library)))
+    }
+
+    /// Styles the layers with a KLayout `.lyp` file before the window opens,
+    /// layers the file does not list keep the palette colors.
+    pub fn load_layer_properties(&mut self, text: &str) -> Result<(), LypError> {
+        let properties = read_layer_properties(text)?;
+        if let Ok(mut context) = self.context.lock() {
+            if let Some(document) = &mut context.document {
+                apply_layer_properties(&mut document.layers, &properties);
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the bookmarks with the ones of a bookmarks file.
+    pub fn import_bookmarks(&mut self, text: &str) -> Result<(), BookmarkError> {
+        let bookmarks = Bookmarks::with_text(text)?;
+        if let Ok(mut context) = self.context.lock() {
+            context.bookmarks = bookmarks;
+        }
+        Ok(())
+    }
+
+    /// The bookmarks as the text of a bookmarks file.
+    pub fn export_bookmarks(&self) -> String {
+        if let Ok(context) = self.context.lock() {
+            context.bookmarks.to_text()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Bookmarks the shown view under `name`, false while there is no window yet.
+    pub fn add_bookmark(&mut self, name: &str) -> bool {
+        let Ok(mut context) = self.context.lock() else {
+            return false;
+        };
+        let context = &mut *context;
+        let Some(graphic) = &context.graphic else {
+            return false;
+        };
+        context.bookmarks.add(graphic.bookmark(name.to_string()));
+        true
+    }
+
+    /// Zooms to the first placement of the cell `name`, false for an unknown cell
+    /// or while there is no window yet.
+    pub fn zoom_to_cell(&mut self, name: &str) -> bool {
+        let Ok(mut context) = self.context.lock() else {
+            return false;
+        };
+        let context = &mut *context;
+        let Some(graphic) = &mut context.graphic else {
+            return false;
+        };
+        let Some(index) = graphic.cell_index(name) else {
+            return false;
+        };
+        graphic.view_command(ViewCommand::ZoomToCell(index));
+        context.redraw.update_camera(graphic.camera_timestamp());
+        // the call comes from outside the event loop, the redraw wakes it up for the animation
+        graphic.window.request_redraw();
+        true
+    }
+
+    fn with_document(document: Document) -> Self {
+        AppState { context: Arc::new(Mutex::from(Context::new(document))) }
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -49,16 +144,23 @@ impl AppState {
     }
 
     fn init_window(&mut self, event_loop: &ActiveEventLoop) {
-        if let Ok(mut context) = self.context.lock() {
-            context.state = ContextState::Initializing
-        }
+        let document = if let Ok(mut context) = self.context.lock() {
+            context.state = ContextState::Initializing;
+            context.document.take()
+        } else {
+            None
+        };
+
+        let Some(document) = document else {
+            return;
+        };
 
         let window = Self::build_window(event_loop);
 
         let clone_context = Arc::clone(&self.context);
         #[cfg(not(target_arch = "wasm32"))] {
             use pollster::FutureExt;
-            let graphic = GraphicContext::with_window(window).block_on();
+            let graphic = GraphicContext::with_window(window, document).block_on();
             if let Ok(mut context) = clone_context.lock() {
                 context.graphic = Some(graphic);
                 context.state = ContextState::Ready;
@@ -68,7 +170,9 @@ impl AppState {
         #[cfg(target_arch = "wasm32")] {
             use wasm_bindgen_futures::spawn_local;
             spawn_local(async move {
-                let graphic = GraphicContext::with_window(window).await;
+                let graphic = GraphicContext::with_window(window, document).await;
+                // the loop may already sleep, nothing else would wake it for the first frame
+                graphic.window.request_redraw();
                 if let Ok(mut context) = clone_context.lock() {
                     context.graphic = Some(graphic);
                     context.state = ContextState::Ready;
@@ -87,14 +191,25 @@ enum ContextState {
 
 pub struct Context {
     state: ContextState,
+    document: Option<Document>,
     graphic: Option<GraphicContext>,
     navigation: NavigationControl,
-    counter: i32,
+    key_control: KeyControl,
+    bookmarks: Bookmarks,
+    redraw: RedrawTracker,
 }
 
 impl Context {
-    fn new() -> Self {
-        Self { state: ContextState::None, graphic: None, navigation: NavigationControl::new(), counter: 0 }
+    fn new(document: Document) -> Self {
+        Self {
+            state: ContextState::None,
+            document: Some(document),
+            graphic: None,
+            navigation: NavigationControl::new(),
+            key_control: KeyControl::new(Keymap::default()),
+            bookmarks: Bookmarks::default(),
+            redraw: RedrawTracker::new(),
+        }
     }
 
     fn handle_window_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
@@ -104,18 +219,68 @@ impl Context {
             return;
         };
 
+        // all the keys go through the keymap, the pan and zoom steps go on to navigation
+        let key_event = match &event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.key_control.update_modifiers(modifiers.state());
+                None
+            }
+            WindowEvent::KeyboardInput { event, .. } => match self.key_control.process_key(event) {
+                Some(KeyAction::Layer(command)) => {
+                    if graphic.layer_command(command) {
+                        self.redraw.invalidate();
+                    }
+                    None
+                }
+                Some(KeyAction::View(ViewCommand::ZoomBox)) => {
+                    self.navigation.arm_zoom_box();
+                    None
+                }
+                Some(KeyAction::View(ViewCommand::AddBookmark)) => {
+                    let bookmark = graphic.bookmark(self.bookmarks.next_name());
+                    info!("bookmark {}", bookmark.name);
+                    self.bookmarks.add(bookmark);
+                    None
+                }
+                Some(KeyAction::View(ViewCommand::ShowBookmark(index))) => {
+                    if let Some(bookmark) = self.bookmarks.get(index) {
+                        if graphic.show_bookmark(bookmark) {
+                            self.redraw.invalidate();
+                        }
+                        self.redraw.update_camera(graphic.camera_timestamp());
+                    }
+                    None
+                }
+                Some(KeyAction::View(command)) => {
+                    graphic.view_command(command);
+                    self.redraw.update_camera(graphic.camera_timestamp());
+                    None
+                }
+                Some(action) => self.navigation.key_event(action),
+                None => None,
+            },
+            _ => None,
+        };
+
         match event {
             WindowEvent::Resized(new_size) => {
                 let new_size = graphic.resize(new_size.width, new_size.height);
                 self.navigation.update_size(new_size);
+                self.redraw.invalidate();
             }
             WindowEvent::RedrawRequested => {
                 graphic.draw();
             }
             WindowEvent::CloseRequested => event_loop.exit(),
             _ => {
-                if let Some(nav_event) = self.navigation.process_event(event) {
+                if let Some(nav_event) = key_event.or_else(|| self.navigation.process_event(event)) {
+                    // the zoom box is drawn over the layout and does not move the camera
+                    let is_zoom_box = matches!(nav_event, NavigationEvent::MoveZoomBox(_) | NavigationEvent::CancelZoomBox | NavigationEvent::ZoomRect(_));
                     graphic.process_navigation_event(nav_event);
+                    if is_zoom_box {
+                        self.redraw.invalidate();
+                    }
+                    self.redraw.update_camera(graphic.camera_timestamp());
                 }
             }
         };
@@ -147,9 +312,16 @@ impl ApplicationHandler for AppState {
         }
 
         if let Ok(context) = &mut self.context.lock() {
+            let context = &mut **context;
             if let Some(graphic) = &mut context.graphic {
-                graphic.window.request_redraw();
-                context.counter += 1;
+                // an animation moves the camera, which asks for the next frame in turn
+                graphic.animate();
+                context.redraw.update_camera(graphic.camera_timestamp());
+
+                // the loop sleeps until the next event unless the picture changed
+                if context.redraw.request_frame() {
+                    graphic.window.request_redraw();
+                }
             }
         }
     }
@@ -0,0 +1,189 @@
+use std::time::Instant;
+use i_triangle::i_overlay::i_float::point::IntPoint;
+use i_triangle::i_overlay::i_float::rect::IntRect;
+use crate::cli::print;
+use crate::draw::document::Document;
+use crate::draw::visibility::Visibility;
+use crate::gds::library::{ArrayRef, Boundary, Element, Library, STrans, StructRef, Structure, Units};
+
+const USAGE: &str = "usage: bench [--polygons N]";
+const DEFAULT_POLYGONS: usize = 1_000_000;
+// squares of this size on a grid of this pitch, spread over a few layers
+const SQUARE: i32 = 80;
+const PITCH: i32 = 100;
+const LAYERS: u16 = 4;
+const POINT_QUERIES: usize = 10_000;
+// the hierarchical layout: a block of squares placed this many times one by one, over an
+// array of the same block with this many columns and rows
+const BLOCK_SQUARES: usize = 16;
+const BLOCK_PITCH: i32 = 500;
+const SINGLE_BLOCKS: usize = 10_000;
+const ARRAY_SIDE: u16 = 1000;
+
+/// `bench [--polygons N]`
+///
+/// Times the spatial index on a synthetic layout of N squares, a million by default: building
+/// the document, culling views from the whole layout down to a small part of it and finding
+/// shapes by rect and by point. Then culls the same views of a hierarchical layout, a block
+/// placed ten thousand times over a million times larger array of it.
+pub(super) fn run(args: &[String]) -> Result<(), String> {
+    let mut polygons = DEFAULT_POLYGONS;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--polygons" => {
+                let value = iter.next().ok_or(USAGE)?;
+                polygons = value.parse().map_err(|_| format!("bad polygon count {value}"))?;
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let library = flat_library(polygons);
+    let start = Instant::now();
+    let document = Document::with_library(&library);
+    print(&format!("flat, build {polygons} polygons: {:.0} ms\n", millis(start)))?;
+
+    let Some(bounds) = document.bounds() else {
+        return Ok(());
+    };
+    cull_views(&document, &bounds)?;
+    let cell_layers = || document.cells.iter().flat_map(|cell| cell.layers.iter());
+
+    for part in [1, 10, 100] {
+        let view = centered(&bounds, part);
+        let start = Instant::now();
+        let found: usize = cell_layers().map(|layer| layer.shapes_in_rect(&view).len()).sum();
+        print(&format!("view 1/{part} of the side: {found} shapes in rect in {:.3} ms\n", millis(start)))?;
+    }
+
+    // points along the diagonal, most of them on a square
+    let start = Instant::now();
+    let mut found = 0;
+    for i in 0..POINT_QUERIES {
+        let t = i as f64 / POINT_QUERIES as f64;
+        let x = bounds.min_x + (t * (bounds.max_x - bounds.min_x) as f64) as i32;
+        let y = bounds.min_y + (t * (bounds.max_y - bounds.min_y) as f64) as i32;
+        found += cell_layers().map(|layer| layer.shapes_at_point(IntPoint::new(x, y)).len()).sum::<usize>();
+    }
+    print(&format!("{POINT_QUERIES} point queries: {found} shapes in {:.3} ms\n", millis(start)))?;
+
+    let document = Document::with_library(&hierarchical_library());
+    print(&format!("hierarchical, {SINGLE_BLOCKS} blocks and a {ARRAY_SIDE} x {ARRAY_SIDE} array of them\n"))?;
+    if let Some(bounds) = document.bounds() {
+        cull_views(&document, &bounds)?;
+    }
+
+    Ok(())
+}
+
+// what the window draws of views from the whole layout down to a small part of it,
+// every index is counted once per instance it is drawn for
+fn cull_views(document: &Document, bounds: &IntRect) -> Result<(), String> {
+    let mut placements = document.mesh_placements();
+    let start = Instant::now();
+    let visibility = Visibility::new(document, &mut placements);
+    print(&format!("index placements: {:.3} ms\n", millis(start)))?;
+
+    let counts: Vec<usize> = placements.iter().map(|p| p.iter().map(|p| p.count()).sum()).collect();
+    let total_instances: usize = counts.iter().sum();
+    let total_indices: usize = document.cells.iter().zip(counts.iter())
+        .map(|(cell, count)| count * cell.layers.iter().map(|l| l.fill_mesh.indices.len()).sum::<usize>())
+        .sum();
+
+    for part in [1, 10, 100] {
+        let view = centered(bounds, part);
+        let start = Instant::now();
+        let draws = visibility.draws(document, &placements, &view);
+        let (mut instances, mut indices) = (0, 0);
+        for (cell, cell_draws) in document.cells.iter().zip(draws.cells.iter()) {
+            for draw in cell_draws.iter() {
+                let count: usize = draw.instances.iter().map(|r| r.len()).sum();
+                instances += count;
+                for layer in cell.layers.iter() {
+                    let part = draw.part(layer, &layer.fill_offsets, 0..layer.fill_mesh.indices.len() as u32);
+                    indices += count * part.indices.iter().map(|r| r.len()).sum::<usize>();
+                }
+            }
+        }
+        let cull_time = millis(start);
+
+        print(&format!("view 1/{part} of the side: draws {instances} of {total_instances} instances and {indices} of {total_indices} indices, culled in {cull_time:.3} ms\n"))?;
+    }
+
+    Ok(())
+}
+
+/// One cell with `count` squares on a grid, the layers alternate.
+fn flat_library(count: usize) -> Library {
+    Library {
+        name: "BENCH".to_string(),
+        units: Units::default(),
+        structures: vec![Structure { name: "TOP".to_string(), elements: squares(count) }],
+    }
+}
+
+/// A block of squares placed one by one on a grid in the middle of a large array of it.
+fn hierarchical_library() -> Library {
+    let side = (SINGLE_BLOCKS as f64).sqrt().ceil() as usize;
+    let middle = ARRAY_SIDE as i32 * BLOCK_PITCH / 2 - side as i32 * BLOCK_PITCH / 2;
+    let mut elements: Vec<Element> = (0..SINGLE_BLOCKS)
+        .map(|i| Element::StructRef(StructRef {
+            name: "BLOCK".to_string(),
+            strans: STrans::default(),
+            position: IntPoint::new(
+                middle + (i % side) as i32 * BLOCK_PITCH,
+                middle + (i / side) as i32 * BLOCK_PITCH,
+            ),
+        }))
+        .collect();
+    let side = ARRAY_SIDE as i32 * BLOCK_PITCH;
+    elements.push(Element::ArrayRef(ArrayRef {
+        name: "BLOCK".to_string(),
+        strans: STrans::default(),
+        columns: ARRAY_SIDE,
+        rows: ARRAY_SIDE,
+        position: IntPoint::new(0, 0),
+        column_point: IntPoint::new(side, 0),
+        row_point: IntPoint::new(0, side),
+    }));
+
+    Library {
+        name: "BENCH".to_string(),
+        units: Units::default(),
+        structures: vec![
+            Structure { name: "BLOCK".to_string(), elements: squares(BLOCK_SQUARES) },
+            Structure { name: "TOP".to_string(), elements },
+        ],
+    }
+}
+
+// `count` squares on a square grid, the layers alternate
+fn squares(count: usize) -> Vec<Element> {
+    let columns = (count as f64).sqrt().ceil() as usize;
+    (0..count)
+        .map(|i| {
+            let x = (i % columns) as i32 * PITCH;
+            let y = (i / columns) as i32 * PITCH;
+            let points = vec![
+                IntPoint::new(x, y),
+                IntPoint::new(x, y + SQUARE),
+                IntPoint::new(x + SQUARE, y + SQUARE),
+                IntPoint::new(x + SQUARE, y),
+                IntPoint::new(x, y),
+            ];
+            Element::Boundary(Boundary { layer: 1 + (i % LAYERS as usize) as u16, datatype: 0, points })
+        })
+        .collect()
+}
+
+// a view in the middle of `bounds` with 1/part of its width and height
+fn centered(bounds: &IntRect, part: i32) -> IntRect {
+    let (cx, cy) = (bounds.min_x / 2 + bounds.max_x / 2, bounds.min_y / 2 + bounds.max_y / 2);
+    let (w, h) = ((bounds.max_x - bounds.min_x) / (2 * part), (bounds.max_y - bounds.min_y) / (2 * part));
+    IntRect { min_x: cx - w, max_x: cx + w, min_y: cy - h, max_y: cy + h }
+}
+
+fn millis(start: Instant) -> f64 {
+    1000.0 * start.elapsed().as_secs_f64()
+}
@@ -0,0 +1,199 @@
+use std::fmt::Write;
+use i_triangle::i_overlay::i_float::rect::IntRect;
+use crate::cli::{load_library, print};
+use crate::draw::document::Document;
+use crate::draw::triangulation::PolygonSize;
+use crate::gds::library::{Library, Units};
+
+/// `info <input.gds> [--json] [--lenient]`
+///
+/// Prints library statistics, polygon and vertex counts are taken after the hierarchy is flattened.
+pub(super) fn run(args: &[String]) -> Result<(), String> {
+    let mut paths = Vec::new();
+    let mut json = false;
+    let mut lenient = false;
+
+    for arg in args.iter() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--lenient" => lenient = true,
+            _ => paths.push(arg.as_str()),
+        }
+    }
+
+    let [input] = paths[..] else {
+        return Err("usage: info <input.gds> [--json] [--lenient]".to_string());
+    };
+
+    let library = load_library(input, lenient)?;
+    let info = LayoutInfo::new(&library);
+    if json {
+        print(&format!("{}\n", info.to_json()))
+    } else {
+        print(&info.to_text())
+    }
+}
+
+struct LayerInfo {
+    layer: u16,
+    datatype: u16,
+    polygons: usize,
+    vertices: usize,
+}
+
+struct LayoutInfo {
+    name: String,
+    units: Units,
+    cells: usize,
+    tops: Vec<String>,
+    depth: usize,
+    bounds: Option<IntRect>,
+    layers: Vec<LayerInfo>,
+}
+
+impl LayoutInfo {
+    fn new(library: &Library) -> Self {
+        // the same shapes the viewer draws
+        let document = Document::with_library(library);
+
+        let mut layers: Vec<LayerInfo> = document.layers.iter()
+            .map(|l| LayerInfo { layer: l.layer, datatype: l.datatype, polygons: 0, vertices: 0 })
+            .collect();
+
+        for (cell, placements) in document.cells.iter().zip(document.placements().iter()) {
+            let count: usize = placements.iter().map(|p| p.count()).sum();
+            for cell_layer in cell.layers.iter() {
+                let info = &mut layers[cell_layer.layer];
+                info.polygons += count * cell_layer.shapes.len();
+                info.vertices += count * cell_layer.shapes.vertices_count();
+            }
+        }
+
+        let mut depths = vec![0; document.cells.len()];
+        let depth = document.tops.iter().map(|&top| cell_depth(&document, top, &mut depths)).max().unwrap_or(0);
+
+        Self {
+            name: library.name.clone(),
+            units: library.units,
+            cells: document.cells.len(),
+            tops: document.tops.iter().map(|&i| document.cells[i].name.clone()).collect(),
+            depth,
+            bounds: document.bounds(),
+            layers,
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "library: {}", self.name);
+        let _ = writeln!(out, "units: {:e} user, {:e} m per database unit", self.units.user, self.units.meter);
+        let _ = writeln!(out, "cells: {}", self.cells);
+        let _ = writeln!(out, "top cells: {}", self.tops.join(", "));
+        let _ = writeln!(out, "depth: {}", self.depth);
+        match &self.bounds {
+            Some(r) => {
+                let _ = writeln!(out, "bounding box: ({}, {}) - ({}, {})", r.min_x, r.min_y, r.max_x, r.max_y);
+            }
+            None => {
+                let _ = writeln!(out, "bounding box: empty");
+            }
+        }
+        let _ = writeln!(out, "layers:");
+        for l in self.layers.iter() {
+            let _ = writeln!(out, "  {}/{}: {} polygons, {} vertices", l.layer, l.datatype, l.polygons, l.vertices);
+        }
+        out
+    }
+
+    fn to_json(&self) -> String {
+        let tops: Vec<String> = self.tops.iter().map(|name| json_string(name)).collect();
+        let bounds = match &self.bounds {
+            Some(r) => format!("[{}, {}, {}, {}]", r.min_x, r.min_y, r.max_x, r.max_y),
+            None => "null".to_string(),
+        };
+        let layers: Vec<String> = self.layers.iter()
+            .map(|l| format!(
+                "{{\"layer\": {}, \"datatype\": {}, \"polygons\": {}, \"vertices\": {}}}",
+                l.layer, l.datatype, l.polygons, l.vertices
+            ))
+            .collect();
+
+        format!(
+            "{{\"library\": {}, \"units\": {{\"user\": {:e}, \"meter\": {:e}}}, \"cells\": {}, \"top_cells\": [{}], \"depth\": {}, \"bounding_box\": {}, \"layers\": [{}]}}",
+            json_string(&self.name),
+            self.units.user,
+            self.units.meter,
+            self.cells,
+            tops.join(", "),
+            self.depth,
+            bounds,
+            layers.join(", "),
+        )
+    }
+}
+
+// a leaf cell has depth 1, cycles are already removed by the document
+fn cell_depth(document: &Document, index: usize, depths: &mut [usize]) -> usize {
+    if depths[index] == 0 {
+        let children = document.cells[index].instances.iter()
+            .map(|instance| cell_depth(document, instance.cell, depths))
+            .max()
+            .unwrap_or(0);
+        depths[index] = children + 1;
+    }
+    depths[index]
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::info::{json_string, LayoutInfo};
+    use crate::gds::reader::tests::{read_strict, sample_stream};
+
+    #[test]
+    fn test_sample_info() {
+        let library = read_strict(&sample_stream()).unwrap();
+        let info = LayoutInfo::new(&library);
+
+        assert_eq!(info.cells, 2);
+        assert_eq!(info.tops, vec!["TOP".to_string()]);
+        assert_eq!(info.depth, 2);
+
+        let text = info.to_text();
+        assert!(text.contains("bounding box: (-2, 0) - (100, 130)"));
+        // one SREF and a 3x2 AREF of the square, the path is a single rectangle
+        assert!(text.contains("  1/0: 7 polygons, 28 vertices\n"));
+        assert!(text.contains("  2/5: 1 polygons, 4 vertices\n"));
+
+        assert_eq!(
+            info.to_json(),
+            "{\"library\": \"LIB\", \"units\": {\"user\": 1e-3, \"meter\": 1e-9}, \"cells\": 2, \"top_cells\": [\"TOP\"], \"depth\": 2, \
+            \"bounding_box\": [-2, 0, 100, 130], \"layers\": [{\"layer\": 1, \"datatype\": 0, \"polygons\": 7, \"vertices\": 28}, \
+            {\"layer\": 2, \"datatype\": 5, \"polygons\": 1, \"vertices\": 4}]}"
+        );
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+    }
+}
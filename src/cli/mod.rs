@@ -0,0 +1,96 @@
+use std::io::{ErrorKind, Write};
+use log::warn;
+use crate::gds::library::Library;
+use crate::gds::reader::{read_library, ReadMode};
+use crate::lyp::properties::{read_layer_properties, LayerPropertiesFile};
+
+mod bench;
+mod info;
+mod render;
+mod write;
+
+/// Runs a headless subcommand. Returns None if `args` do not start with a known command,
+/// the caller opens the viewer then.
+pub fn run(args: &[String]) -> Option<Result<(), String>> {
+    let (command, args) = args.split_first()?;
+    match command.as_str() {
+        "bench" => Some(bench::run(args)),
+        "info" => Some(info::run(args)),
+        "render" => Some(render::run(args)),
+        "write" => Some(write::run(args)),
+        _ => None,
+    }
+}
+
+/// Writes the text to stdout, a reader that stops early like `head` is not an error.
+fn print(text: &str) -> Result<(), String> {
+    write_output(&mut std::io::stdout().lock(), text)
+}
+
+fn write_output(out: &mut impl Write, text: &str) -> Result<(), String> {
+    let written = write!(out, "{text}").and_then(|_| out.flush());
+    match written {
+        Err(err) if err.kind() != ErrorKind::BrokenPipe => Err(format!("can not write the output: {err}")),
+        _ => Ok(()),
+    }
+}
+
+fn load_library(path: &str, lenient: bool) -> Result<Library, String> {
+    let data = std::fs::read(path).map_err(|err| format!("can not read {path}: {err}"))?;
+    let mode = if lenient { ReadMode::Lenient } else { ReadMode::Strict };
+    let (library, warnings) = read_library(&data, mode).map_err(|err| format!("can not load {path}: {err}"))?;
+    for warning in warnings.iter() {
+        warn!("skipped: {warning}");
+    }
+
+    Ok(library)
+}
+
+fn load_layer_properties(path: &str) -> Result<LayerPropertiesFile, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("can not read {path}: {err}"))?;
+    read_layer_properties(&text).map_err(|err| format!("can not load {path}: {err}"))
+}
+
+/// Parses a "layer/datatype" pair.
+fn parse_layer(value: &str) -> Result<(u16, u16), String> {
+    let (layer, datatype) = value.split_once('/').ok_or_else(|| format!("expected layer/datatype, got {value}"))?;
+    let layer = layer.trim().parse().map_err(|_| format!("bad layer {layer}"))?;
+    let datatype = datatype.trim().parse().map_err(|_| format!("bad datatype {datatype}"))?;
+
+    Ok((layer, datatype))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Error, ErrorKind, Write};
+    use crate::cli::write_output;
+
+    // accepts `room` bytes and then fails with `kind`
+    struct Pipe {
+        room: usize,
+        kind: ErrorKind,
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.room == 0 {
+                return Err(Error::from(self.kind));
+            }
+            let n = buf.len().min(self.room);
+            self.room -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_closed_pipe() {
+        let text = "line\n".repeat(100);
+        assert_eq!(write_output(&mut Pipe { room: 1000, kind: ErrorKind::BrokenPipe }, &text), Ok(()));
+        assert_eq!(write_output(&mut Pipe { room: 10, kind: ErrorKind::BrokenPipe }, &text), Ok(()));
+        assert!(write_output(&mut Pipe { room: 10, kind: ErrorKind::PermissionDenied }, &text).is_err());
+    }
+}
\ No newline at end of file
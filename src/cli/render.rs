@@ -0,0 +1,90 @@
+use crate::cli::{load_layer_properties, load_library};
+use crate::draw::document::Document;
+use crate::draw::png::encode_rgba;
+use crate::draw::raster::render;
+use crate::geometry::point::Point;
+use crate::geometry::rect::Rect;
+use crate::lyp::properties::apply_layer_properties;
+
+/// `render <input.gds> <output.png> [--size WxH] [--view x0,y0,x1,y1 | --cell NAME] [--lyp layers.lyp] [--lenient]`
+///
+/// Rasterizes the layout on the CPU, no window or GPU is needed. The view is given in
+/// database units or as the first placement of a cell, the whole layout is shown by default.
+pub(super) fn run(args: &[String]) -> Result<(), String> {
+    let mut paths = Vec::new();
+    let mut size = (1024, 768);
+    let mut view = None;
+    let mut cell = None;
+    let mut lyp = None;
+    let mut lenient = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--lenient" => lenient = true,
+            "--size" => {
+                let value = iter.next().ok_or("--size needs a value")?;
+                size = parse_size(value)?;
+            }
+            "--view" => {
+                let value = iter.next().ok_or("--view needs a value")?;
+                view = Some(parse_view(value)?);
+            }
+            "--cell" => {
+                cell = Some(iter.next().ok_or("--cell needs a value")?);
+            }
+            "--lyp" => {
+                lyp = Some(iter.next().ok_or("--lyp needs a value")?);
+            }
+            _ => paths.push(arg.as_str()),
+        }
+    }
+
+    let [input, output] = paths[..] else {
+        return Err("usage: render <input.gds> <output.png> [--size WxH] [--view x0,y0,x1,y1 | --cell NAME] [--lyp layers.lyp] [--lenient]".to_string());
+    };
+
+    let library = load_library(input, lenient)?;
+    let mut document = Document::with_library(&library);
+    if let Some(path) = lyp {
+        apply_layer_properties(&mut document.layers, &load_layer_properties(path)?);
+    }
+    if let Some(name) = cell {
+        let rect = document.cell_index(name).and_then(|index| document.cell_view(index));
+        view = Some(rect.ok_or(format!("cell {name} is not found or empty"))?);
+    }
+    let view = view.unwrap_or(document.rect);
+
+    let canvas = render(&document, &view, size.0, size.1);
+    let png = encode_rgba(canvas.width, canvas.height, &canvas.pixels);
+    std::fs::write(output, png).map_err(|err| format!("can not write {output}: {err}"))
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let (width, height) = value.split_once('x').ok_or_else(|| format!("expected WxH, got {value}"))?;
+    let width: u32 = width.trim().parse().map_err(|_| format!("bad width {width}"))?;
+    let height: u32 = height.trim().parse().map_err(|_| format!("bad height {height}"))?;
+    if width == 0 || height == 0 || width > 16_384 || height > 16_384 {
+        return Err(format!("size {value} is out of range"));
+    }
+
+    Ok((width, height))
+}
+
+fn parse_view(value: &str) -> Result<Rect, String> {
+    let values = value.split(',')
+        .map(|v| v.trim().parse::<f64>().map_err(|_| format!("bad coordinate {v}")))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let [x0, y0, x1, y1] = values[..] else {
+        return Err(format!("expected x0,y0,x1,y1, got {value}"));
+    };
+    if x0 == x1 || y0 == y1 {
+        return Err(format!("view {value} is empty"));
+    }
+
+    Ok(Rect::new(
+        Point { x: x0.min(x1), y: y0.min(y1) },
+        Point { x: x0.max(x1), y: y0.max(y1) },
+    ))
+}
@@ -0,0 +1,36 @@
+use crate::cli::{load_library, parse_layer};
+use crate::gds::writer::write_library;
+
+/// `write <input.gds> <output.gds> [--layers L/D,L/D...] [--lenient]`
+///
+/// Reads a library and writes it back, optionally keeping only the listed layers.
+pub(super) fn run(args: &[String]) -> Result<(), String> {
+    let mut paths = Vec::new();
+    let mut layers = None;
+    let mut lenient = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--lenient" => lenient = true,
+            "--layers" => {
+                let value = iter.next().ok_or("--layers needs a value")?;
+                let list = value.split(',').map(parse_layer).collect::<Result<Vec<_>, _>>()?;
+                layers = Some(list);
+            }
+            _ => paths.push(arg.as_str()),
+        }
+    }
+
+    let [input, output] = paths[..] else {
+        return Err("usage: write <input.gds> <output.gds> [--layers L/D,...] [--lenient]".to_string());
+    };
+
+    let mut library = load_library(input, lenient)?;
+    if let Some(layers) = layers {
+        library.retain_layers(&layers);
+    }
+
+    let data = write_library(&library).map_err(|err| format!("can not write {output}: {err}"))?;
+    std::fs::write(output, data).map_err(|err| format!("can not write {output}: {err}"))
+}
@@ -0,0 +1,61 @@
+/// Time in seconds from some fixed moment, tests use a clock they step by hand.
+/// The painter that keeps a clock lives behind a mutex, so it must be `Send`.
+pub(crate) trait Clock: Send {
+    fn now(&self) -> f64;
+}
+
+/// Seconds since the clock was made. `std::time::Instant` is not available in the browser,
+/// the page clock is used there.
+pub(crate) struct SystemClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+}
+
+impl SystemClock {
+    pub(crate) fn new() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> f64 {
+        #[cfg(target_arch = "wasm32")] {
+            web_sys::window()
+                .and_then(|window| window.performance())
+                .map_or(0.0, |performance| 0.001 * performance.now())
+        }
+
+        #[cfg(not(target_arch = "wasm32"))] {
+            self.start.elapsed().as_secs_f64()
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::{Arc, Mutex};
+    use crate::control::clock::Clock;
+
+    /// A clock that shows the time it was set to, its clones share the time.
+    #[derive(Clone)]
+    pub(crate) struct TestClock(Arc<Mutex<f64>>);
+
+    impl TestClock {
+        pub(crate) fn new() -> Self {
+            Self(Arc::new(Mutex::new(0.0)))
+        }
+
+        pub(crate) fn set(&self, time: f64) {
+            *self.0.lock().unwrap() = time;
+        }
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> f64 {
+            *self.0.lock().unwrap()
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use winit::event::{ElementState, KeyEvent};
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
+use crate::control::layers::LayerCommand;
+use crate::control::view::ViewCommand;
+
+/// What a key does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum KeyAction {
+    /// moves the view by parts of the screen size, x to the right and y up
+    Pan { x: f64, y: f64 },
+    /// magnification around the screen center, above 1 zooms in
+    Zoom(f64),
+    View(ViewCommand),
+    Layer(LayerCommand),
+}
+
+impl KeyAction {
+    /// A held key repeats the steps, the commands run once per press.
+    pub(crate) fn repeats(&self) -> bool {
+        matches!(self, KeyAction::Pan { .. } | KeyAction::Zoom(_))
+    }
+}
+
+/// A key and the modifiers held with it, other modifiers must be up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeyChord {
+    pub(crate) code: KeyCode,
+    pub(crate) modifiers: ModifiersState,
+}
+
+impl KeyChord {
+    pub(crate) const fn new(code: KeyCode, modifiers: ModifiersState) -> Self {
+        Self { code, modifiers }
+    }
+}
+
+const NONE: ModifiersState = ModifiersState::empty();
+const SHIFT: ModifiersState = ModifiersState::SHIFT;
+const CONTROL: ModifiersState = ModifiersState::CONTROL;
+const ALT: ModifiersState = ModifiersState::ALT;
+
+// a step moves a tenth of the screen, with Shift a half
+const PAN_STEP: f64 = 0.1;
+const LARGE_PAN_STEP: f64 = 0.5;
+const ZOOM_STEP: f64 = 1.25;
+
+const DEFAULT_BINDINGS: [(KeyChord, KeyAction); 25] = [
+    (KeyChord::new(KeyCode::ArrowLeft, NONE), KeyAction::Pan { x: -PAN_STEP, y: 0.0 }),
+    (KeyChord::new(KeyCode::ArrowRight, NONE), KeyAction::Pan { x: PAN_STEP, y: 0.0 }),
+    (KeyChord::new(KeyCode::ArrowUp, NONE), KeyAction::Pan { x: 0.0, y: PAN_STEP }),
+    (KeyChord::new(KeyCode::ArrowDown, NONE), KeyAction::Pan { x: 0.0, y: -PAN_STEP }),
+    (KeyChord::new(KeyCode::ArrowLeft, SHIFT), KeyAction::Pan { x: -LARGE_PAN_STEP, y: 0.0 }),
+    (KeyChord::new(KeyCode::ArrowRight, SHIFT), KeyAction::Pan { x: LARGE_PAN_STEP, y: 0.0 }),
+    (KeyChord::new(KeyCode::ArrowUp, SHIFT), KeyAction::Pan { x: 0.0, y: LARGE_PAN_STEP }),
+    (KeyChord::new(KeyCode::ArrowDown, SHIFT), KeyAction::Pan { x: 0.0, y: -LARGE_PAN_STEP }),
+    // `+` is Shift + `=` on most layouts
+    (KeyChord::new(KeyCode::Equal, NONE), KeyAction::Zoom(ZOOM_STEP)),
+    (KeyChord::new(KeyCode::Equal, SHIFT), KeyAction::Zoom(ZOOM_STEP)),
+    (KeyChord::new(KeyCode::NumpadAdd, NONE), KeyAction::Zoom(ZOOM_STEP)),
+    (KeyChord::new(KeyCode::Minus, NONE), KeyAction::Zoom(1.0 / ZOOM_STEP)),
+    (KeyChord::new(KeyCode::Minus, SHIFT), KeyAction::Zoom(1.0 / ZOOM_STEP)),
+    (KeyChord::new(KeyCode::NumpadSubtract, NONE), KeyAction::Zoom(1.0 / ZOOM_STEP)),
+    (KeyChord::new(KeyCode::Home, NONE), KeyAction::View(ViewCommand::Fit)),
+    (KeyChord::new(KeyCode::KeyF, NONE), KeyAction::View(ViewCommand::Fit)),
+    (KeyChord::new(KeyCode::KeyZ, NONE), KeyAction::View(ViewCommand::ZoomBox)),
+    (KeyChord::new(KeyCode::Digit0, CONTROL), KeyAction::View(ViewCommand::PixelsPerUnit(1.0))),
+    (KeyChord::new(KeyCode::KeyR, NONE), KeyAction::View(ViewCommand::RotateLeft)),
+    (KeyChord::new(KeyCode::KeyR, SHIFT), KeyAction::View(ViewCommand::RotateRight)),
+    (KeyChord::new(KeyCode::KeyM, NONE), KeyAction::View(ViewCommand::Flip)),
+    (KeyChord::new(KeyCode::ArrowLeft, ALT), KeyAction::View(ViewCommand::Back)),
+    (KeyChord::new(KeyCode::ArrowRight, ALT), KeyAction::View(ViewCommand::Forward)),
+    (KeyChord::new(KeyCode::KeyB, CONTROL), KeyAction::View(ViewCommand::AddBookmark)),
+    (KeyChord::new(KeyCode::KeyA, NONE), KeyAction::Layer(LayerCommand::ShowAll)),
+];
+
+// the first ten layers and the first nine bookmarks, in the order of the keyboard row
+const DIGITS: [KeyCode; 10] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+];
+
+/// Key bindings. By default arrows pan, Shift + arrows pan farther, `+` and `-` zoom,
+/// Home and F fit the document, Z arms a zoom box, Ctrl + 0 zooms to 1:1, R turns the view
+/// left, Shift + R right and M mirrors it, Alt + Left and Alt + Right go back and forward
+/// in the view history and Ctrl + B adds a bookmark. Digits 1..9 and 0 toggle the first
+/// ten layers, Shift + digit isolates a layer, Alt + digit toggles its selection and
+/// A shows all layers again, Ctrl + 1..9 show the first nine bookmarks.
+pub(crate) struct Keymap {
+    bindings: Vec<(KeyChord, KeyAction)>,
+}
+
+impl Keymap {
+    pub(crate) fn new(bindings: Vec<(KeyChord, KeyAction)>) -> Self {
+        Self { bindings }
+    }
+
+    /// The first binding of the chord wins.
+    pub(crate) fn action(&self, chord: KeyChord) -> Option<KeyAction> {
+        self.bindings.iter().find(|(c, _)| *c == chord).map(|(_, a)| *a)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut bindings = DEFAULT_BINDINGS.to_vec();
+        for (index, code) in DIGITS.into_iter().enumerate() {
+            bindings.push((KeyChord::new(code, NONE), KeyAction::Layer(LayerCommand::Toggle(index))));
+            bindings.push((KeyChord::new(code, SHIFT), KeyAction::Layer(LayerCommand::Isolate(index))));
+            bindings.push((KeyChord::new(code, ALT), KeyAction::Layer(LayerCommand::ToggleSelectable(index))));
+            // Ctrl + 0 is taken by the 1:1 zoom
+            if code != KeyCode::Digit0 {
+                bindings.push((KeyChord::new(code, CONTROL), KeyAction::View(ViewCommand::ShowBookmark(index))));
+            }
+        }
+        Self::new(bindings)
+    }
+}
+
+/// Turns the key presses into actions through a keymap.
+pub(crate) struct KeyControl {
+    keymap: Keymap,
+    modifiers: ModifiersState,
+}
+
+impl KeyControl {
+    pub(crate) fn new(keymap: Keymap) -> Self {
+        Self { keymap, modifiers: ModifiersState::empty() }
+    }
+
+    pub(crate) fn update_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    pub(crate) fn process_key(&self, event: &KeyEvent) -> Option<KeyAction> {
+        if event.state != ElementState::Pressed {
+            return None;
+        }
+        let PhysicalKey::Code(code) = event.physical_key else {
+            return None;
+        };
+        let action = self.keymap.action(KeyChord::new(code, self.modifiers))?;
+        if event.repeat && !action.repeats() {
+            return None;
+        }
+        Some(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::{KeyCode, ModifiersState};
+    use crate::control::keymap::{KeyAction, KeyChord, Keymap};
+    use crate::control::layers::LayerCommand;
+    use crate::control::view::ViewCommand;
+
+    #[test]
+    fn test_keymap() {
+        let keymap = Keymap::default();
+        let action = |code: KeyCode, modifiers: ModifiersState| keymap.action(KeyChord::new(code, modifiers));
+        let none = ModifiersState::empty();
+
+        assert_eq!(action(KeyCode::ArrowRight, none), Some(KeyAction::Pan { x: 0.1, y: 0.0 }));
+        assert_eq!(action(KeyCode::ArrowDown, ModifiersState::SHIFT), Some(KeyAction::Pan { x: 0.0, y: -0.5 }));
+        assert_eq!(action(KeyCode::Equal, ModifiersState::SHIFT), Some(KeyAction::Zoom(1.25)));
+        assert_eq!(action(KeyCode::Minus, none), Some(KeyAction::Zoom(0.8)));
+        assert_eq!(action(KeyCode::Home, none), Some(KeyAction::View(ViewCommand::Fit)));
+        assert_eq!(action(KeyCode::KeyF, none), Some(KeyAction::View(ViewCommand::Fit)));
+        assert!(action(KeyCode::ArrowUp, none).unwrap().repeats());
+        assert!(!action(KeyCode::Home, none).unwrap().repeats());
+
+        // the view commands
+        assert_eq!(action(KeyCode::KeyZ, none), Some(KeyAction::View(ViewCommand::ZoomBox)));
+        assert_eq!(action(KeyCode::Digit0, ModifiersState::CONTROL), Some(KeyAction::View(ViewCommand::PixelsPerUnit(1.0))));
+        assert_eq!(action(KeyCode::KeyR, none), Some(KeyAction::View(ViewCommand::RotateLeft)));
+        assert_eq!(action(KeyCode::KeyR, ModifiersState::SHIFT), Some(KeyAction::View(ViewCommand::RotateRight)));
+        assert_eq!(action(KeyCode::KeyM, none), Some(KeyAction::View(ViewCommand::Flip)));
+        assert_eq!(action(KeyCode::ArrowLeft, ModifiersState::ALT), Some(KeyAction::View(ViewCommand::Back)));
+        assert_eq!(action(KeyCode::ArrowRight, ModifiersState::ALT), Some(KeyAction::View(ViewCommand::Forward)));
+        assert_eq!(action(KeyCode::KeyB, ModifiersState::CONTROL), Some(KeyAction::View(ViewCommand::AddBookmark)));
+        assert_eq!(action(KeyCode::Digit3, ModifiersState::CONTROL), Some(KeyAction::View(ViewCommand::ShowBookmark(2))));
+
+        // the layer commands
+        assert_eq!(action(KeyCode::Digit1, none), Some(KeyAction::Layer(LayerCommand::Toggle(0))));
+        assert_eq!(action(KeyCode::Digit0, none), Some(KeyAction::Layer(LayerCommand::Toggle(9))));
+        assert_eq!(action(KeyCode::Digit3, ModifiersState::SHIFT), Some(KeyAction::Layer(LayerCommand::Isolate(2))));
+        assert_eq!(action(KeyCode::Digit3, ModifiersState::ALT), Some(KeyAction::Layer(LayerCommand::ToggleSelectable(2))));
+        assert_eq!(action(KeyCode::KeyA, none), Some(KeyAction::Layer(LayerCommand::ShowAll)));
+
+        // system shortcuts and other modifiers are left alone
+        assert_eq!(action(KeyCode::KeyA, ModifiersState::CONTROL), None);
+        assert_eq!(action(KeyCode::Digit1, ModifiersState::SUPER), None);
+        assert_eq!(action(KeyCode::KeyF, ModifiersState::CONTROL), None);
+        assert_eq!(action(KeyCode::KeyZ, ModifiersState::SHIFT), None);
+        assert_eq!(action(KeyCode::KeyQ, none), None);
+
+        // a custom table replaces the default one
+        let keymap = Keymap::new(vec![
+            (KeyChord::new(KeyCode::KeyD, none), KeyAction::Pan { x: 1.0, y: 0.0 }),
+            (KeyChord::new(KeyCode::KeyD, none), KeyAction::View(ViewCommand::Fit)),
+        ]);
+        assert_eq!(keymap.action(KeyChord::new(KeyCode::KeyD, none)), Some(KeyAction::Pan { x: 1.0, y: 0.0 }));
+        assert_eq!(keymap.action(KeyChord::new(KeyCode::ArrowLeft, none)), None);
+    }
+}
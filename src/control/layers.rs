@@ -0,0 +1,7 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LayerCommand {
+    Toggle(usize),
+    Isolate(usize),
+    ShowAll,
+    ToggleSelectable(usize),
+}
@@ -1,2 +1,7 @@
+pub(crate) mod clock;
+pub(crate) mod keymap;
 pub(crate) mod scroll;
-pub(crate) mod navigation;
\ No newline at end of file
+pub(crate) mod touch;
+pub(crate) mod navigation;
+pub(crate) mod layers;
+pub(crate) mod view;
\ No newline at end of file
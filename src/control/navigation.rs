@@ -1,10 +1,13 @@
 use winit::event::{ElementState, MouseButton, WindowEvent};
+use crate::control::clock::{Clock, SystemClock};
+use crate::control::keymap::KeyAction;
 use crate::control::scroll::ScrollControl;
+use crate::control::touch::TouchControl;
 use crate::geometry::point::Point;
 use crate::geometry::size::Size;
 
 pub(crate) struct ZoomEvent {
-    pub(crate) scale: f32,
+    pub(crate) scale: f64,
     pub(crate) cursor: Point,
 }
 
@@ -21,14 +24,33 @@ pub(crate) enum NavigationEvent {
     StartDragged(DraggedEvent),
     EndDragged(DraggedEvent),
     MoveDragged(DraggedEvent),
+    /// the zoom box is dragged between the two screen points
+    MoveZoomBox(DraggedEvent),
+    /// the zoom box was let go too small, it only disappears
+    CancelZoomBox,
+    /// the screen rectangle between the two points is zoomed to fill the view, the box disappears
+    ZoomRect(DraggedEvent),
+    /// a single step, the view moves by the screen vector
+    Pan(Point),
+    /// a single step, the zoom is multiplied by the scale
+    ZoomStep(ZoomEvent),
 }
 
+// a shorter drag is taken for a click and does not zoom
+const MIN_ZOOM_BOX: f64 = 4.0;
+
 pub(crate) struct NavigationControl {
     size: Size,
     cursor: Point,
     scroll_control: ScrollControl,
+    touch_control: TouchControl,
+    clock: SystemClock,
     start_dragged: Point,
     is_left_mouse_pressed: bool,
+    // the button that drags a zoom box and its first corner
+    zoom_box: Option<(MouseButton, Point)>,
+    // the next left drag draws a zoom box
+    is_zoom_box_armed: bool,
 }
 
 impl NavigationControl {
@@ -37,11 +59,19 @@ impl NavigationControl {
             size: Size { width: 1.0, height: 1.0 },
             cursor: Point { x: 0.0, y: 0.0 },
             scroll_control: ScrollControl::new(),
+            touch_control: TouchControl::new(),
+            clock: SystemClock::new(),
             start_dragged: Point { x: 0.0, y: 0.0 },
             is_left_mouse_pressed: false,
+            zoom_box: None,
+            is_zoom_box_armed: false,
         }
     }
 
+    pub(crate) fn arm_zoom_box(&mut self) {
+        self.is_zoom_box_armed = true;
+    }
+
     pub(crate) fn update_size(&mut self, size: Size) {
         self.size = size;
         self.cursor = Point { x: 0.5 * size.width, y: 0.5 * size.height };
@@ -55,33 +85,156 @@ impl NavigationControl {
                 Some(self.scroll_control.on_scroll(delta, phase, self.cursor))
             }
             WindowEvent::CursorMoved { device_id: _, position } => {
-                self.cursor = Point { x: position.x as f32, y: position.y as f32 };
-                if self.is_left_mouse_pressed {
+                self.cursor = Point { x: position.x, y: position.y };
+                if let Some((_, start)) = self.zoom_box {
+                    Some(NavigationEvent::MoveZoomBox(DraggedEvent { start, current: self.cursor }))
+                } else if self.is_left_mouse_pressed {
                     Some(NavigationEvent::MoveDragged(DraggedEvent { start: self.start_dragged, current: self.cursor }))
                 } else {
                     None
                 }
             }
-            WindowEvent::MouseInput { device_id: _, state, button } => {
-                if button != MouseButton::Left {
-                    return None;
-                }
-
-                Some(match state {
-                    ElementState::Pressed => {
-                        self.start_dragged = self.cursor;
-                        self.is_left_mouse_pressed = true;
-                        NavigationEvent::StartDragged(DraggedEvent { start: self.start_dragged, current: self.cursor })
-                    }
-                    ElementState::Released => {
-                        self.is_left_mouse_pressed = false;
-                        NavigationEvent::EndDragged(DraggedEvent { start: self.start_dragged, current: self.cursor })
-                    }
-                })
+            WindowEvent::MouseInput { device_id: _, state, button } => self.on_mouse_input(state, button),
+            WindowEvent::Touch(touch) => {
+                let location = Point { x: touch.location.x, y: touch.location.y };
+                self.touch_control.on_touch(touch.id, touch.phase, location, self.clock.now())
+            }
+            WindowEvent::PinchGesture { device_id: _, delta, phase } => {
+                Some(self.touch_control.on_pinch(delta, phase, self.cursor))
             }
             _ => {
                 None
             }
         }
     }
-}
\ No newline at end of file
+
+    fn on_mouse_input(&mut self, state: ElementState, button: MouseButton) -> Option<NavigationEvent> {
+        // the right button always draws a zoom box, the left one after Z
+        let is_zoom_box = button == MouseButton::Right || (button == MouseButton::Left && self.is_zoom_box_armed);
+        match (state, self.zoom_box) {
+            // a drag in progress does not turn into a box
+            (ElementState::Pressed, None) if is_zoom_box && !self.is_left_mouse_pressed => {
+                self.is_zoom_box_armed = false;
+                self.zoom_box = Some((button, self.cursor));
+                return None;
+            }
+            // only the button that started the box ends it
+            (ElementState::Released, Some((box_button, _))) if box_button == button => {
+                return Some(self.end_zoom_box());
+            }
+            // the other buttons are ignored while the box is open
+            (_, Some(_)) => return None,
+            _ => {}
+        }
+
+        if button != MouseButton::Left {
+            return None;
+        }
+
+        match state {
+            ElementState::Pressed => {
+                self.start_dragged = self.cursor;
+                self.is_left_mouse_pressed = true;
+                Some(NavigationEvent::StartDragged(DraggedEvent { start: self.start_dragged, current: self.cursor }))
+            }
+            // a release whose press went to the box is not a drag
+            ElementState::Released if !self.is_left_mouse_pressed => None,
+            ElementState::Released => {
+                self.is_left_mouse_pressed = false;
+                Some(NavigationEvent::EndDragged(DraggedEvent { start: self.start_dragged, current: self.cursor }))
+            }
+        }
+    }
+
+    /// The step of a pan or zoom key, None for the commands.
+    pub(crate) fn key_event(&self, action: KeyAction) -> Option<NavigationEvent> {
+        match action {
+            // screen y goes down
+            KeyAction::Pan { x, y } => Some(NavigationEvent::Pan(Point { x: x * self.size.width, y: -y * self.size.height })),
+            KeyAction::Zoom(magnification) => {
+                let cursor = Point { x: 0.5 * self.size.width, y: 0.5 * self.size.height };
+                Some(NavigationEvent::ZoomStep(ZoomEvent { scale: 1.0 / magnification, cursor }))
+            }
+            KeyAction::View(_) | KeyAction::Layer(_) => None,
+        }
+    }
+
+    fn end_zoom_box(&mut self) -> NavigationEvent {
+        let Some((_, start)) = self.zoom_box.take() else {
+            return NavigationEvent::CancelZoomBox;
+        };
+        let current = self.cursor;
+        if (current.x - start.x).abs() < MIN_ZOOM_BOX && (current.y - start.y).abs() < MIN_ZOOM_BOX {
+            return NavigationEvent::CancelZoomBox;
+        }
+        NavigationEvent::ZoomRect(DraggedEvent { start, current })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::dpi::PhysicalPosition;
+    use winit::event::{DeviceId, ElementState, MouseButton, WindowEvent};
+    use crate::control::navigation::{NavigationControl, NavigationEvent};
+    use crate::geometry::point::Point;
+
+    // names the event of a mouse button after moving the cursor
+    fn click(control: &mut NavigationControl, state: ElementState, button: MouseButton, x: f64, y: f64) -> &'static str {
+        control.cursor = Point { x, y };
+        match control.on_mouse_input(state, button) {
+            None => "none",
+            Some(NavigationEvent::StartDragged(_)) => "start drag",
+            Some(NavigationEvent::EndDragged(_)) => "end drag",
+            Some(NavigationEvent::ZoomRect(_)) => "zoom rect",
+            Some(NavigationEvent::CancelZoomBox) => "cancel box",
+            Some(_) => "other",
+        }
+    }
+
+    // names the event of moving the cursor
+    fn move_to(control: &mut NavigationControl, x: f64, y: f64) -> &'static str {
+        let position = PhysicalPosition::new(x, y);
+        match control.process_event(WindowEvent::CursorMoved { device_id: DeviceId::dummy(), position }) {
+            None => "none",
+            Some(NavigationEvent::MoveDragged(_)) => "move drag",
+            Some(NavigationEvent::MoveZoomBox(_)) => "move box",
+            Some(_) => "other",
+        }
+    }
+
+    #[test]
+    fn test_zoom_box_buttons() {
+        use ElementState::{Pressed, Released};
+        use MouseButton::{Left, Right};
+        let mut control = NavigationControl::new();
+
+        // a left click inside a right button box is ignored
+        assert_eq!(click(&mut control, Pressed, Right, 10.0, 10.0), "none");
+        assert_eq!(click(&mut control, Pressed, Left, 20.0, 20.0), "none");
+        assert_eq!(move_to(&mut control, 30.0, 30.0), "move box");
+        assert_eq!(click(&mut control, Released, Left, 30.0, 30.0), "none");
+        assert_eq!(click(&mut control, Released, Right, 50.0, 50.0), "zoom rect");
+        assert_eq!(move_to(&mut control, 60.0, 60.0), "none");
+
+        // the left button held past the box does not drag afterwards
+        assert_eq!(click(&mut control, Pressed, Right, 10.0, 10.0), "none");
+        assert_eq!(click(&mut control, Pressed, Left, 20.0, 20.0), "none");
+        assert_eq!(click(&mut control, Released, Right, 50.0, 50.0), "zoom rect");
+        assert_eq!(move_to(&mut control, 60.0, 60.0), "none");
+        assert_eq!(click(&mut control, Released, Left, 60.0, 60.0), "none");
+
+        // a right click inside a left drag does not open a box
+        assert_eq!(click(&mut control, Pressed, Left, 10.0, 10.0), "start drag");
+        assert_eq!(click(&mut control, Pressed, Right, 20.0, 20.0), "none");
+        assert_eq!(move_to(&mut control, 30.0, 30.0), "move drag");
+        assert_eq!(click(&mut control, Released, Right, 30.0, 30.0), "none");
+        assert_eq!(click(&mut control, Released, Left, 40.0, 40.0), "end drag");
+
+        // a box too small to zoom only disappears
+        control.arm_zoom_box();
+        assert_eq!(click(&mut control, Pressed, Left, 10.0, 10.0), "none");
+        assert_eq!(click(&mut control, Released, Right, 10.0, 10.0), "none");
+        assert_eq!(click(&mut control, Released, Left, 11.0, 12.0), "cancel box");
+        assert_eq!(click(&mut control, Pressed, Left, 10.0, 10.0), "start drag");
+    }
+}
@@ -3,16 +3,25 @@ use crate::control::navigation::{NavigationEvent, ZoomEvent};
 use crate::geometry::point::Point;
 use crate::geometry::size::Size;
 
+// a wheel notch zooms by this factor
+const WHEEL_STEP: f64 = 1.25;
+
+/// Zooms with the wheel and the trackpad. A trackpad scroll comes as a gesture with a start and
+/// an end and zooms smoothly relative to its start. A mouse wheel sends single line deltas without
+/// a start, each notch is a step around the cursor. Pixel deltas without a start, like the browser
+/// sends, are steps of their own size.
 pub(super) struct ScrollControl {
     scroll: Point,
-    sx: f32,
-    sy: f32
+    sx: f64,
+    sy: f64,
+    // a trackpad gesture started and did not end yet
+    is_gesture: bool,
 }
 
 impl ScrollControl {
 
     pub(super) fn new() -> Self {
-        Self { scroll: Point { x: 0.0, y: 0.0 }, sx: 1.0, sy: 1.0 }
+        Self { scroll: Point { x: 0.0, y: 0.0 }, sx: 1.0, sy: 1.0, is_gesture: false }
     }
 
     pub(super) fn update_size(&mut self, size: Size) {
@@ -22,48 +31,119 @@ impl ScrollControl {
 
     pub(super) fn on_scroll(&mut self, delta: MouseScrollDelta, phase: TouchPhase, cursor: Point) -> NavigationEvent {
         // info!("scroll: {:?} ", self.scroll);
+        if let MouseScrollDelta::LineDelta(_, y) = delta {
+            // up zooms in
+            let scale = WHEEL_STEP.powf(-y as f64);
+            return NavigationEvent::ZoomStep(ZoomEvent { scale, cursor });
+        }
+
         match phase {
             TouchPhase::Started => {
+                self.is_gesture = true;
                 self.scroll = Point { x: 0.0, y: 0.0 };
                 self.accumulate(delta);
                 let scale = self.value();
                 NavigationEvent::StartZoom(ZoomEvent { scale, cursor })
             }
-            TouchPhase::Moved => {
+            TouchPhase::Moved if self.is_gesture => {
                 self.accumulate(delta);
                 let scale = self.value();
                 NavigationEvent::ProcessZoom(ZoomEvent { scale, cursor })
             }
-            TouchPhase::Ended => {
+            TouchPhase::Ended if self.is_gesture => {
+                self.is_gesture = false;
                 self.accumulate(delta);
                 let scale = self.value();
                 NavigationEvent::EndZoom(ZoomEvent { scale, cursor })
             }
-            TouchPhase::Cancelled => {
+            TouchPhase::Cancelled if self.is_gesture => {
+                self.is_gesture = false;
                 let scale = self.value();
                 self.scroll = Point { x: 0.0, y: 0.0 };
                 NavigationEvent::CancelZoom(ZoomEvent { scale, cursor })
             }
+            // without a start there is no zoom to be relative to
+            _ => {
+                self.scroll = Point { x: 0.0, y: 0.0 };
+                self.accumulate(delta);
+                let scale = self.value();
+                NavigationEvent::ZoomStep(ZoomEvent { scale, cursor })
+            }
         }
     }
 
     fn accumulate(&mut self, delta: MouseScrollDelta) {
         match delta {
             MouseScrollDelta::LineDelta(x, y) => {
-                let x = self.scroll.x - x;
-                let y = self.scroll.y - y;
+                let x = self.scroll.x - x as f64;
+                let y = self.scroll.y - y as f64;
                 self.scroll = Point { x, y };
             }
             MouseScrollDelta::PixelDelta(data) => {
-                let x = self.scroll.x - data.x as f32;
-                let y = self.scroll.y - data.y as f32;
+                let x = self.scroll.x - data.x;
+                let y = self.scroll.y - data.y;
                 self.scroll = Point { x, y };
             }
         }
     }
 
-    fn value(&self) -> f32 {
+    fn value(&self) -> f64 {
         (1.0 + self.sy * self.scroll.y).max(0.01)
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::dpi::PhysicalPosition;
+    use winit::event::{MouseScrollDelta, TouchPhase};
+    use crate::control::navigation::NavigationEvent;
+    use crate::control::scroll::ScrollControl;
+    use crate::geometry::point::Point;
+    use crate::geometry::size::Size;
+
+    // the event name and its scale
+    fn scroll(control: &mut ScrollControl, delta: MouseScrollDelta, phase: TouchPhase) -> (&'static str, f64) {
+        match control.on_scroll(delta, phase, Point { x: 10.0, y: 20.0 }) {
+            NavigationEvent::StartZoom(e) => ("start", e.scale),
+            NavigationEvent::ProcessZoom(e) => ("process", e.scale),
+            NavigationEvent::EndZoom(e) => ("end", e.scale),
+            NavigationEvent::CancelZoom(e) => ("cancel", e.scale),
+            NavigationEvent::ZoomStep(e) => {
+                assert_eq!(e.cursor, Point { x: 10.0, y: 20.0 });
+                ("step", e.scale)
+            }
+            _ => ("other", 0.0),
+        }
+    }
+
+    #[test]
+    fn test_wheel() {
+        let mut control = ScrollControl::new();
+        control.update_size(Size { width: 400.0, height: 200.0 });
+
+        // every notch is a step of its own, up zooms in
+        let line = |y: f32| MouseScrollDelta::LineDelta(0.0, y);
+        assert_eq!(scroll(&mut control, line(1.0), TouchPhase::Moved), ("step", 0.8));
+        assert_eq!(scroll(&mut control, line(1.0), TouchPhase::Moved), ("step", 0.8));
+        assert_eq!(scroll(&mut control, line(-2.0), TouchPhase::Moved), ("step", 1.5625));
+    }
+
+    #[test]
+    fn test_trackpad() {
+        let mut control = ScrollControl::new();
+        control.update_size(Size { width: 400.0, height: 200.0 });
+        let pixels = |y: f64| MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, y));
+
+        // a gesture zooms relative to its start
+        assert_eq!(scroll(&mut control, pixels(20.0), TouchPhase::Started), ("start", 0.9));
+        assert_eq!(scroll(&mut control, pixels(20.0), TouchPhase::Moved), ("process", 0.8));
+        assert_eq!(scroll(&mut control, pixels(-60.0), TouchPhase::Moved), ("process", 1.1));
+        assert_eq!(scroll(&mut control, pixels(0.0), TouchPhase::Ended), ("end", 1.1));
+
+        // pixels without a gesture are steps of their size
+        assert_eq!(scroll(&mut control, pixels(20.0), TouchPhase::Moved), ("step", 0.9));
+        assert_eq!(scroll(&mut control, pixels(20.0), TouchPhase::Moved), ("step", 0.9));
+        assert_eq!(scroll(&mut control, pixels(0.0), TouchPhase::Ended), ("step", 1.0));
+    }
+}
@@ -0,0 +1,263 @@
+use winit::event::TouchPhase;
+use crate::control::navigation::{DraggedEvent, NavigationEvent, ZoomEvent};
+use crate::geometry::point::Point;
+
+// a touch shorter and closer than this is a tap
+const TAP_TIME: f64 = 0.3;
+const TAP_SLOP: f64 = 20.0;
+// the second tap must follow the first one this fast
+const DOUBLE_TAP_TIME: f64 = 0.3;
+// a double tap zooms in by this factor, dragging the second tap zooms by a factor of 2 per this distance
+const DOUBLE_TAP_ZOOM: f64 = 2.0;
+const TAP_ZOOM_DISTANCE: f64 = 200.0;
+
+enum Gesture {
+    // no gesture, a finger that moves starts a pan
+    None,
+    Pan { start: Point },
+    // the zoom is relative to the first distance of the two fingers
+    Pinch { distance: f64, scale: f64, center: Point },
+    // the second tap of a double tap, dragging it down zooms in and up zooms out
+    TapZoom { start: Point, scale: f64, moved: bool },
+}
+
+/// Turns touches into navigation: one finger pans, two fingers zoom around their centroid
+/// and a double tap zooms in. A double tap that keeps the finger down zooms while it is dragged.
+pub(super) struct TouchControl {
+    // fingers on the screen in the order they came
+    touches: Vec<(u64, Point)>,
+    gesture: Gesture,
+    // where and when the single finger went down
+    down: Option<(f64, Point)>,
+    last_tap: Option<(f64, Point)>,
+    pinch: f64,
+}
+
+impl TouchControl {
+    pub(super) fn new() -> Self {
+        Self { touches: Vec::new(), gesture: Gesture::None, down: None, last_tap: None, pinch: 0.0 }
+    }
+
+    /// `time` is in seconds, it is used only to tell taps apart.
+    pub(super) fn on_touch(&mut self, id: u64, phase: TouchPhase, location: Point, time: f64) -> Option<NavigationEvent> {
+        match phase {
+            TouchPhase::Started => self.on_start(id, location, time),
+            TouchPhase::Moved => self.on_move(id, location),
+            TouchPhase::Ended => self.on_end(id, location, time),
+            TouchPhase::Cancelled => self.on_cancel(id),
+        }
+    }
+
+    /// A trackpad pinch, `delta` is the change of the magnification.
+    pub(super) fn on_pinch(&mut self, delta: f64, phase: TouchPhase, cursor: Point) -> NavigationEvent {
+        if phase == TouchPhase::Started {
+            self.pinch = 0.0;
+        }
+        if delta.is_finite() {
+            self.pinch += delta;
+        }
+        let scale = 1.0 / (1.0 + self.pinch).max(0.01);
+        match phase {
+            TouchPhase::Started => NavigationEvent::StartZoom(ZoomEvent { scale, cursor }),
+            TouchPhase::Moved => NavigationEvent::ProcessZoom(ZoomEvent { scale, cursor }),
+            TouchPhase::Ended => NavigationEvent::EndZoom(ZoomEvent { scale, cursor }),
+            TouchPhase::Cancelled => NavigationEvent::CancelZoom(ZoomEvent { scale: 1.0, cursor }),
+        }
+    }
+
+    fn on_start(&mut self, id: u64, location: Point, time: f64) -> Option<NavigationEvent> {
+        self.touches.retain(|(i, _)| *i != id);
+        self.touches.push((id, location));
+
+        match self.touches.len() {
+            1 => {
+                let is_double_tap = self.last_tap.take()
+                    .is_some_and(|(t, p)| time - t < DOUBLE_TAP_TIME && distance(p, location) < TAP_SLOP);
+                if is_double_tap {
+                    self.down = None;
+                    self.gesture = Gesture::TapZoom { start: location, scale: 1.0, moved: false };
+                    return Some(NavigationEvent::StartZoom(ZoomEvent { scale: 1.0, cursor: location }));
+                }
+                self.down = Some((time, location));
+                self.gesture = Gesture::Pan { start: location };
+                Some(NavigationEvent::StartDragged(DraggedEvent { start: location, current: location }))
+            }
+            2 if !matches!(self.gesture, Gesture::TapZoom { .. }) => {
+                // the pan stops where it is, the pinch takes over
+                self.down = None;
+                let (distance, center) = pair(&self.touches);
+                self.gesture = Gesture::Pinch { distance: distance.max(1.0), scale: 1.0, center };
+                Some(NavigationEvent::StartZoom(ZoomEvent { scale: 1.0, cursor: center }))
+            }
+            _ => None,
+        }
+    }
+
+    fn on_move(&mut self, id: u64, location: Point) -> Option<NavigationEvent> {
+        let touch = self.touches.iter_mut().find(|(i, _)| *i == id)?;
+        touch.1 = location;
+
+        match &mut self.gesture {
+            Gesture::None if self.touches.len() == 1 => {
+                // the finger left after a pinch goes on as a pan
+                self.gesture = Gesture::Pan { start: location };
+                Some(NavigationEvent::StartDragged(DraggedEvent { start: location, current: location }))
+            }
+            Gesture::None => None,
+            Gesture::Pan { start } => Some(NavigationEvent::MoveDragged(DraggedEvent { start: *start, current: location })),
+            Gesture::Pinch { .. } if self.touches.len() < 2 => None,
+            Gesture::Pinch { distance: start_distance, scale, center } => {
+                let (distance, new_center) = pair(&self.touches);
+                *scale = *start_distance / distance.max(1.0);
+                *center = new_center;
+                Some(NavigationEvent::ProcessZoom(ZoomEvent { scale: *scale, cursor: *center }))
+            }
+            Gesture::TapZoom { start, scale, moved } => {
+                *moved |= distance(*start, location) >= TAP_SLOP;
+                *scale = 2f64.powf((start.y - location.y) / TAP_ZOOM_DISTANCE);
+                Some(NavigationEvent::ProcessZoom(ZoomEvent { scale: *scale, cursor: *start }))
+            }
+        }
+    }
+
+    fn on_end(&mut self, id: u64, location: Point, time: f64) -> Option<NavigationEvent> {
+        let count = self.touches.len();
+        self.touches.retain(|(i, _)| *i != id);
+        if self.touches.len() == count {
+            return None;
+        }
+
+        match self.gesture {
+            Gesture::Pan { start } => {
+                if let Some((t, p)) = self.down.take() {
+                    if time - t < TAP_TIME && distance(p, location) < TAP_SLOP {
+                        self.last_tap = Some((time, location));
+                    }
+                }
+                self.gesture = Gesture::None;
+                Some(NavigationEvent::EndDragged(DraggedEvent { start, current: location }))
+            }
+            Gesture::Pinch { scale, center, .. } if self.touches.len() < 2 => {
+                self.gesture = Gesture::None;
+                Some(NavigationEvent::EndZoom(ZoomEvent { scale, cursor: center }))
+            }
+            Gesture::TapZoom { start, scale, moved } if self.touches.is_empty() => {
+                self.gesture = Gesture::None;
+                let scale = if moved { scale } else { 1.0 / DOUBLE_TAP_ZOOM };
+                Some(NavigationEvent::EndZoom(ZoomEvent { scale, cursor: start }))
+            }
+            _ => None,
+        }
+    }
+
+    fn on_cancel(&mut self, id: u64) -> Option<NavigationEvent> {
+        self.touches.retain(|(i, _)| *i != id);
+        self.down = None;
+        self.last_tap = None;
+
+        let event = match self.gesture {
+            Gesture::None => None,
+            Gesture::Pan { start } => Some(NavigationEvent::EndDragged(DraggedEvent { start, current: start })),
+            Gesture::Pinch { center, .. } => Some(NavigationEvent::CancelZoom(ZoomEvent { scale: 1.0, cursor: center })),
+            Gesture::TapZoom { start, .. } => Some(NavigationEvent::CancelZoom(ZoomEvent { scale: 1.0, cursor: start })),
+        };
+        self.gesture = Gesture::None;
+        event
+    }
+}
+
+// distance and centroid of the first two fingers
+fn pair(touches: &[(u64, Point)]) -> (f64, Point) {
+    let (a, b) = (touches[0].1, touches[1].1);
+    (distance(a, b), Point { x: 0.5 * (a.x + b.x), y: 0.5 * (a.y + b.y) })
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+    let d = a - b;
+    (d.x * d.x + d.y * d.y).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::TouchPhase;
+    use crate::control::navigation::NavigationEvent;
+    use crate::control::touch::TouchControl;
+    use crate::geometry::point::Point;
+
+    // replays (id, phase, x, y, time) and names the events that came out
+    fn replay(control: &mut TouchControl, touches: &[(u64, TouchPhase, f64, f64, f64)]) -> Vec<String> {
+        touches.iter()
+            .filter_map(|&(id, phase, x, y, time)| control.on_touch(id, phase, Point { x, y }, time))
+            .map(|event| match event {
+                NavigationEvent::StartDragged(e) => format!("start drag {} {}", e.current.x, e.current.y),
+                NavigationEvent::MoveDragged(e) => format!("drag {} {}", e.current.x - e.start.x, e.current.y - e.start.y),
+                NavigationEvent::EndDragged(_) => "end drag".to_string(),
+                NavigationEvent::StartZoom(e) => format!("start zoom {} at {} {}", e.scale, e.cursor.x, e.cursor.y),
+                NavigationEvent::ProcessZoom(e) => format!("zoom {} at {} {}", e.scale, e.cursor.x, e.cursor.y),
+                NavigationEvent::EndZoom(e) => format!("end zoom {}", e.scale),
+                NavigationEvent::CancelZoom(_) => "cancel zoom".to_string(),
+                _ => "other".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gestures() {
+        use TouchPhase::{Cancelled, Ended, Moved, Started};
+        let mut control = TouchControl::new();
+
+        // one finger pans
+        let events = replay(&mut control, &[
+            (1, Started, 100.0, 100.0, 0.0),
+            (1, Moved, 130.0, 90.0, 0.1),
+            (1, Ended, 150.0, 90.0, 0.5),
+        ]);
+        assert_eq!(events, ["start drag 100 100", "drag 30 -10", "end drag"]);
+
+        // a second finger turns the pan into a pinch around the centroid, the last finger pans again
+        let events = replay(&mut control, &[
+            (1, Started, 100.0, 100.0, 1.0),
+            (2, Started, 200.0, 100.0, 1.1),
+            (2, Moved, 300.0, 100.0, 1.2),
+            (2, Ended, 300.0, 100.0, 1.3),
+            (1, Moved, 110.0, 100.0, 1.4),
+            (1, Moved, 120.0, 100.0, 1.5),
+            (1, Ended, 120.0, 100.0, 1.6),
+        ]);
+        assert_eq!(events, [
+            "start drag 100 100",
+            "start zoom 1 at 150 100",
+            "zoom 0.5 at 200 100",
+            "end zoom 0.5",
+            "start drag 110 100",
+            "drag 10 0",
+            "end drag",
+        ]);
+
+        // a double tap zooms in at the tap, a slow second tap is a new pan
+        let events = replay(&mut control, &[
+            (3, Started, 50.0, 50.0, 2.0),
+            (3, Ended, 52.0, 50.0, 2.1),
+            (4, Started, 55.0, 52.0, 2.3),
+            (4, Ended, 55.0, 52.0, 2.35),
+            (5, Started, 55.0, 52.0, 3.0),
+            (5, Ended, 55.0, 52.0, 3.1),
+        ]);
+        assert_eq!(events, ["start drag 50 50", "end drag", "start zoom 1 at 55 52", "end zoom 0.5", "start drag 55 52", "end drag"]);
+
+        // dragging the second tap down zooms in, a cancel restores the zoom
+        let events = replay(&mut control, &[
+            (6, Started, 55.0, 52.0, 3.2),
+            (6, Moved, 55.0, 252.0, 3.4),
+            (6, Ended, 55.0, 252.0, 3.5),
+            (7, Started, 10.0, 10.0, 5.0),
+            (8, Started, 20.0, 10.0, 5.1),
+            (8, Cancelled, 20.0, 10.0, 5.2),
+        ]);
+        assert_eq!(events, ["start zoom 1 at 55 52", "zoom 0.5 at 55 52", "end zoom 0.5", "start drag 10 10", "start zoom 1 at 15 10", "cancel zoom"]);
+
+        // pinch on a trackpad
+        let event = control.on_pinch(1.0, Moved, Point { x: 0.0, y: 0.0 });
+        assert!(matches!(event, NavigationEvent::ProcessZoom(e) if e.scale == 0.5));
+    }
+}
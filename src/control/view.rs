@@ -0,0 +1,23 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ViewCommand {
+    /// the whole document with a margin
+    Fit,
+    /// one database unit covers the given number of pixels
+    PixelsPerUnit(f64),
+    /// the next left drag zooms to a box instead of panning
+    ZoomBox,
+    /// the picture turns by 90° counterclockwise
+    RotateLeft,
+    RotateRight,
+    /// the picture is mirrored left to right
+    Flip,
+    /// the previous view in the history
+    Back,
+    Forward,
+    /// the view and the layers are bookmarked under a new name
+    AddBookmark,
+    /// the bookmark with the given index, in the order they were added
+    ShowBookmark(usize),
+    /// the first placement of the cell with the given index fills the view
+    ZoomToCell(usize),
+}
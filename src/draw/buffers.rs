@@ -1,60 +1,178 @@
 use std::mem;
-use wgpu::{Buffer, BufferSlice, BufferUsages, Device};
+use std::ops::Range;
+use bytemuck::{Pod, Zeroable};
+use wgpu::{Buffer, BufferUsages, Device};
 use wgpu::util::DeviceExt;
-use crate::eye::camera::OrthoNoRotCamera;
+use crate::draw::index_mesh::ListMesh;
+use crate::draw::instance::Lattice;
+use crate::eye::camera::OrthoCamera;
 
 pub(crate) struct GeometryCommonBuffers {
+    pub(crate) transform: Buffer,
+    /// world units per screen pixel as `PixelSize`
+    pub(crate) pixel_size: Buffer,
+    pub(crate) lattice: Buffer,
+    /// stride of per-layer and per-array uniforms bound with a dynamic offset
+    pub(crate) uniform_stride: usize,
+}
+
+/// The size of a screen pixel in world units.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub(crate) struct PixelSize {
+    pub(crate) size: f32,
+    // uniforms are aligned to 16 bytes
+    _padding: [f32; 3],
+}
+
+/// Meshes of all cells on a single layer packed into one vertex and one index buffer.
+pub(crate) struct LayerMesh {
     pub(crate) vertex: Buffer,
     pub(crate) index: Buffer,
-    pub(crate) transform: Buffer,
+    pub(crate) parts: Vec<MeshPart>,
 }
 
-impl GeometryCommonBuffers {
-    pub(crate) fn new(camera: &OrthoNoRotCamera, device: &Device, vertex_capacity: usize, index_capacity: usize) -> Self {
-        let vertex = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Vertex Buffer"),
-            size: Self::vertex_size(vertex_capacity) as wgpu::BufferAddress,
-            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+/// The range of a single cell mesh inside a `LayerMesh`.
+pub(crate) struct MeshPart {
+    pub(crate) cell: usize,
+    /// index in `Cell::layers`
+    pub(crate) layer: usize,
+    pub(crate) indices: Range<u32>,
+    pub(crate) base_vertex: i32,
+}
 
-        let index = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Index Buffer"),
-            size: Self::index_size(index_capacity) as wgpu::BufferAddress,
-            usage: BufferUsages::INDEX | BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+/// GPU data owned by a layer, every mesh is uploaded only when it changes.
+#[derive(Default)]
+pub(crate) struct LayerBuffers {
+    pub(crate) fill: Option<LayerMesh>,
+    pub(crate) stroke: Option<LayerMesh>,
+}
 
+/// The mesh revision of every layer that is on the GPU.
+pub(crate) struct MeshUploads {
+    revisions: Vec<Option<usize>>,
+}
+
+impl GeometryCommonBuffers {
+    pub(crate) fn new(camera: &OrthoCamera, device: &Device, lattice: Buffer, uniform_stride: usize) -> Self {
         let transform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Transform Buffer"),
             contents: bytemuck::cast_slice(&camera.clip_matrix()),
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
 
+        let pixel_size = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Pixel Size Buffer"),
+            contents: bytemuck::bytes_of(&PixelSize::new(camera.convert_size_screen_to_world(1.0) as f32)),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
         Self {
-            vertex,
-            index,
             transform,
+            pixel_size,
+            lattice,
+            uniform_stride,
         }
     }
 
     #[inline]
-    pub(crate) fn vertex_size(count: usize) -> usize {
-        count * mem::size_of::<[f32; 2]>()
+    pub(crate) fn lattice_size() -> u64 {
+        mem::size_of::<Lattice>() as u64
     }
 
+    /// Size of a uniform rounded up to the alignment of dynamic offsets.
     #[inline]
-    pub(crate) fn index_size(count: usize) -> usize {
-        count * mem::size_of::<u32>()
+    pub(crate) fn uniform_stride(size: usize, alignment: usize) -> usize {
+        size.div_ceil(alignment) * alignment
     }
+}
 
-    #[inline]
-    pub(crate) fn vertex_slice(&self, count: usize) -> BufferSlice<'_> {
-        self.vertex.slice(0..Self::vertex_size(count) as u64)
+impl PixelSize {
+    pub(crate) fn new(size: f32) -> Self {
+        Self { size, _padding: [0.0; 3] }
     }
+}
 
-    #[inline]
-    pub(crate) fn index_slice(&self, count: usize) -> BufferSlice<'_> {
-        self.index.slice(0..Self::index_size(count) as u64)
+impl MeshUploads {
+    /// Nothing is uploaded yet.
+    pub(crate) fn new(layers: usize) -> Self {
+        Self { revisions: vec![None; layers] }
     }
-}
\ No newline at end of file
+
+    /// The layers whose mesh revision is not on the GPU, they count as uploaded afterwards.
+    pub(crate) fn changed(&mut self, revisions: impl Iterator<Item=usize>) -> Vec<usize> {
+        let mut changed = Vec::new();
+        for (i, (uploaded, revision)) in self.revisions.iter_mut().zip(revisions).enumerate() {
+            if *uploaded != Some(revision) {
+                *uploaded = Some(revision);
+                changed.push(i);
+            }
+        }
+        changed
+    }
+}
+
+impl LayerMesh {
+    pub(crate) fn new<'a, V: Pod>(meshes: impl Iterator<Item=(usize, usize, &'a ListMesh<V>)>, device: &Device) -> Option<Self> {
+        let mut points: Vec<V> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        let mut parts = Vec::new();
+
+        for (cell, layer, mesh) in meshes {
+            if mesh.indices.is_empty() {
+                continue;
+            }
+            let start = indices.len() as u32;
+            parts.push(MeshPart {
+                cell,
+                layer,
+                indices: start..start + mesh.indices.len() as u32,
+                base_vertex: points.len() as i32,
+            });
+            points.extend_from_slice(&mesh.points);
+            indices.extend_from_slice(&mesh.indices);
+        }
+
+        if parts.is_empty() {
+            return None;
+        }
+
+        let vertex = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Layer Vertex Buffer"),
+            contents: bytemuck::cast_slice(&points),
+            usage: BufferUsages::VERTEX,
+        });
+
+        let index = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Layer Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: BufferUsages::INDEX,
+        });
+
+        Some(Self { vertex, index, parts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::draw::buffers::MeshUploads;
+
+    #[test]
+    fn test_mesh_uploads() {
+        let mut revisions = [0, 0, 0];
+        let mut uploads = MeshUploads::new(revisions.len());
+
+        // the first frame uploads every layer
+        assert_eq!(uploads.changed(revisions.iter().copied()), vec![0, 1, 2]);
+
+        // later frames and camera moves keep the meshes
+        for _ in 0..3 {
+            assert!(uploads.changed(revisions.iter().copied()).is_empty());
+        }
+
+        // only the layer whose mesh changed goes up again
+        revisions[1] += 1;
+        assert_eq!(uploads.changed(revisions.iter().copied()), vec![1]);
+        assert!(uploads.changed(revisions.iter().copied()).is_empty());
+    }
+}
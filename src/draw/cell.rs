@@ -0,0 +1,399 @@
+use std::ops::Range;
+use i_triangle::delaunay::triangulate::ShapeTriangulate;
+use i_triangle::i_overlay::i_float::point::IntPoint;
+use i_triangle::i_overlay::i_float::rect::IntRect;
+use i_triangle::i_overlay::i_shape::int::shape::{IntShape, IntShapes};
+use crate::draw::index_mesh::{ListMesh, StrokeVertex};
+use crate::draw::triangulation::{PathTriangulation, PolygonSize};
+use crate::geometry::affine::Affine;
+use crate::geometry::spatial::{overlaps, shape_contains, shape_intersects, SpatialIndex};
+
+/// Shapes of a single layer inside a cell, triangulated once and shared by all placements.
+pub(crate) struct CellLayer {
+    /// index in `Document::layers`
+    pub(crate) layer: usize,
+    pub(crate) fill_mesh: ListMesh,
+    /// outlines without a width, the stroke shader offsets them to the pencil width
+    pub(crate) stroke_mesh: ListMesh<StrokeVertex>,
+    /// sorted by `index`, nearby shapes are next to each other in the meshes too
+    pub(crate) shapes: IntShapes,
+    pub(crate) index: SpatialIndex,
+    /// the triangles of shape `i` are `fill_offsets[i]..fill_offsets[i + 1]` in `fill_mesh.indices`
+    pub(crate) fill_offsets: Vec<u32>,
+    pub(crate) stroke_offsets: Vec<u32>,
+}
+
+/// Regular array of placements, steps are given in the parent coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Repetition {
+    pub(crate) columns: u32,
+    pub(crate) rows: u32,
+    pub(crate) column_step: (f64, f64),
+    pub(crate) row_step: (f64, f64),
+}
+
+/// A reference from one cell to another (SREF or AREF).
+pub(crate) struct Instance {
+    pub(crate) cell: usize,
+    /// child cell to parent cell coordinates
+    pub(crate) transform: Affine,
+    pub(crate) repetition: Option<Repetition>,
+}
+
+pub(crate) struct Cell {
+    pub(crate) name: String,
+    /// meshes are relative to this point, so the f32 vertices of a large cell stay whole numbers
+    pub(crate) anchor: IntPoint,
+    pub(crate) layers: Vec<CellLayer>,
+    pub(crate) instances: Vec<Instance>,
+    /// bounding box including all children, None for an empty cell
+    pub(crate) rect: Option<IntRect>,
+}
+
+/// The part of a cell mesh that can show in a view.
+#[derive(Clone, Debug)]
+pub(crate) enum Culling {
+    All,
+    /// the shapes touching this rect in cell coordinates
+    Rect(IntRect),
+}
+
+/// Where a cell appears in the world after the hierarchy is resolved.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Placement {
+    /// cell to world coordinates
+    pub(crate) transform: Affine,
+    /// repetition with steps in world coordinates
+    pub(crate) repetition: Option<Repetition>,
+    /// an array of the arrays above, an AREF of a cell that is itself arrayed,
+    /// only set together with `repetition`
+    pub(crate) outer: Option<Repetition>,
+}
+
+impl CellLayer {
+    /// Meshes are built relative to `anchor`, the shapes keep the cell coordinates.
+    pub(crate) fn new(layer: usize, shapes: IntShapes, anchor: IntPoint) -> Self {
+        // a shape without points has nothing to draw or find
+        let (rects, mut slots): (Vec<IntRect>, Vec<Option<IntShape>>) = shapes.into_iter()
+            .filter_map(|shape| Some((IntRect::with_points(shape.first()?)?, Some(shape))))
+            .unzip();
+        let index = SpatialIndex::new(&rects);
+        let shapes: IntShapes = index.order().iter().filter_map(|&i| slots[i as usize].take()).collect();
+
+        let mut fill_mesh = ListMesh::with_capacity(shapes.vertices_count());
+        let mut stroke_mesh = ListMesh::with_capacity(4 * shapes.vertices_count());
+        let mut fill_offsets = Vec::with_capacity(shapes.len() + 1);
+        let mut stroke_offsets = Vec::with_capacity(shapes.len() + 1);
+        fill_offsets.push(0);
+        stroke_offsets.push(0);
+        for shape in shapes.iter() {
+            fill_mesh.append_triangulation(shape.triangulation(), anchor);
+            stroke_mesh.append(shape.triangulate_path(anchor));
+            fill_offsets.push(fill_mesh.indices.len() as u32);
+            stroke_offsets.push(stroke_mesh.indices.len() as u32);
+        }
+
+        Self { layer, fill_mesh, stroke_mesh, shapes, index, fill_offsets, stroke_offsets }
+    }
+
+    #[inline]
+    pub(crate) fn rect(&self) -> Option<IntRect> {
+        self.index.bounds()
+    }
+
+    /// Indices of the shapes that share a point with `rect`, in cell coordinates.
+    pub(crate) fn shapes_in_rect(&self, rect: &IntRect) -> Vec<usize> {
+        self.index.intersecting(rect).into_iter()
+            .filter(|&i| shape_intersects(&self.shapes[i], rect))
+            .collect()
+    }
+
+    /// Indices of the shapes under `point`, in cell coordinates.
+    pub(crate) fn shapes_at_point(&self, point: IntPoint) -> Vec<usize> {
+        let rect = IntRect { min_x: point.x, max_x: point.x, min_y: point.y, max_y: point.y };
+        self.index.intersecting(&rect).into_iter()
+            .filter(|&i| shape_contains(&self.shapes[i], point))
+            .collect()
+    }
+
+    /// Mesh index ranges that hold every shape touching `rect`, `offsets` are the fill or
+    /// the stroke offsets.
+    pub(crate) fn culled(&self, rect: &IntRect, offsets: &[u32]) -> Vec<Range<u32>> {
+        self.index.ranges(rect).into_iter()
+            .map(|r| offsets[r.start]..offsets[r.end])
+            .filter(|r| !r.is_empty())
+            .collect()
+    }
+}
+
+impl Cell {
+    /// Where the meshes of this cell are drawn, the transform takes mesh coordinates.
+    pub(crate) fn mesh_placement(&self, placement: &Placement) -> Placement {
+        let anchor = Affine::translation(self.anchor.x as f64, self.anchor.y as f64);
+        Placement { transform: placement.transform.multiply(&anchor), ..*placement }
+    }
+
+    /// Bounding box including all children relative to the anchor, where the meshes are.
+    pub(crate) fn mesh_rect(&self) -> Option<IntRect> {
+        let rect = self.rect.as_ref()?;
+        Some(Affine::translation(-self.anchor.x as f64, -self.anchor.y as f64).transform_rect(rect))
+    }
+}
+
+impl Repetition {
+    pub(crate) fn count(&self) -> usize {
+        self.columns as usize * self.rows as usize
+    }
+
+    /// The same repetition seen through `transform`, only the linear part affects the steps.
+    pub(crate) fn transformed(&self, transform: &Affine) -> Self {
+        let map = |(x, y): (f64, f64)| (transform.a * x + transform.b * y, transform.c * x + transform.d * y);
+        Self {
+            columns: self.columns,
+            rows: self.rows,
+            column_step: map(self.column_step),
+            row_step: map(self.row_step),
+        }
+    }
+
+    /// Bounding box of the rect repeated by the lattice.
+    pub(crate) fn rect(&self, rect: &IntRect) -> IntRect {
+        // the lattice is affine, its corners bound everything in between
+        let c = (self.columns - 1) as f64;
+        let r = (self.rows - 1) as f64;
+        let mut result = rect.clone();
+        for (i, j) in [(c, 0.0), (0.0, r), (c, r)] {
+            let dx = i * self.column_step.0 + j * self.row_step.0;
+            let dy = i * self.column_step.1 + j * self.row_step.1;
+            let moved = Affine::translation(dx, dy).transform_rect(rect);
+            result = IntRect::with_rects(&result, &moved);
+        }
+
+        result
+    }
+
+    pub(crate) fn offsets(&self) -> impl Iterator<Item=(f64, f64)> + '_ {
+        (0..self.rows).flat_map(move |row| (0..self.columns).map(move |column| self.offset(row * self.columns + column)))
+    }
+
+    /// Offset of the place `index`, the columns of a row go first.
+    pub(crate) fn offset(&self, index: u32) -> (f64, f64) {
+        let (c, r) = ((index % self.columns) as f64, (index / self.columns) as f64);
+        (
+            c * self.column_step.0 + r * self.row_step.0,
+            c * self.column_step.1 + r * self.row_step.1,
+        )
+    }
+
+    // the columns and rows of the places whose copy of `rect` touches `view`, `rect` is the
+    // copy of the first place; only the rows that can reach the view are walked
+    fn visible_span(&self, rect: &IntRect, view: &IntRect) -> Option<Span> {
+        // the offsets that move `rect` onto the view
+        let lo = (view.min_x as f64 - rect.max_x as f64, view.min_y as f64 - rect.max_y as f64);
+        let hi = (view.max_x as f64 - rect.min_x as f64, view.max_y as f64 - rect.min_y as f64);
+
+        let mut span = None;
+        for row in self.visible_rows(lo, hi) {
+            let (dx, dy) = (row as f64 * self.row_step.0, row as f64 * self.row_step.1);
+            let columns = steps_in(self.column_step, (lo.0 - dx, lo.1 - dy), (hi.0 - dx, hi.1 - dy), self.columns);
+            if !columns.is_empty() {
+                span = Some(joined(span, (columns, row..row + 1)));
+            }
+        }
+        span
+    }
+
+    // the lattice cut to the columns and rows of `span` and the offset of its first place
+    fn clipped(&self, (columns, rows): Span) -> (Self, (f64, f64)) {
+        let offset = self.offset(rows.start * self.columns + columns.start);
+        (Self { columns: columns.len() as u32, rows: rows.len() as u32, ..*self }, offset)
+    }
+
+    // rows that can hold an offset between `lo` and `hi`
+    fn visible_rows(&self, lo: (f64, f64), hi: (f64, f64)) -> Range<u32> {
+        let (u, v) = (self.column_step, self.row_step);
+        let det = u.0 * v.1 - u.1 * v.0;
+        if det == 0.0 {
+            // the rows lie on one line with the columns
+            return if self.columns == 1 { steps_in(v, lo, hi, self.rows) } else { 0..self.rows };
+        }
+
+        // the row coordinate of the corners of the offset box
+        let rows = [(lo.0, lo.1), (lo.0, hi.1), (hi.0, lo.1), (hi.0, hi.1)].map(|(x, y)| (u.0 * y - u.1 * x) / det);
+        let first = rows.iter().cloned().fold(f64::INFINITY, f64::min).floor().max(0.0);
+        let last = rows.iter().cloned().fold(f64::NEG_INFINITY, f64::max).ceil().min(self.rows as f64 - 1.0);
+        if first > last { 0..0 } else { first as u32..last as u32 + 1 }
+    }
+}
+
+impl Instance {
+    /// Bounding box in the parent coordinates for a child with the bounding box `rect`.
+    pub(crate) fn rect(&self, rect: &IntRect) -> IntRect {
+        Placement { transform: self.transform, repetition: self.repetition, outer: None }.rect(rect)
+    }
+}
+
+impl Placement {
+    /// How many times the cell is drawn.
+    pub(crate) fn count(&self) -> usize {
+        [self.repetition, self.outer].iter().flatten().map(|r| r.count()).product()
+    }
+
+    /// Bounding box of all the transforms for a cell with the bounding box `rect`.
+    pub(crate) fn rect(&self, rect: &IntRect) -> IntRect {
+        [self.repetition, self.outer].iter().flatten()
+            .fold(self.transform.transform_rect(rect), |rect, repetition| repetition.rect(&rect))
+    }
+
+    /// The transform of the place `index`, the inner places go first.
+    pub(crate) fn transform_at(&self, index: u32) -> Affine {
+        let Some(repetition) = &self.repetition else {
+            return self.transform;
+        };
+        let count = repetition.count() as u32;
+        let (dx, dy) = repetition.offset(index % count);
+        let (ox, oy) = self.outer.map_or((0.0, 0.0), |outer| outer.offset(index / count));
+        Affine::translation(dx + ox, dy + oy).multiply(&self.transform)
+    }
+
+    /// This placement cut to the columns and rows whose copies of a cell with the bounding box
+    /// `rect` can touch `view`, None if none of them does.
+    pub(crate) fn clipped(&self, rect: &IntRect, view: &IntRect) -> Option<Placement> {
+        let first = self.transform.transform_rect(rect);
+        let placement = match (&self.repetition, &self.outer) {
+            (None, _) => {
+                if !overlaps(&first, view) {
+                    return None;
+                }
+                *self
+            }
+            (Some(repetition), None) => {
+                let (repetition, (dx, dy)) = repetition.clipped(repetition.visible_span(&first, view)?);
+                let transform = Affine::translation(dx, dy).multiply(&self.transform);
+                Placement { transform, repetition: Some(repetition), outer: None }
+            }
+            (Some(repetition), Some(outer)) => {
+                // the inner lattice keeps what any of the outer places shows
+                let outer_span = outer.visible_span(&repetition.rect(&first), view)?;
+                let whole = (0..repetition.columns, 0..repetition.rows);
+                let mut span: Option<Span> = None;
+                'places: for row in outer_span.1.clone() {
+                    for column in outer_span.0.clone() {
+                        let (dx, dy) = outer.offset(row * outer.columns + column);
+                        let moved = Affine::translation(dx, dy).transform_rect(&first);
+                        if let Some(visible) = repetition.visible_span(&moved, view) {
+                            span = Some(joined(span, visible));
+                        }
+                        if span.as_ref() == Some(&whole) {
+                            break 'places;
+                        }
+                    }
+                }
+                let (repetition, (dx, dy)) = repetition.clipped(span?);
+                let (outer, (ox, oy)) = outer.clipped(outer_span);
+                let transform = Affine::translation(dx + ox, dy + oy).multiply(&self.transform);
+                Placement { transform, repetition: Some(repetition), outer: Some(outer) }
+            }
+        };
+        Some(placement)
+    }
+}
+
+// the columns and rows of a part of a lattice
+type Span = (Range<u32>, Range<u32>);
+
+// the span that holds both
+fn joined(span: Option<Span>, (columns, rows): Span) -> Span {
+    match span {
+        None => (columns, rows),
+        Some((c, r)) => (c.start.min(columns.start)..c.end.max(columns.end), r.start.min(rows.start)..r.end.max(rows.end)),
+    }
+}
+
+// the steps `i * step` for `i` below `count` that lie between `lo` and `hi`
+fn steps_in(step: (f64, f64), lo: (f64, f64), hi: (f64, f64), count: u32) -> Range<u32> {
+    // a little slack keeps the places on the border of the view
+    const SLACK: f64 = 1e-9;
+    let (mut first, mut last) = (0.0, count as f64 - 1.0);
+    for (step, lo, hi) in [(step.0, lo.0, hi.0), (step.1, lo.1, hi.1)] {
+        if step == 0.0 {
+            if lo > 0.0 || hi < 0.0 {
+                return 0..0;
+            }
+            continue;
+        }
+        let (a, b) = if step > 0.0 { (lo / step, hi / step) } else { (hi / step, lo / step) };
+        first = f64::max(first, (a - SLACK).ceil());
+        last = f64::min(last, (b + SLACK).floor());
+    }
+    if first > last { 0..0 } else { first as u32..last as u32 + 1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use i_triangle::i_overlay::i_float::point::IntPoint;
+    use i_triangle::i_overlay::i_float::rect::IntRect;
+    use crate::draw::cell::{Instance, Placement, Repetition};
+    use crate::geometry::spatial::overlaps;
+    use crate::gds::library::STrans;
+    use crate::geometry::affine::Affine;
+
+    #[test]
+    fn test_array_rect() {
+        let strans = STrans { reflection: true, ..STrans::default() };
+        let instance = Instance {
+            cell: 0,
+            transform: Affine::with_strans(&strans, IntPoint::new(0, 0)),
+            repetition: Some(Repetition { columns: 4, rows: 3, column_step: (10.0, 0.0), row_step: (0.0, -20.0) }),
+        };
+
+        let rect = instance.rect(&IntRect { min_x: 0, max_x: 5, min_y: 0, max_y: 5 });
+        assert_eq!((rect.min_x, rect.max_x, rect.min_y, rect.max_y), (0, 35, -45, 0));
+    }
+
+    #[test]
+    fn test_clipped_placement() {
+        // a slanted lattice of rotated copies inside a plain one
+        let strans = STrans { angle: 30.0, ..STrans::default() };
+        let placement = Placement {
+            transform: Affine::with_strans(&strans, IntPoint::new(-50, 20)),
+            repetition: Some(Repetition { columns: 7, rows: 5, column_step: (12.0, 3.0), row_step: (-4.0, 11.0) }),
+            outer: Some(Repetition { columns: 3, rows: 2, column_step: (100.0, 0.0), row_step: (0.0, 80.0) }),
+        };
+        let rect = IntRect { min_x: 0, max_x: 6, min_y: 0, max_y: 4 };
+        let places = |placement: &Placement| -> Vec<(i64, i64)> {
+            (0..placement.count() as u32)
+                .map(|i| placement.transform_at(i))
+                .map(|t| (t.tx.round() as i64, t.ty.round() as i64))
+                .collect()
+        };
+
+        for (view, count) in [
+            (IntRect { min_x: 0, max_x: 40, min_y: 30, max_y: 60 }, 56),
+            (IntRect { min_x: -200, max_x: 400, min_y: -200, max_y: 400 }, 210),
+            (IntRect { min_x: 1000, max_x: 1010, min_y: 0, max_y: 10 }, 0),
+        ] {
+            let shown: Vec<(i64, i64)> = (0..placement.count() as u32)
+                .map(|i| placement.transform_at(i))
+                .filter(|t| overlaps(&t.transform_rect(&rect), &view))
+                .map(|t| (t.tx.round() as i64, t.ty.round() as i64))
+                .collect();
+
+            // every place that shows is kept, with the rest of the columns and rows they span
+            let clipped = placement.clipped(&rect, &view);
+            let kept = clipped.as_ref().map_or(Vec::new(), places);
+            assert!(shown.iter().all(|p| kept.contains(p)));
+            assert_eq!(kept.len(), count);
+        }
+
+        // a plain array is cut exactly, 3 columns of 2 rows show
+        let array = Placement {
+            transform: Affine::IDENTITY,
+            repetition: Some(Repetition { columns: 100, rows: 100, column_step: (10.0, 0.0), row_step: (0.0, 10.0) }),
+            outer: None,
+        };
+        let clipped = array.clipped(&rect, &IntRect { min_x: 205, max_x: 228, min_y: 502, max_y: 515 }).unwrap();
+        assert_eq!(clipped.repetition.map(|r| (r.columns, r.rows)), Some((3, 2)));
+        assert_eq!((clipped.transform.tx, clipped.transform.ty), (200.0, 500.0));
+    }
+}
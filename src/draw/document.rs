@@ -1,152 +1,441 @@
-use i_triangle::delaunay::triangulate::ShapeTriangulate;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use log::warn;
+use i_triangle::i_overlay::core::fill_rule::FillRule;
+use i_triangle::i_overlay::core::simplify::Simplify;
 use i_triangle::i_overlay::i_float::point::IntPoint;
 use i_triangle::i_overlay::i_float::rect::IntRect;
+use i_triangle::i_overlay::i_shape::int::path::{IntPath, PointPathExtension};
 use i_triangle::i_overlay::i_shape::int::shape::IntShapes;
+use crate::draw::cell::{Cell, CellLayer, Instance, Placement, Repetition};
 use crate::draw::fill::brush::Brush;
-use crate::draw::index_mesh::ListMesh;
+use crate::draw::layer::{Layer, LayerTable};
 use crate::draw::stroke::pencil::Pencil;
-use crate::draw::triangulation::PathTriangulation;
-use crate::geometry::size::Size;
-
-pub(crate) struct Layer {
-    pub(crate) fill_mesh: ListMesh,
-    pub(crate) stroke_mesh: ListMesh,
-    pub(crate) brush: Brush,
-    pub(crate) pencil: Pencil,
-    pub(crate) shapes: IntShapes,
-    pub(crate) width: f32,
-}
+use crate::gds::library::{Element, Library, Structure};
+use crate::geometry::affine::Affine;
+use crate::geometry::outline::{outline_path, PathCap};
+use crate::geometry::point::Point;
+use crate::geometry::rect::Rect;
 
 pub(crate) struct Document {
-    pub(crate) layers: Vec<Layer>,
-    pub(crate) size: Size,
+    pub(crate) layers: LayerTable,
+    pub(crate) cells: Vec<Cell>,
+    /// cells that are not referenced by any other cell
+    pub(crate) tops: Vec<usize>,
+    pub(crate) rect: Rect,
 }
 
 impl Document {
     pub(crate) fn polygons() -> Self {
-        let mut layers = Vec::new();
-        let mut rect = IntRect::new(i32::MAX, i32::MIN, i32::MAX, i32::MIN);
-
-        {
-            let plus = [
-                [
-                    IntPoint::new(0, 1),
-                    IntPoint::new(0, 2),
-                    IntPoint::new(1, 2),
-                    IntPoint::new(1, 3),
-                    IntPoint::new(2, 3),
-                    IntPoint::new(2, 2),
-                    IntPoint::new(3, 2),
-                    IntPoint::new(3, 1),
-                    IntPoint::new(2, 1),
-                    IntPoint::new(2, 0),
-                    IntPoint::new(1, 0),
-                    IntPoint::new(1, 1),
-                ].to_vec()
-            ].to_vec();
-
-            let tor = [
-                [
-                    IntPoint::new(5, 0),
-                    IntPoint::new(4, 1),
-                    IntPoint::new(4, 2),
-                    IntPoint::new(5, 3),
-                    IntPoint::new(6, 3),
-                    IntPoint::new(7, 2),
-                    IntPoint::new(7, 1),
-                    IntPoint::new(6, 0),
-                ].to_vec(),
-                [
-                    IntPoint::new(5, 1),
-                    IntPoint::new(6, 1),
-                    IntPoint::new(6, 2),
-                    IntPoint::new(5, 2)
-                ].to_vec()
-            ].to_vec();
-
-            for path in plus.iter() {
-                for p in path.iter() {
-                    rect.unsafe_add_point(p);
-                }
-            }
+        let plus = [
+            [
+                IntPoint::new(0, 1),
+                IntPoint::new(0, 2),
+                IntPoint::new(1, 2),
+                IntPoint::new(1, 3),
+                IntPoint::new(2, 3),
+                IntPoint::new(2, 2),
+                IntPoint::new(3, 2),
+                IntPoint::new(3, 1),
+                IntPoint::new(2, 1),
+                IntPoint::new(2, 0),
+                IntPoint::new(1, 0),
+                IntPoint::new(1, 1),
+            ].to_vec()
+        ].to_vec();
+
+        let tor = [
+            [
+                IntPoint::new(5, 0),
+                IntPoint::new(4, 1),
+                IntPoint::new(4, 2),
+                IntPoint::new(5, 3),
+                IntPoint::new(6, 3),
+                IntPoint::new(7, 2),
+                IntPoint::new(7, 1),
+                IntPoint::new(6, 0),
+            ].to_vec(),
+            [
+                IntPoint::new(5, 1),
+                IntPoint::new(6, 1),
+                IntPoint::new(6, 2),
+                IntPoint::new(5, 2)
+            ].to_vec()
+        ].to_vec();
+
+        let square = [
+            [
+                IntPoint::new(2, 0),
+                IntPoint::new(2, 3),
+                IntPoint::new(5, 3),
+                IntPoint::new(5, 0),
+            ].to_vec()
+        ].to_vec();
+
+        let layers = vec![
+            Layer {
+                brush: Brush::new(1.0, 0.0, 0.0, 0.2),
+                pencil: Pencil::new(1.0, 0.0, 0.0, 1.0),
+                ..Layer::new(1, 0, 0)
+            },
+            Layer {
+                brush: Brush::new(0.0, 0.0, 1.0, 0.2),
+                pencil: Pencil::new(0.0, 0.0, 1.0, 1.0),
+                ..Layer::new(2, 0, 1)
+            },
+        ];
+
+        let cell_layers = vec![
+            CellLayer::new(0, vec![plus, tor], IntPoint::new(0, 0)),
+            CellLayer::new(1, vec![square], IntPoint::new(0, 0)),
+        ];
+
+        let cell = Cell { name: "DEMO".to_string(), anchor: IntPoint::new(0, 0), layers: cell_layers, instances: vec![], rect: None };
+
+        Self::with_cells(LayerTable::new(layers), vec![cell])
+    }
+
+    pub(crate) fn with_library(library: &Library) -> Self {
+        let indices: HashMap<&str, usize> = library.structures.iter()
+            .enumerate()
+            .map(|(i, s)| (s.name.as_str(), i))
+            .collect();
 
-            for path in tor.iter() {
-                for p in path.iter() {
-                    rect.unsafe_add_point(p);
+        // layers are sorted by layer and datatype
+        let mut keys = BTreeSet::new();
+        for structure in library.structures.iter() {
+            for element in structure.elements.iter() {
+                match element {
+                    Element::Boundary(boundary) => { keys.insert((boundary.layer, boundary.datatype)); }
+                    Element::Box(gds_box) => { keys.insert((gds_box.layer, gds_box.box_type)); }
+                    Element::Path(path) => { keys.insert((path.layer, path.datatype)); }
+                    _ => {}
                 }
             }
+        }
+
+        let layers = LayerTable::new(keys.iter()
+            .enumerate()
+            .map(|(i, &(layer, datatype))| Layer::new(layer, datatype, i))
+            .collect()
+        );
+
+        let cells = library.structures.iter()
+            .map(|structure| build_cell(structure, &layers, &indices))
+            .collect();
+
+        Self::with_cells(layers, cells)
+    }
 
-            let plus_triangulation = plus.triangulation();
-            let tor_triangulation = tor.triangulation();
-
-            let mut fill_mesh = ListMesh::with_capacity(plus_triangulation.points.len() + tor_triangulation.points.len());
-
-            fill_mesh.append_triangulation(plus_triangulation);
-            fill_mesh.append_triangulation(tor_triangulation);
-
-            layers.push(Layer {
-                fill_mesh,
-                stroke_mesh: ListMesh { points: vec![], indices: vec![] },
-                brush: Brush { red: 1.0, green: 0.0, blue: 0.0, alpha: 0.2 },
-                pencil: Pencil {
-                    red: 1.0,
-                    green: 0.0,
-                    blue: 0.0,
-                    alpha: 1.0,
-                },
-                shapes: vec![plus, tor],
-                width: 0.0,
+    fn with_cells(layers: LayerTable, mut cells: Vec<Cell>) -> Self {
+        let mut visits = vec![Visit::New; cells.len()];
+        for index in 0..cells.len() {
+            resolve_rect(&mut cells, &mut visits, index);
+        }
+
+        let mut referenced = vec![false; cells.len()];
+        for cell in cells.iter() {
+            for instance in cell.instances.iter() {
+                referenced[instance.cell] = true;
             }
-            );
         }
+        let tops: Vec<usize> = (0..cells.len()).filter(|&i| !referenced[i]).collect();
+
+        let mut document = Self { layers, cells, tops, rect: Rect::new(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 1.0 }) };
+        if let Some(rect) = document.bounds() {
+            document.rect = Rect::with_int_rect(&rect);
+        }
+
+        document
+    }
+
+    /// Bounding box of all top cells in database units, None for an empty document.
+    pub(crate) fn bounds(&self) -> Option<IntRect> {
+        let mut rect: Option<IntRect> = None;
+        for &top in self.tops.iter() {
+            rect = IntRect::with_optional_rects(rect, self.cells[top].rect.clone());
+        }
+        rect
+    }
+
+    pub(crate) fn cell_index(&self, name: &str) -> Option<usize> {
+        self.cells.iter().position(|c| c.name == name)
+    }
+
+    /// World rectangle of the first placement of a cell, None for an empty or unplaced cell.
+    pub(crate) fn cell_view(&self, index: usize) -> Option<Rect> {
+        let rect = self.cells[index].rect.clone()?;
+        let placement = *self.placements()[index].first()?;
+        Some(Rect::with_int_rect(&placement.transform.transform_rect(&rect)))
+    }
+
+    /// Resolves the hierarchy: for every cell all the places it is drawn at in world coordinates.
+    pub(crate) fn placements(&self) -> Vec<Vec<Placement>> {
+        let mut placements = vec![Vec::new(); self.cells.len()];
+        for &top in self.tops.iter() {
+            self.place(top, Placement { transform: Affine::IDENTITY, repetition: None, outer: None }, &mut placements);
+        }
+        placements
+    }
 
-        {
-            let square = [
-                [
-                    IntPoint::new(2, 0),
-                    IntPoint::new(2, 3),
-                    IntPoint::new(5, 3),
-                    IntPoint::new(5, 0),
-                ].to_vec()
-            ].to_vec();
-
-            for path in square.iter() {
-                for p in path.iter() {
-                    rect.unsafe_add_point(p);
+    /// Placements of the cell meshes, which are relative to the cell anchors.
+    pub(crate) fn mesh_placements(&self) -> Vec<Vec<Placement>> {
+        self.placements().iter().zip(self.cells.iter())
+            .map(|(placements, cell)| placements.iter().map(|p| cell.mesh_placement(p)).collect())
+            .collect()
+    }
+
+    fn place(&self, index: usize, placement: Placement, placements: &mut Vec<Vec<Placement>>) {
+        for instance in self.cells[index].instances.iter() {
+            let transform = placement.transform.multiply(&instance.transform);
+            let repetition = instance.repetition.map(|r| r.transformed(&placement.transform));
+
+            let child = match (repetition, placement.repetition, placement.outer) {
+                (None, _, _) => Placement { transform, ..placement },
+                (Some(_), None, _) => Placement { transform, repetition, outer: None },
+                (Some(_), Some(_), None) => Placement { transform, repetition, outer: placement.repetition },
+                (Some(_), Some(_), Some(outer)) => {
+                    // a placement keeps two lattices, so the outermost one is unrolled
+                    for (dx, dy) in outer.offsets() {
+                        let transform = Affine::translation(dx, dy).multiply(&transform);
+                        let child = Placement { transform, repetition, outer: placement.repetition };
+                        self.place(instance.cell, child, placements);
+                    }
+                    continue;
                 }
+            };
+            self.place(instance.cell, child, placements);
+        }
+
+        placements[index].push(placement);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    Active,
+    Done,
+}
+
+// computes the bounding box of a cell and drops instances that reference the cell back
+fn resolve_rect(cells: &mut [Cell], visits: &mut [Visit], index: usize) -> Option<IntRect> {
+    match visits[index] {
+        Visit::Done => return cells[index].rect.clone(),
+        Visit::Active => return None,
+        Visit::New => {}
+    }
+    visits[index] = Visit::Active;
+
+    let mut rect = None;
+    for layer in cells[index].layers.iter() {
+        rect = IntRect::with_optional_rects(rect, layer.rect());
+    }
+
+    let instances = std::mem::take(&mut cells[index].instances);
+    let mut resolved = Vec::with_capacity(instances.len());
+    for instance in instances {
+        if visits[instance.cell] == Visit::Active {
+            warn!("recursive reference to {} from {}", cells[instance.cell].name, cells[index].name);
+            continue;
+        }
+        if let Some(child) = resolve_rect(cells, visits, instance.cell) {
+            rect = IntRect::with_optional_rects(rect, Some(instance.rect(&child)));
+        }
+        resolved.push(instance);
+    }
+
+    cells[index].instances = resolved;
+    cells[index].rect = rect.clone();
+    visits[index] = Visit::Done;
+
+    rect
+}
+
+fn build_cell(structure: &Structure, layers: &LayerTable, indices: &HashMap<&str, usize>) -> Cell {
+    let mut shapes: BTreeMap<usize, IntShapes> = BTreeMap::new();
+    let mut instances = Vec::new();
+    // every layer of the library is in the table
+    let layer_index = |layer: u16, datatype: u16| layers.index_of(layer, datatype).unwrap_or_default();
+
+    for element in structure.elements.iter() {
+        match element {
+            Element::Boundary(boundary) => {
+                let layer = layer_index(boundary.layer, boundary.datatype);
+                add_polygon(shapes.entry(layer).or_default(), boundary.points.clone());
+            }
+            Element::Box(gds_box) => {
+                let layer = layer_index(gds_box.layer, gds_box.box_type);
+                add_polygon(shapes.entry(layer).or_default(), gds_box.points.clone());
+            }
+            Element::Path(path) => {
+                // an absolute width is treated as a relative one, cells are outlined once in local coordinates
+                let width = path.width.unsigned_abs() as f64;
+                let cap = PathCap::with_path_type(path.path_type, width, path.begin_extension, path.end_extension);
+                let layer_shapes = shapes.entry(layer_index(path.layer, path.datatype)).or_default();
+                layer_shapes.extend(outline_path(&path.points, width, cap));
             }
+            Element::StructRef(reference) => {
+                let Some(&cell) = indices.get(reference.name.as_str()) else {
+                    warn!("unknown structure {} in {}", reference.name, structure.name);
+                    continue;
+                };
+                let transform = Affine::with_strans(&reference.strans, reference.position);
+                instances.push(Instance { cell, transform, repetition: None });
+            }
+            Element::ArrayRef(reference) => {
+                let Some(&cell) = indices.get(reference.name.as_str()) else {
+                    warn!("unknown structure {} in {}", reference.name, structure.name);
+                    continue;
+                };
 
-            let triangulation = square.triangulation();
-
-            let mut fill_mesh = ListMesh::with_capacity(triangulation.points.len());
-
-            fill_mesh.append_triangulation(triangulation);
-
-            layers.push(Layer {
-                fill_mesh,
-                stroke_mesh: ListMesh { points: vec![], indices: vec![] },
-                brush: Brush { red: 0.0, green: 0.0, blue: 1.0, alpha: 0.2 },
-                pencil: Pencil {
-                    red: 0.0,
-                    green: 0.0,
-                    blue: 1.0,
-                    alpha: 1.0,
-                },
-                shapes: vec![square],
-                width: 0.0,
-            });
+                let columns = reference.columns.max(1) as f64;
+                let rows = reference.rows.max(1) as f64;
+                let repetition = Repetition {
+                    columns: reference.columns.max(1) as u32,
+                    rows: reference.rows.max(1) as u32,
+                    column_step: (
+                        (reference.column_point.x - reference.position.x) as f64 / columns,
+                        (reference.column_point.y - reference.position.y) as f64 / columns,
+                    ),
+                    row_step: (
+                        (reference.row_point.x - reference.position.x) as f64 / rows,
+                        (reference.row_point.y - reference.position.y) as f64 / rows,
+                    ),
+                };
+                let transform = Affine::with_strans(&reference.strans, reference.position);
+                instances.push(Instance { cell, transform, repetition: Some(repetition) });
+            }
+            Element::Text(_) | Element::Node(_) => {}
         }
+    }
 
-        Self { layers, size: Size { width: rect.width() as f32, height: rect.height() as f32 } }
+    let mut rect = None;
+    for shape in shapes.values().flatten() {
+        rect = IntRect::with_optional_rects(rect, IntRect::with_points(&shape[0]));
     }
+    let anchor = rect.map_or(IntPoint::new(0, 0), |r| IntPoint::new(
+        ((r.min_x as i64 + r.max_x as i64) / 2) as i32,
+        ((r.min_y as i64 + r.max_y as i64) / 2) as i32,
+    ));
+
+    let layers = shapes.into_iter()
+        .filter(|(_, shapes)| !shapes.is_empty())
+        .map(|(layer, shapes)| CellLayer::new(layer, shapes, anchor))
+        .collect();
+
+    Cell { name: structure.name.clone(), anchor, layers, instances, rect: None }
 }
 
-impl Layer {
-    pub(crate) fn build_strokes(&mut self, width: f32) {
-        if self.width == width {
-            return;
+fn add_polygon(shapes: &mut IntShapes, mut path: IntPath) {
+    path.dedup();
+    if path.len() > 1 && path.first() == path.last() {
+        path.pop();
+    }
+    if path.len() < 3 {
+        return;
+    }
+
+    if path.is_convex() {
+        // fast path for rectangles and other simple polygons
+        match path.unsafe_area() {
+            0 => {}
+            area => {
+                if area < 0 {
+                    path.reverse();
+                }
+                shapes.push(vec![path]);
+            }
         }
-        self.width = width;
-        self.stroke_mesh = self.shapes.triangulate_path(width);
+    } else {
+        shapes.extend(path.simplify(FillRule::NonZero, 0));
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use i_triangle::i_overlay::i_float::point::IntPoint;
+    use crate::draw::document::Document;
+    use crate::gds::library::{ArrayRef, Boundary, Element, Library, STrans, Structure, Units};
+    use crate::gds::reader::tests::{read_strict, sample_stream};
+
+    #[test]
+    fn test_with_library() {
+        let library = read_strict(&sample_stream()).unwrap();
+        let document = Document::with_library(&library);
+
+        assert_eq!(document.layers.len(), 2);
+        assert_eq!(document.cells.len(), 2);
+        assert_eq!(document.tops, vec![1]);
+
+        // the square is triangulated once
+        let square = &document.cells[0];
+        assert_eq!(square.layers.len(), 1);
+        assert_eq!(square.layers[0].fill_mesh.indices.len(), 6);
+
+        // path type 2 is extended by half width on both ends
+        let top = &document.cells[1];
+        let path = &top.layers[0].shapes[0][0];
+        assert_eq!(top.layers[0].layer, 1);
+        assert!(path.iter().any(|p| p.x == -2) && path.iter().any(|p| p.x == 42));
+
+        // one SREF and a 3x2 AREF of the square
+        let placements = document.placements();
+        assert_eq!(placements[1].len(), 1);
+        assert_eq!(placements[0].iter().map(|p| p.count()).sum::<usize>(), 7);
+
+        // SREF rotated by 90° around (100, 0)
+        let rotated = placements[0][0].transform.transform_rect(&square.rect.clone().unwrap());
+        assert_eq!((rotated.min_x, rotated.max_x, rotated.min_y, rotated.max_y), (90, 100, 0, 10));
+
+        let array = placements[0][1].repetition.unwrap();
+        assert_eq!((array.columns, array.rows), (3, 2));
+        assert_eq!(array.column_step, (20.0, 0.0));
+        assert_eq!(array.row_step, (0.0, 20.0));
+
+        let rect = top.rect.clone().unwrap();
+        assert_eq!((rect.min_x, rect.min_y, rect.max_x, rect.max_y), (-2, 0, 100, 130));
+        assert_eq!((document.rect.center.x, document.rect.center.y), (49.0, 65.0));
+        assert_eq!((document.rect.size.width, document.rect.size.height), (102.0, 130.0));
+
+        let view = document.cell_view(document.cell_index("SQUARE").unwrap()).unwrap();
+        assert_eq!((view.center.x, view.center.y, view.size.width), (95.0, 5.0, 10.0));
+        assert!(document.cell_index("MISSING").is_none());
+    }
+
+    #[test]
+    fn test_nested_array() {
+        let array = |name: &str, columns, rows, column_point, row_point| Element::ArrayRef(ArrayRef {
+            name: name.to_string(),
+            strans: STrans::default(),
+            columns,
+            rows,
+            position: IntPoint::new(0, 0),
+            column_point,
+            row_point,
+        });
+        let square = vec![IntPoint::new(0, 0), IntPoint::new(0, 10), IntPoint::new(10, 10), IntPoint::new(10, 0)];
+        let structures = vec![
+            Structure { name: "SQUARE".to_string(), elements: vec![Element::Boundary(Boundary { layer: 1, datatype: 0, points: square })] },
+            // a row of 4 squares at a pitch of 20, repeated 3 times 50 apart
+            Structure { name: "ROW".to_string(), elements: vec![array("SQUARE", 4, 1, IntPoint::new(80, 0), IntPoint::new(0, 0))] },
+            Structure { name: "TOP".to_string(), elements: vec![array("ROW", 1, 3, IntPoint::new(0, 0), IntPoint::new(0, 150))] },
+        ];
+        let library = Library { name: "NESTED".to_string(), units: Units::default(), structures };
+        let document = Document::with_library(&library);
+
+        // the array of arrays is not unrolled
+        let placements = document.placements();
+        assert_eq!(placements[0].len(), 1);
+        let placement = placements[0][0];
+        assert_eq!(placement.count(), 12);
+
+        let mut corners: Vec<(f64, f64)> = (0..12).map(|i| placement.transform_at(i)).map(|t| (t.tx, t.ty)).collect();
+        corners.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut expected: Vec<(f64, f64)> = (0..12).map(|i| (20.0 * (i % 4) as f64, 50.0 * (i / 4) as f64)).collect();
+        expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(corners, expected);
+
+        let rect = placement.rect(&document.cells[0].rect.clone().unwrap());
+        assert_eq!((rect.min_x, rect.min_y, rect.max_x, rect.max_y), (0, 0, 70, 110));
+    }
+}
@@ -1,4 +1,5 @@
 use bytemuck::{Pod, Zeroable};
+use crate::draw::fill::pattern::SOLID_PATTERN;
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
@@ -7,6 +8,22 @@ pub(crate) struct Brush {
     pub(crate) green: f32,
     pub(crate) blue: f32,
     pub(crate) alpha: f32,
+    /// index in the pattern table
+    pub(crate) pattern: u32,
+    /// screen pixels per pattern pixel
+    pub(crate) pattern_scale: f32,
+    // uniforms are aligned to 16 bytes
+    _padding: [f32; 2],
+}
+
+impl Brush {
+    pub(crate) fn new(red: f32, green: f32, blue: f32, alpha: f32) -> Self {
+        Self { red, green, blue, alpha, ..Self::default() }
+    }
+
+    pub(crate) fn with_pattern(self, pattern: u32) -> Self {
+        Self { pattern, ..self }
+    }
 }
 
 impl Default for Brush {
@@ -16,6 +33,9 @@ impl Default for Brush {
             green: 1.0,
             blue: 1.0,
             alpha: 1.0,
+            pattern: SOLID_PATTERN,
+            pattern_scale: 1.0,
+            _padding: [0.0; 2],
         }
     }
-}
\ No newline at end of file
+}
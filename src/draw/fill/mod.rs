@@ -1,2 +1,3 @@
 pub(crate) mod render;
 pub(crate) mod brush;
+pub(crate) mod pattern;
@@ -0,0 +1,120 @@
+/// Side of a fill pattern in pattern pixels.
+pub(crate) const PATTERN_SIZE: usize = 32;
+
+/// Patterns the fill shader can hold, built-in ones included.
+pub(crate) const MAX_PATTERNS: usize = 64;
+
+pub(crate) const SOLID_PATTERN: u32 = 0;
+pub(crate) const HOLLOW_PATTERN: u32 = 1;
+
+/// A 32x32 bitmap repeated over the screen, bit `x` of `rows[y]` is the pixel (x, y).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FillPattern {
+    pub(crate) rows: [u32; PATTERN_SIZE],
+}
+
+impl FillPattern {
+    fn with_fn(is_set: impl Fn(usize, usize) -> bool) -> Self {
+        let mut rows = [0; PATTERN_SIZE];
+        for (y, row) in rows.iter_mut().enumerate() {
+            for x in 0..PATTERN_SIZE {
+                if is_set(x, y) {
+                    *row |= 1 << x;
+                }
+            }
+        }
+        Self { rows }
+    }
+
+    /// Reads a bitmap given as text rows, `*` is a set pixel, anything else is clear.
+    /// Smaller bitmaps are repeated to fill 32x32.
+    pub(crate) fn with_lines(lines: &[&str]) -> Option<Self> {
+        let lines: Vec<Vec<bool>> = lines.iter()
+            .map(|line| line.trim().chars().map(|c| c == '*').collect())
+            .collect();
+        let height = lines.len();
+        let width = lines.first().map_or(0, |l| l.len());
+        if height == 0 || height > PATTERN_SIZE || width == 0 || width > PATTERN_SIZE || lines.iter().any(|l| l.len() != width) {
+            return None;
+        }
+
+        Some(Self::with_fn(|x, y| lines[y % height][x % width]))
+    }
+
+    #[inline]
+    pub(crate) fn is_set(&self, x: usize, y: usize) -> bool {
+        self.rows[y % PATTERN_SIZE] >> (x % PATTERN_SIZE) & 1 == 1
+    }
+}
+
+/// Standard patterns, the indices follow the first KLayout dither patterns `I0`..`I15`.
+pub(crate) fn builtin_patterns() -> Vec<FillPattern> {
+    // screen y goes down, so `x + y` lines go up to the right
+    let left = |x: usize, y: usize, period: usize| (x + y) % period;
+    let right = |x: usize, y: usize, period: usize| (x + PATTERN_SIZE - y) % period;
+
+    vec![
+        // solid
+        FillPattern::with_fn(|_, _| true),
+        // hollow
+        FillPattern::with_fn(|_, _| false),
+        // dotted
+        FillPattern::with_fn(|x, y| x % 2 == 0 && y % 2 == 0),
+        // coarsely dotted
+        FillPattern::with_fn(|x, y| x % 4 == 0 && y % 4 == 0),
+        // left-hatched
+        FillPattern::with_fn(|x, y| left(x, y, 4) == 0),
+        // lightly left-hatched
+        FillPattern::with_fn(|x, y| left(x, y, 8) == 0),
+        // strongly left-hatched, dense
+        FillPattern::with_fn(|x, y| left(x, y, 4) < 2),
+        // strongly left-hatched, sparse
+        FillPattern::with_fn(|x, y| left(x, y, 8) < 2),
+        // right-hatched
+        FillPattern::with_fn(|x, y| right(x, y, 4) == 0),
+        // lightly right-hatched
+        FillPattern::with_fn(|x, y| right(x, y, 8) == 0),
+        // strongly right-hatched, dense
+        FillPattern::with_fn(|x, y| right(x, y, 4) < 2),
+        // strongly right-hatched, sparse
+        FillPattern::with_fn(|x, y| right(x, y, 8) < 2),
+        // cross-hatched
+        FillPattern::with_fn(|x, y| left(x, y, 4) == 0 || right(x, y, 4) == 0),
+        // lightly cross-hatched
+        FillPattern::with_fn(|x, y| left(x, y, 8) == 0 || right(x, y, 8) == 0),
+        // checkerboard
+        FillPattern::with_fn(|x, y| (x / 2 + y / 2) % 2 == 0),
+        // strongly cross-hatched, sparse
+        FillPattern::with_fn(|x, y| left(x, y, 8) < 2 || right(x, y, 8) < 2),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::draw::fill::pattern::{builtin_patterns, FillPattern, HOLLOW_PATTERN, SOLID_PATTERN};
+
+    #[test]
+    fn test_patterns() {
+        let patterns = builtin_patterns();
+        assert!(patterns[SOLID_PATTERN as usize].rows.iter().all(|&r| r == u32::MAX));
+        assert!(patterns[HOLLOW_PATTERN as usize].rows.iter().all(|&r| r == 0));
+
+        // every pattern tiles without a seam
+        for pattern in patterns.iter() {
+            for y in 0..64 {
+                for x in 0..64 {
+                    assert_eq!(pattern.is_set(x, y), pattern.is_set(x + 32, y + 32));
+                }
+            }
+        }
+
+        let pattern = FillPattern::with_lines(&["*..", ".*."]).unwrap();
+        assert!(pattern.is_set(0, 0));
+        assert!(!pattern.is_set(2, 0));
+        assert!(pattern.is_set(3, 0));
+        assert!(pattern.is_set(1, 1));
+        assert!(pattern.is_set(0, 2));
+        assert!(FillPattern::with_lines(&["*.", "*"]).is_none());
+        assert!(FillPattern::with_lines(&[]).is_none());
+    }
+}
@@ -2,6 +2,25 @@ struct VertexInput {
     @location(0) position: vec2<f32>,
 };
 
+struct InstanceInput {
+    @location(1) x_axis: vec2<f32>,
+    @location(2) y_axis: vec2<f32>,
+    @location(3) translate: vec2<f32>,
+};
+
+struct Lattice {
+    x_axis: vec2<f32>,
+    y_axis: vec2<f32>,
+    translate: vec2<f32>,
+    column_step: vec2<f32>,
+    row_step: vec2<f32>,
+    outer_column_step: vec2<f32>,
+    outer_row_step: vec2<f32>,
+    columns: u32,
+    rows: u32,
+    outer_columns: u32,
+};
+
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(1) frag_position: vec2<f32>,
@@ -12,6 +31,8 @@ struct Brush {
     g: f32,
     b: f32,
     a: f32,
+    pattern: u32,
+    pattern_scale: f32,
 };
 
 @group(0) @binding(0)
@@ -20,15 +41,49 @@ var<uniform> transform: mat4x4<f32>;
 @group(0) @binding(1)
 var<uniform> brush: Brush;
 
-@vertex
-fn vs_main(in: VertexInput) -> VertexOutput {
+@group(0) @binding(2)
+var<uniform> lattice: Lattice;
+
+// 32x32 bit patterns, 8 vectors of 4 rows each, bit x of row y is the pixel (x, y)
+@group(0) @binding(3)
+var<uniform> patterns: array<vec4<u32>, 512>;
+
+fn world_vertex(position: vec2<f32>) -> VertexOutput {
     var out: VertexOutput;
-    out.position = transform * vec4<f32>(in.position, 0.0, 1.0);
-    out.frag_position = in.position;
+    out.position = transform * vec4<f32>(position, 0.0, 1.0);
+    out.frag_position = position;
     return out;
 }
 
+@vertex
+fn vs_main(in: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let position = in.position.x * instance.x_axis + in.position.y * instance.y_axis + instance.translate;
+    return world_vertex(position);
+}
+
+// the instance index runs over the inner lattice first, then over the outer one
+fn lattice_offset(index: u32) -> vec2<f32> {
+    let count = lattice.columns * lattice.rows;
+    let inner = index % count;
+    let outer = index / count;
+    return f32(inner % lattice.columns) * lattice.column_step + f32(inner / lattice.columns) * lattice.row_step
+        + f32(outer % lattice.outer_columns) * lattice.outer_column_step + f32(outer / lattice.outer_columns) * lattice.outer_row_step;
+}
+
+@vertex
+fn vs_array(in: VertexInput, @builtin(instance_index) index: u32) -> VertexOutput {
+    let offset = lattice_offset(index);
+    let position = in.position.x * lattice.x_axis + in.position.y * lattice.y_axis + lattice.translate + offset;
+    return world_vertex(position);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // screen space, the pattern does not shimmer while the view moves
+    let pixel = vec2<u32>(floor(in.position.xy / brush.pattern_scale)) % vec2<u32>(32u, 32u);
+    let row = patterns[brush.pattern * 8u + pixel.y / 4u][pixel.y % 4u];
+    if ((row >> pixel.x) & 1u) == 0u {
+        discard;
+    }
     return vec4<f32>(brush.r, brush.g, brush.b, brush.a);
 }
@@ -1,64 +1,185 @@
-use wgpu::{BufferUsages, ColorTargetState, Device, util::DeviceExt, BufferAddress};
+use log::info;
+use wgpu::{ColorTargetState, Device};
+use crate::control::clock::SystemClock;
+use crate::control::layers::LayerCommand;
 use crate::control::navigation::NavigationEvent;
-use crate::draw::buffers::GeometryCommonBuffers;
+use crate::control::view::ViewCommand;
+use crate::draw::buffers::{GeometryCommonBuffers, LayerBuffers, LayerMesh, MeshUploads, PixelSize};
+use crate::draw::cell::{CellLayer, Placement};
 use crate::draw::context::DrawContext;
 use crate::draw::document::Document;
+use crate::draw::fill::brush::Brush;
+use crate::draw::fill::pattern::HOLLOW_PATTERN;
 use crate::draw::fill::render::FillRender;
+use crate::draw::instance::{create_instances, update_instances, update_lattices, CellInstances};
+use crate::draw::overlay::render::OverlayRender;
 use crate::draw::painter::Painter;
+use crate::draw::stroke::pencil::Pencil;
 use crate::draw::stroke::render::StrokeRender;
-use crate::draw::triangulation::PolygonSize;
-use crate::eye::camera::OrthoNoRotCamera;
+use crate::draw::visibility::{InstanceDraw, PartDraw, Visibility};
+use crate::eye::animation::CameraAnimation;
+use crate::eye::bookmark::Bookmark;
+use crate::eye::camera::OrthoCamera;
+use crate::eye::history::ViewHistory;
+use crate::eye::orientation::Orientation;
 use crate::geometry::point::Point;
 use crate::geometry::rect::Rect;
 use crate::geometry::size::Size;
 
+// the fitted document leaves this part of its size free on every side
+const FIT_MARGIN: f64 = 0.05;
+
+// the visible draws of every part of a layer mesh
+struct LayerDraws {
+    fill: Vec<Vec<PartDraw>>,
+    stroke: Vec<Vec<PartDraw>>,
+}
+
 pub(crate) struct GeometryPainter {
     pub(crate) document: Document,
-    camera: OrthoNoRotCamera,
+    // mesh placements of every cell in world coordinates, the single ones first
+    placements: Vec<Vec<Placement>>,
+    visibility: Visibility,
+    // per layer, what of it shows in the view at `draws_timestamp`
+    layer_draws: Vec<LayerDraws>,
+    draws_timestamp: usize,
+    // the same placements on the GPU, relative to `instances_origin`
+    instances: Vec<CellInstances>,
+    instances_origin: Point,
+    layer_buffers: Vec<LayerBuffers>,
+    uploads: MeshUploads,
+    camera: OrthoCamera,
     fill_render: FillRender,
     stroke_render: StrokeRender,
+    overlay_render: OverlayRender,
+    // the zoom box corners on the screen while it is dragged
+    zoom_box: Option<(Point, Point)>,
     common_buffers: GeometryCommonBuffers,
     camera_timestamp: usize,
-    start_zoom: f32,
+    start_zoom: f64,
     start_dragged: Point,
-    stroke_width: f32,
+    // views where a gesture or a command ended
+    history: ViewHistory,
+    animation: CameraAnimation,
 }
 
 impl GeometryPainter {
-    pub(crate) fn create(color: ColorTargetState, device: &Device, screen_width: u32, screen_height: u32) -> Self {
-        let document = Document::polygons();
-
-        let camera = OrthoNoRotCamera::new(
+    pub(crate) fn create(document: Document, color: ColorTargetState, device: &Device, screen_width: u32, screen_height: u32) -> Self {
+        let camera = OrthoCamera::new(
             Size::new_uint(screen_width, screen_height),
-            Rect::with_size(document.size),
+            Self::fit_rect(&document),
         );
 
-        let mut vertex_capacity = 0;
-        let mut index_capacity = 0;
-        for layer in document.layers.iter() {
-            let n = layer.shapes.vertices_count();
-            vertex_capacity = vertex_capacity.max(layer.fill_mesh.points.len());
-            vertex_capacity = vertex_capacity.max(4 * n);
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
+        let stride = GeometryCommonBuffers::uniform_stride(GeometryCommonBuffers::lattice_size() as usize, alignment);
+        let mut placements = document.mesh_placements();
+        let visibility = Visibility::new(&document, &mut placements);
+        let (instances, lattice) = create_instances(&placements, camera.origin(), stride, device);
 
-            index_capacity = index_capacity.max(layer.fill_mesh.indices.len());
-            index_capacity = index_capacity.max(6 * n)
-        }
+        // the meshes go up with the first frame
+        let layer_buffers = (0..document.layers.len()).map(|_| LayerBuffers::default()).collect();
+        let uploads = MeshUploads::new(document.layers.len());
 
-        let common_buffers = GeometryCommonBuffers::new(&camera, device, vertex_capacity, index_capacity);
-        let fill_render = FillRender::new(&color, &common_buffers, device);
-        let stroke_render = StrokeRender::new(&color, &common_buffers, device);
+        let brushes: Vec<Brush> = document.layers.iter().map(|l| l.brush).collect();
+        let pencils: Vec<Pencil> = document.layers.iter().map(|l| l.pencil).collect();
+
+        let common_buffers = GeometryCommonBuffers::new(&camera, device, lattice, stride);
+        let fill_render = FillRender::new(&color, &brushes, document.layers.patterns(), &common_buffers, device);
+        let stroke_render = StrokeRender::new(&color, &pencils, &common_buffers, device);
+        let overlay_render = OverlayRender::new(&color, device);
 
         Self {
             document,
+            instances_origin: camera.origin(),
+            history: ViewHistory::new(camera.view_state(), Box::new(SystemClock::new())),
+            placements,
+            visibility,
+            layer_draws: Vec::new(),
+            draws_timestamp: usize::MAX,
+            instances,
+            layer_buffers,
+            uploads,
             common_buffers,
             fill_render,
             stroke_render,
+            overlay_render,
+            zoom_box: None,
             camera,
             camera_timestamp: usize::MAX,
             start_zoom: 1.0,
             start_dragged: Point { x: 0.0, y: 0.0 },
-            stroke_width: 2.0,
+            animation: CameraAnimation::new(Box::new(SystemClock::new())),
+        }
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.camera.set_orientation(orientation);
+        info!("view orientation {}", orientation.name());
+    }
+
+    fn fit_rect(document: &Document) -> Rect {
+        document.rect.scaled(1.0 + 2.0 * FIT_MARGIN)
+    }
+
+    // cell layers on the given layer with their index in the cell, only of cells that are placed somewhere
+    fn drawn_layers<'a>(document: &'a Document, instances: &'a [CellInstances], layer: usize) -> impl Iterator<Item=(usize, usize, &'a CellLayer)> {
+        document.cells.iter().enumerate()
+            .filter(move |(c, _)| !instances[*c].is_empty())
+            .flat_map(move |(c, cell)| cell.layers.iter().enumerate()
+                .filter(move |(_, l)| l.layer == layer)
+                .map(move |(j, l)| (c, j, l))
+            )
+    }
+
+    // uploads the meshes of the layers that changed, what shows of them is found again
+    fn update_meshes(&mut self, device: &Device) {
+        let changed = self.uploads.changed(self.document.layers.iter().map(|l| l.mesh_revision));
+        for &i in changed.iter() {
+            let layers = || Self::drawn_layers(&self.document, &self.instances, i);
+            self.layer_buffers[i] = LayerBuffers {
+                fill: LayerMesh::new(layers().map(|(c, j, l)| (c, j, &l.fill_mesh)), device),
+                stroke: LayerMesh::new(layers().map(|(c, j, l)| (c, j, &l.stroke_mesh)), device),
+            };
+        }
+        if !changed.is_empty() {
+            self.draws_timestamp = usize::MAX;
+        }
+    }
+
+    // what shows of the layer meshes, found again only when the camera or a mesh changes
+    fn update_draws(&mut self, context: &mut DrawContext) {
+        self.update_meshes(context.device);
+        if self.camera.timestamp() == self.draws_timestamp {
+            return;
         }
+        self.draws_timestamp = self.camera.timestamp();
+
+        // strokes reach out of the shapes by half their width
+        let width = self.document.layers.iter().map(|l| l.pencil.width).fold(0.0, f32::max);
+        let view = self.camera.view_rect().int_rect(self.camera.convert_size_screen_to_world(width as f64));
+        let draws = self.visibility.draws(&self.document, &self.placements, &view);
+        // the arrays are drawn cut to what shows
+        update_lattices(&self.common_buffers.lattice, &draws.placements, self.instances_origin, self.common_buffers.uniform_stride, context.queue);
+
+        self.layer_draws = self.layer_buffers.iter()
+            .map(|buffers| LayerDraws {
+                fill: buffers.fill.as_ref().map_or(Vec::new(), |mesh| self.part_draws(mesh, &draws.cells, |l| &l.fill_offsets)),
+                stroke: buffers.stroke.as_ref().map_or(Vec::new(), |mesh| self.part_draws(mesh, &draws.cells, |l| &l.stroke_offsets)),
+            })
+            .collect();
+    }
+
+    // the draws of every mesh part, `offsets` picks the fill or the stroke ones
+    fn part_draws(&self, mesh: &LayerMesh, draws: &[Vec<InstanceDraw>], offsets: fn(&CellLayer) -> &[u32]) -> Vec<Vec<PartDraw>> {
+        mesh.parts.iter()
+            .map(|part| {
+                let cell_layer = &self.document.cells[part.cell].layers[part.layer];
+                draws[part.cell].iter()
+                    .map(|draw| draw.part(cell_layer, offsets(cell_layer), part.indices.clone()))
+                    .filter(|draw| !draw.indices.is_empty())
+                    .collect()
+            })
+            .collect()
     }
 
     fn update_transform_buffer(&mut self, context: &mut DrawContext) {
@@ -67,51 +188,85 @@ impl GeometryPainter {
         }
         self.camera_timestamp = self.camera.timestamp();
 
+        // far from the old origin the f32 translations lose precision, they are rebuilt around the view
+        let origin = self.camera.origin();
+        if origin != self.instances_origin {
+            self.instances_origin = origin;
+            update_instances(&self.instances, &self.placements, origin, context.queue);
+        }
+
         let ortho_matrix = self.camera.clip_matrix();
+        context.queue.write_buffer(&self.common_buffers.transform, 0, bytemuck::cast_slice(&ortho_matrix));
 
-        let staging_buffer = context.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Staging Buffer"),
-            contents: bytemuck::cast_slice(&ortho_matrix),
-            usage: BufferUsages::MAP_WRITE | BufferUsages::COPY_SRC,
-        });
+        // strokes keep their pixel width, a zoom changes only this value
+        let pixel_size = self.camera.convert_size_screen_to_world(1.0);
+        context.queue.write_buffer(&self.common_buffers.pixel_size, 0, bytemuck::bytes_of(&PixelSize::new(pixel_size as f32)));
+    }
+}
+
+impl Painter for GeometryPainter {
+    fn draw(&mut self, context: &mut DrawContext) {
+        self.update_transform_buffer(context);
+        self.update_draws(context);
+        self.overlay_render.update(self.zoom_box, self.camera.screen(), context.queue);
 
         let mut encoder = context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Update Transform Buffer Encoder"),
+            label: Some("Geometry Encoder"),
         });
 
-        encoder.copy_buffer_to_buffer(
-            &staging_buffer,
-            0,
-            &self.common_buffers.transform,
-            0,
-            std::mem::size_of::<[f32; 16]>() as BufferAddress,
-        );
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &context.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            for i in self.document.layers.draw_order() {
+                let buffers = &self.layer_buffers[i];
+                let draws = &self.layer_draws[i];
+                // a hollow layer has only the outline
+                let hollow = self.document.layers[i].brush.pattern == HOLLOW_PATTERN;
+                if let (Some(mesh), false) = (&buffers.fill, hollow) {
+                    self.fill_render.draw(&mut rpass, i, mesh, &draws.fill, &self.instances);
+                }
+                if let Some(mesh) = &buffers.stroke {
+                    self.stroke_render.draw(&mut rpass, i, mesh, &draws.stroke, &self.instances);
+                }
+            }
+            self.overlay_render.draw(&mut rpass);
+        }
 
         context.queue.submit(Some(encoder.finish()));
     }
-}
 
-impl Painter for GeometryPainter {
-    fn draw(&mut self, context: &mut DrawContext) {
-        self.update_transform_buffer(context);
-        let width = self.camera.convert_size_screen_to_world(self.stroke_width);
-
-        let mut clear = true;
-        for layer in self.document.layers.iter_mut() {
-            layer.build_strokes(width);
-            self.fill_render.draw(clear, &layer.fill_mesh, layer.brush, &self.common_buffers, context);
-            self.stroke_render.draw(&layer.stroke_mesh, layer.pencil, &self.common_buffers, context);
-            clear = false;
-        }
+    fn camera_timestamp(&self) -> usize {
+        self.camera.timestamp()
     }
 
     fn update_size(&mut self, size: Size) {
         self.camera.set_screen(size);
     }
 
+    fn animate(&mut self) {
+        // a glide ends somewhere new
+        if self.animation.is_running() && !self.animation.update(&mut self.camera) {
+            self.history.push(self.camera.view_state());
+        }
+    }
+
     fn navigation_event(&mut self, navigation_event: NavigationEvent) {
         match navigation_event {
             NavigationEvent::StartZoom(s) => {
+                self.animation.stop();
                 self.start_zoom = self.camera.zoom();
                 self.camera.set_zoom(self.start_zoom * s.scale, s.cursor);
             }
@@ -120,19 +275,111 @@ impl Painter for GeometryPainter {
             }
             NavigationEvent::EndZoom(e) => {
                 self.camera.set_zoom(self.start_zoom * e.scale, e.cursor);
+                self.history.push(self.camera.view_state());
             }
             NavigationEvent::CancelZoom(e) => {
                 self.camera.set_zoom(self.start_zoom * e.scale, e.cursor);
             }
             NavigationEvent::StartDragged(_e) => {
+                self.animation.stop();
                 self.start_dragged = self.camera.world_position();
+                self.animation.track(self.start_dragged);
+            }
+            NavigationEvent::EndDragged(_e) => {
+                // a glide records the view where it stops
+                self.animation.release(&self.camera);
+                if !self.animation.is_running() {
+                    self.history.push(self.camera.view_state());
+                }
             }
-            NavigationEvent::EndDragged(_e) => {}
             NavigationEvent::MoveDragged(e) => {
                 let screen_delta = e.start - e.current;
                 let world_delta = self.camera.convert_vector_screen_to_world(screen_delta);
                 self.camera.move_to(self.start_dragged + world_delta);
+                self.animation.track(self.camera.world_position());
+            }
+            NavigationEvent::MoveZoomBox(e) => self.zoom_box = Some((e.start, e.current)),
+            NavigationEvent::CancelZoomBox => self.zoom_box = None,
+            NavigationEvent::ZoomRect(e) => {
+                self.zoom_box = None;
+                let from = self.animation.finish(&mut self.camera);
+                let a = self.camera.convert_point_screen_to_world(e.start);
+                let b = self.camera.convert_point_screen_to_world(e.current);
+                let min = Point { x: a.x.min(b.x), y: a.y.min(b.y) };
+                let max = Point { x: a.x.max(b.x), y: a.y.max(b.y) };
+                self.camera.zoom_to_rect(Rect::new(min, max));
+                self.history.push(self.camera.view_state());
+                self.animation.transition(&mut self.camera, from);
+            }
+            NavigationEvent::Pan(screen_delta) => {
+                let from = self.animation.finish(&mut self.camera);
+                let world_delta = self.camera.convert_vector_screen_to_world(screen_delta);
+                self.camera.move_to(self.camera.world_position() + world_delta);
+                // a held arrow key repeats, the run is one entry
+                self.history.step(self.camera.view_state());
+                self.animation.transition(&mut self.camera, from);
+            }
+            NavigationEvent::ZoomStep(e) => {
+                let from = self.animation.finish(&mut self.camera);
+                self.camera.set_zoom(self.camera.zoom() * e.scale, e.cursor);
+                self.history.step(self.camera.view_state());
+                self.animation.transition(&mut self.camera, from);
             }
         }
     }
+
+    fn layer_command(&mut self, command: LayerCommand) -> bool {
+        self.document.layers.apply(command)
+    }
+
+    fn view_command(&mut self, command: ViewCommand) {
+        let from = self.animation.finish(&mut self.camera);
+
+        match command {
+            ViewCommand::Fit => self.camera.zoom_to_rect(Self::fit_rect(&self.document)),
+            ViewCommand::PixelsPerUnit(pixels) => self.camera.set_pixels_per_unit(pixels),
+            ViewCommand::ZoomToCell(index) => {
+                if let Some(rect) = self.document.cell_view(index) {
+                    self.camera.zoom_to_rect(rect);
+                }
+            }
+            ViewCommand::RotateLeft => self.set_orientation(self.camera.orientation().rotated_left()),
+            ViewCommand::RotateRight => self.set_orientation(self.camera.orientation().rotated_right()),
+            ViewCommand::Flip => self.set_orientation(self.camera.orientation().flipped()),
+            ViewCommand::Back => {
+                if let Some(state) = self.history.back() {
+                    self.camera.set_view_state(state);
+                }
+            }
+            ViewCommand::Forward => {
+                if let Some(state) = self.history.forward() {
+                    self.camera.set_view_state(state);
+                }
+            }
+            // the navigation control starts the box and the app keeps the bookmarks
+            ViewCommand::ZoomBox | ViewCommand::AddBookmark | ViewCommand::ShowBookmark(_) => {}
+        }
+
+        // moving through the history must not record it
+        if !matches!(command, ViewCommand::Back | ViewCommand::Forward) {
+            self.history.push(self.camera.view_state());
+        }
+        self.animation.transition(&mut self.camera, from);
+    }
+
+    fn cell_index(&self, name: &str) -> Option<usize> {
+        self.document.cell_index(name)
+    }
+
+    fn bookmark(&self, name: String) -> Bookmark {
+        Bookmark { name, rect: self.camera.view_rect(), hidden: self.document.layers.hidden() }
+    }
+
+    fn show_bookmark(&mut self, bookmark: &Bookmark) -> bool {
+        let from = self.animation.finish(&mut self.camera);
+        self.camera.zoom_to_rect(bookmark.rect);
+        self.history.push(self.camera.view_state());
+        self.animation.transition(&mut self.camera, from);
+        self.document.layers.show_except(&bookmark.hidden)
+    }
 }
\ No newline at end of file
@@ -1,19 +1,50 @@
+use bytemuck::{Pod, Zeroable};
+use i_triangle::i_overlay::i_float::point::IntPoint;
 use i_triangle::triangulation::int::Triangulation;
 
-pub(crate) struct ListMesh {
-    pub(crate) points: Vec<[f32; 2]>,
+pub(crate) struct ListMesh<V = [f32; 2]> {
+    pub(crate) points: Vec<V>,
     pub(crate) indices: Vec<u32>,
 }
 
-impl ListMesh {
+/// A point of a stroke outline, the shader moves it along `normal` by the half width.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub(crate) struct StrokeVertex {
+    pub(crate) position: [f32; 2],
+    /// the offset for a half width of one, longer than one at miter joins
+    pub(crate) normal: [f32; 2],
+}
+
+impl<V> ListMesh<V> {
     pub(crate) fn with_capacity(capacity: usize) -> Self {
         Self { points: Vec::with_capacity(capacity), indices: Vec::with_capacity(3 * capacity) }
     }
 
-    pub(crate) fn append_triangulation(&mut self, triangulation: Triangulation) {
+    pub(crate) fn append(&mut self, mesh: ListMesh<V>) {
         let offset = self.points.len() as u32;
+        self.points.extend(mesh.points);
+        self.indices.extend(mesh.indices.iter().map(|&i| i + offset));
+    }
+}
 
-        self.points.extend(triangulation.points.iter().map(|p| [p.x as f32, p.y as f32]));
+impl ListMesh {
+    pub(crate) fn append_triangulation(&mut self, triangulation: Triangulation, anchor: IntPoint) {
+        let offset = self.points.len() as u32;
+
+        let (x, y) = (anchor.x as i64, anchor.y as i64);
+        self.points.extend(triangulation.points.iter().map(|p| [(p.x as i64 - x) as f32, (p.y as i64 - y) as f32]));
         self.indices.extend(triangulation.indices.iter().map(|&i| i as u32 + offset ));
     }
-}
\ No newline at end of file
+}
+
+impl ListMesh<StrokeVertex> {
+    /// The outline of the given half width, what the stroke shader does on the GPU.
+    pub(crate) fn expanded(&self, r: f32) -> ListMesh {
+        let points = self.points.iter()
+            .map(|v| [v.position[0] + r * v.normal[0], v.position[1] + r * v.normal[1]])
+            .collect();
+
+        ListMesh { points, indices: self.indices.clone() }
+    }
+}
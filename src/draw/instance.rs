@@ -0,0 +1,239 @@
+use std::mem;
+use bytemuck::{Pod, Zeroable};
+use wgpu::{Buffer, BufferAddress, BufferUsages, Device, Queue};
+use wgpu::util::DeviceExt;
+use crate::draw::cell::{Placement, Repetition};
+use crate::geometry::affine::Affine;
+use crate::geometry::point::Point;
+
+/// Per-instance vertex data: cell to world transform.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub(crate) struct InstanceTransform {
+    pub(crate) x_axis: [f32; 2],
+    pub(crate) y_axis: [f32; 2],
+    pub(crate) translate: [f32; 2],
+}
+
+/// Uniform for an array placement, the shader derives column and row from the instance index.
+/// The outer lattice repeats the whole inner one, a single outer place when there is none.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub(crate) struct Lattice {
+    pub(crate) transform: InstanceTransform,
+    pub(crate) column_step: [f32; 2],
+    pub(crate) row_step: [f32; 2],
+    pub(crate) outer_column_step: [f32; 2],
+    pub(crate) outer_row_step: [f32; 2],
+    pub(crate) columns: u32,
+    pub(crate) rows: u32,
+    pub(crate) outer_columns: u32,
+    _padding: u32,
+}
+
+/// All placements of a single cell on the GPU.
+pub(crate) struct CellInstances {
+    /// single placements, one instance per item
+    pub(crate) transforms: Option<Buffer>,
+    pub(crate) count: u32,
+    /// array placements: dynamic offset in the lattice buffer
+    pub(crate) arrays: Vec<u32>,
+}
+
+impl InstanceTransform {
+    pub(crate) const ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+        1 => Float32x2,
+        2 => Float32x2,
+        3 => Float32x2
+    ];
+
+    pub(crate) fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<InstanceTransform>() as BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+
+    fn with_affine(affine: &Affine) -> Self {
+        Self {
+            x_axis: [affine.a as f32, affine.c as f32],
+            y_axis: [affine.b as f32, affine.d as f32],
+            translate: [affine.tx as f32, affine.ty as f32],
+        }
+    }
+}
+
+impl CellInstances {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.count == 0 && self.arrays.is_empty()
+    }
+}
+
+// per cell the single transforms, they are moved to `origin` in f64, so the f32 translations
+// stay small near the view
+fn instance_transforms(placements: &[Vec<Placement>], origin: Point) -> Vec<Vec<InstanceTransform>> {
+    let to_origin = Affine::translation(-origin.x, -origin.y);
+    placements.iter()
+        .map(|cell_placements| cell_placements.iter()
+            .filter(|p| p.repetition.is_none())
+            .map(|p| InstanceTransform::with_affine(&to_origin.multiply(&p.transform)))
+            .collect()
+        )
+        .collect()
+}
+
+// per cell the offsets of its lattices, and the lattice uniforms of all cells moved to `origin`
+fn lattice_data(placements: &[Vec<Placement>], origin: Point, stride: usize) -> (Vec<Vec<u32>>, Vec<u8>) {
+    let to_origin = Affine::translation(-origin.x, -origin.y);
+    let mut lattices: Vec<u8> = Vec::new();
+    let mut arrays = Vec::with_capacity(placements.len());
+
+    for cell_placements in placements.iter() {
+        let mut cell_arrays = Vec::new();
+        for placement in cell_placements.iter() {
+            let Some(repetition) = &placement.repetition else {
+                continue;
+            };
+            let step = |(x, y): (f64, f64)| [x as f32, y as f32];
+            let outer = placement.outer.unwrap_or(Repetition { columns: 1, rows: 1, column_step: (0.0, 0.0), row_step: (0.0, 0.0) });
+            let lattice = Lattice {
+                transform: InstanceTransform::with_affine(&to_origin.multiply(&placement.transform)),
+                column_step: step(repetition.column_step),
+                row_step: step(repetition.row_step),
+                outer_column_step: step(outer.column_step),
+                outer_row_step: step(outer.row_step),
+                columns: repetition.columns,
+                rows: repetition.rows,
+                outer_columns: outer.columns,
+                _padding: 0,
+            };
+            let offset = lattices.len();
+            lattices.extend_from_slice(bytemuck::bytes_of(&lattice));
+            lattices.resize(offset + stride, 0);
+            cell_arrays.push(offset as u32);
+        }
+        arrays.push(cell_arrays);
+    }
+
+    // a binding must always point to a valid lattice
+    if lattices.is_empty() {
+        lattices.resize(stride, 0);
+    }
+
+    (arrays, lattices)
+}
+
+/// Builds instance buffers for every cell and one uniform buffer with all the lattices.
+/// Lattices are placed at `stride` so they can be bound with a dynamic offset.
+pub(crate) fn create_instances(placements: &[Vec<Placement>], origin: Point, stride: usize, device: &Device) -> (Vec<CellInstances>, Buffer) {
+    let transforms = instance_transforms(placements, origin);
+    let (arrays, lattice_bytes) = lattice_data(placements, origin, stride);
+
+    let cells = transforms.iter().zip(arrays)
+        .map(|(transforms, arrays)| {
+            let buffer = if transforms.is_empty() {
+                None
+            } else {
+                Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Instance Buffer"),
+                    contents: bytemuck::cast_slice(transforms),
+                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                }))
+            };
+
+            CellInstances { transforms: buffer, count: transforms.len() as u32, arrays }
+        })
+        .collect();
+
+    let lattices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Lattice Buffer"),
+        contents: &lattice_bytes,
+        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+    });
+
+    (cells, lattices)
+}
+
+/// Moves the single instances to a new origin, the buffers keep their sizes.
+pub(crate) fn update_instances(instances: &[CellInstances], placements: &[Vec<Placement>], origin: Point, queue: &Queue) {
+    for (cell, transforms) in instances.iter().zip(instance_transforms(placements, origin).iter()) {
+        if let Some(buffer) = &cell.transforms {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(transforms));
+        }
+    }
+}
+
+/// Rewrites the lattices, `placements` may cut the arrays to fewer columns and rows.
+pub(crate) fn update_lattices(lattices: &Buffer, placements: &[Vec<Placement>], origin: Point, stride: usize, queue: &Queue) {
+    let (_, bytes) = lattice_data(placements, origin, stride);
+    queue.write_buffer(lattices, 0, &bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+    use i_triangle::i_overlay::i_float::point::IntPoint;
+    use crate::draw::cell::Placement;
+    use crate::draw::instance::{instance_transforms, InstanceTransform, Lattice};
+    use crate::eye::camera::OrthoCamera;
+    use crate::gds::library::STrans;
+    use crate::geometry::affine::Affine;
+    use crate::geometry::point::Point;
+    use crate::geometry::rect::Rect;
+    use crate::geometry::size::Size;
+
+    #[test]
+    fn test_lattice_layout() {
+        // must match the size of `Lattice` in the shaders
+        assert_eq!(mem::size_of::<Lattice>(), 72);
+        assert_eq!(mem::size_of::<InstanceTransform>(), 24);
+    }
+
+    #[test]
+    fn test_instance_transform() {
+        let strans = STrans { angle: 90.0, ..STrans::default() };
+        let t = InstanceTransform::with_affine(&Affine::with_strans(&strans, IntPoint::new(100, 0)));
+
+        // the shader computes x * x_axis + y * y_axis + translate
+        let (x, y) = (10.0, 0.0);
+        let p = [
+            x * t.x_axis[0] + y * t.y_axis[0] + t.translate[0],
+            x * t.x_axis[1] + y * t.y_axis[1] + t.translate[1],
+        ];
+        assert_eq!(p, [100.0, 10.0]);
+    }
+
+    #[test]
+    fn test_precision_at_die_corner() {
+        // a 20 mm die in 1 nm units, flat: the single cell is anchored in its middle
+        let die = Rect::new(Point { x: 0.0, y: 0.0 }, Point { x: 20_000_000.0, y: 20_000_000.0 });
+        let placement = Placement { transform: Affine::translation(10_000_000.0, 10_000_000.0), repetition: None, outer: None };
+        let (vertex, world) = ([9_999_992.0f32, 9_999_995.0f32], Point { x: 19_999_992.0, y: 19_999_995.0 });
+        // f32 world coordinates could not even hold the vertex
+        assert_ne!(world.y as f32 as f64, world.y);
+
+        let screen = Size { width: 800.0, height: 600.0 };
+        let mut camera = OrthoCamera::new(screen, die);
+
+        // the view moves by sub-unit steps at the maximum zoom, a few hundred pixels per unit
+        for i in 0..10 {
+            let center = Point { x: 19_999_992.25 + 0.001 * i as f64, y: 19_999_994.5 - 0.003 * i as f64 };
+            camera.move_to(center);
+            camera.set_zoom(0.0, Point { x: 400.0, y: 300.0 });
+            let pixel = camera.convert_size_screen_to_world(1.0);
+            assert!(pixel < 0.01);
+
+            // what the shaders do in f32
+            let t = instance_transforms(&[vec![placement]], camera.origin())[0][0];
+            let m = camera.clip_matrix();
+            let x = vertex[0] * t.x_axis[0] + vertex[1] * t.y_axis[0] + t.translate[0];
+            let y = vertex[0] * t.x_axis[1] + vertex[1] * t.y_axis[1] + t.translate[1];
+            let clip = ((m[0] * x + m[12]) as f64, (m[5] * y + m[13]) as f64);
+
+            let expected = ((world.x - center.x) / pixel / 400.0, (world.y - center.y) / pixel / 300.0);
+            let error = (400.0 * (clip.0 - expected.0).abs()).max(300.0 * (clip.1 - expected.1).abs());
+            assert!(error < 0.01, "{error} pixels off");
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::ops::Index;
+use log::info;
+use crate::control::layers::LayerCommand;
+use crate::draw::fill::brush::Brush;
+use crate::draw::fill::pattern::{builtin_patterns, FillPattern, MAX_PATTERNS};
+use crate::draw::palette::{layer_brush, layer_pencil};
+use crate::draw::stroke::pencil::Pencil;
+
+pub(crate) struct Layer {
+    pub(crate) layer: u16,
+    pub(crate) datatype: u16,
+    pub(crate) name: String,
+    pub(crate) visible: bool,
+    pub(crate) selectable: bool,
+    /// layers are drawn by increasing order, the later one is on top
+    pub(crate) order: usize,
+    pub(crate) brush: Brush,
+    pub(crate) pencil: Pencil,
+    /// changes with the cell meshes on the layer, the GPU buffers follow it
+    pub(crate) mesh_revision: usize,
+}
+
+impl Layer {
+    /// A visible layer named by its numbers with palette colors of the `index` slot.
+    pub(crate) fn new(layer: u16, datatype: u16, index: usize) -> Self {
+        Self {
+            layer,
+            datatype,
+            name: format!("{layer}/{datatype}"),
+            visible: true,
+            selectable: true,
+            order: index,
+            brush: layer_brush(index),
+            pencil: layer_pencil(index),
+            mesh_revision: 0,
+        }
+    }
+}
+
+/// All layers of a document, cell layers refer to them by index.
+pub(crate) struct LayerTable {
+    layers: Vec<Layer>,
+    indices: HashMap<(u16, u16), usize>,
+    // built-in fill patterns followed by custom ones, brushes refer to them by index
+    patterns: Vec<FillPattern>,
+}
+
+impl LayerTable {
+    pub(crate) fn new(layers: Vec<Layer>) -> Self {
+        let indices = layers.iter()
+            .enumerate()
+            .map(|(i, l)| ((l.layer, l.datatype), i))
+            .collect();
+
+        Self { layers, indices, patterns: builtin_patterns() }
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    #[inline]
+    pub(crate) fn iter(&self) -> impl Iterator<Item=&Layer> {
+        self.layers.iter()
+    }
+
+    #[inline]
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item=&mut Layer> {
+        self.layers.iter_mut()
+    }
+
+    pub(crate) fn index_of(&self, layer: u16, datatype: u16) -> Option<usize> {
+        self.indices.get(&(layer, datatype)).copied()
+    }
+
+    #[inline]
+    pub(crate) fn patterns(&self) -> &[FillPattern] {
+        &self.patterns
+    }
+
+    /// Adds a custom pattern and returns its index, None if the table is full.
+    pub(crate) fn add_pattern(&mut self, pattern: FillPattern) -> Option<u32> {
+        if self.patterns.len() >= MAX_PATTERNS {
+            return None;
+        }
+        self.patterns.push(pattern);
+        Some(self.patterns.len() as u32 - 1)
+    }
+
+    /// Indices of the visible layers, bottom first.
+    pub(crate) fn draw_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.layers.len()).filter(|&i| self.layers[i].visible).collect();
+        order.sort_by_key(|&i| (self.layers[i].order, i));
+        order
+    }
+
+    pub(crate) fn toggle(&mut self, index: usize) -> bool {
+        let Some(layer) = self.layers.get_mut(index) else {
+            return false;
+        };
+        layer.visible = !layer.visible;
+        info!("layer {} is {}", layer.name, if layer.visible { "shown" } else { "hidden" });
+        true
+    }
+
+    pub(crate) fn toggle_selectable(&mut self, index: usize) -> bool {
+        let Some(layer) = self.layers.get_mut(index) else {
+            return false;
+        };
+        layer.selectable = !layer.selectable;
+        info!("layer {} is {}", layer.name, if layer.selectable { "selectable" } else { "locked" });
+        true
+    }
+
+    /// Shows the layer and hides all others.
+    pub(crate) fn isolate(&mut self, index: usize) -> bool {
+        if index >= self.layers.len() {
+            return false;
+        }
+        let mut changed = false;
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            changed |= layer.visible != (i == index);
+            layer.visible = i == index;
+        }
+        changed
+    }
+
+    pub(crate) fn show_all(&mut self) -> bool {
+        let mut changed = false;
+        for layer in self.layers.iter_mut() {
+            changed |= !layer.visible;
+            layer.visible = true;
+        }
+        changed
+    }
+
+    pub(crate) fn hidden(&self) -> Vec<(u16, u16)> {
+        self.layers.iter().filter(|l| !l.visible).map(|l| (l.layer, l.datatype)).collect()
+    }
+
+    /// Hides the listed layers and shows all others.
+    pub(crate) fn show_except(&mut self, hidden: &[(u16, u16)]) -> bool {
+        let mut changed = false;
+        for layer in self.layers.iter_mut() {
+            let visible = !hidden.contains(&(layer.layer, layer.datatype));
+            changed |= layer.visible != visible;
+            layer.visible = visible;
+        }
+        changed
+    }
+
+    /// Returns true if the picture changed.
+    pub(crate) fn apply(&mut self, command: LayerCommand) -> bool {
+        match command {
+            LayerCommand::Toggle(index) => self.toggle(index),
+            LayerCommand::Isolate(index) => self.isolate(index),
+            LayerCommand::ShowAll => self.show_all(),
+            // hit testing is not drawn
+            LayerCommand::ToggleSelectable(index) => {
+                self.toggle_selectable(index);
+                false
+            }
+        }
+    }
+}
+
+impl Index<usize> for LayerTable {
+    type Output = Layer;
+
+    #[inline]
+    fn index(&self, index: usize) -> &Layer {
+        &self.layers[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::draw::layer::{Layer, LayerTable};
+
+    #[test]
+    fn test_visibility() {
+        let mut table = LayerTable::new(vec![
+            Layer::new(1, 0, 0),
+            Layer { order: 5, ..Layer::new(2, 0, 1) },
+            Layer { order: 2, ..Layer::new(2, 5, 2) },
+        ]);
+
+        assert_eq!(table.index_of(2, 5), Some(2));
+        assert_eq!(table.index_of(3, 0), None);
+        assert_eq!(table[1].name, "2/0");
+        assert_eq!(table.draw_order(), vec![0, 2, 1]);
+
+        assert!(table.toggle(2));
+        assert_eq!(table.draw_order(), vec![0, 1]);
+
+        assert!(table.isolate(1));
+        assert_eq!(table.draw_order(), vec![1]);
+        assert!(!table.isolate(1));
+        assert!(!table.isolate(7));
+
+        assert!(table.show_all());
+        assert!(!table.show_all());
+        assert_eq!(table.draw_order(), vec![0, 2, 1]);
+
+        assert!(table.show_except(&[(2, 0), (9, 9)]));
+        assert_eq!(table.hidden(), vec![(2, 0)]);
+        assert_eq!(table.draw_order(), vec![0, 2]);
+        assert!(!table.show_except(&[(2, 0)]));
+        assert!(table.show_all());
+
+        assert!(table.toggle_selectable(0));
+        assert!(!table[0].selectable);
+        assert!(table[0].visible);
+    }
+}
@@ -2,9 +2,16 @@ pub(crate) mod painter;
 pub(crate) mod geometry;
 pub(crate) mod fill;
 pub(crate) mod context;
-mod document;
+pub(crate) mod document;
+pub(crate) mod layer;
+pub(crate) mod cell;
 mod buffers;
+mod instance;
 mod stroke;
+mod overlay;
 pub mod index_mesh;
-mod triangulation;
-
+pub(crate) mod triangulation;
+pub(crate) mod palette;
+pub(crate) mod png;
+pub(crate) mod raster;
+pub(crate) mod visibility;
\ No newline at end of file
@@ -0,0 +1 @@
+pub(crate) mod render;
\ No newline at end of file
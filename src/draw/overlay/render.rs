@@ -0,0 +1,152 @@
+use std::mem;
+use bytemuck::{Pod, Zeroable};
+use wgpu::{Buffer, BufferAddress, BufferUsages, ColorTargetState, Device, Queue, RenderPass, RenderPipeline};
+use crate::geometry::point::Point;
+use crate::geometry::size::Size;
+
+// the zoom box outline width in pixels and its colors
+const BOX_LINE: f64 = 1.0;
+const BOX_LINE_COLOR: [f32; 4] = [0.1, 0.3, 0.9, 1.0];
+const BOX_FILL_COLOR: [f32; 4] = [0.1, 0.3, 0.9, 0.15];
+// the fill and four lines, two triangles each
+const BOX_VERTICES: usize = 30;
+
+/// A vertex of the screen overlay, the position is in clip coordinates.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub(crate) struct OverlayVertex {
+    pub(crate) position: [f32; 2],
+    pub(crate) color: [f32; 4],
+}
+
+/// Draws what lies over the layout in screen space, the zoom box while it is dragged.
+pub(crate) struct OverlayRender {
+    pipeline: RenderPipeline,
+    vertex: Buffer,
+    count: u32,
+}
+
+impl OverlayRender {
+    pub(crate) fn new(color: &ColorTargetState, device: &Device) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Overlay Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: mem::size_of::<OverlayVertex>() as BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format: color.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let vertex = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Overlay Vertex Buffer"),
+            size: (BOX_VERTICES * mem::size_of::<OverlayVertex>()) as BufferAddress,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self { pipeline, vertex, count: 0 }
+    }
+
+    /// Shows the zoom box between two screen points, None hides it.
+    pub(crate) fn update(&mut self, zoom_box: Option<(Point, Point)>, screen: Size, queue: &Queue) {
+        let vertices = zoom_box.map_or(Vec::new(), |(a, b)| box_vertices(a, b, screen));
+        if !vertices.is_empty() {
+            queue.write_buffer(&self.vertex, 0, bytemuck::cast_slice(&vertices));
+        }
+        self.count = vertices.len() as u32;
+    }
+
+    pub(crate) fn draw<'a>(&'a self, rpass: &mut RenderPass<'a>) {
+        if self.count == 0 {
+            return;
+        }
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_vertex_buffer(0, self.vertex.slice(..));
+        rpass.draw(0..self.count, 0..1);
+    }
+}
+
+/// A filled box with an outline between two screen points, the lines lie inside the box.
+pub(crate) fn box_vertices(a: Point, b: Point, screen: Size) -> Vec<OverlayVertex> {
+    let (x0, x1) = (a.x.min(b.x), a.x.max(b.x));
+    let (y0, y1) = (a.y.min(b.y), a.y.max(b.y));
+    let w = BOX_LINE.min(0.5 * (x1 - x0));
+    let h = BOX_LINE.min(0.5 * (y1 - y0));
+
+    let rects = [
+        (x0, y0, x1, y1, BOX_FILL_COLOR),
+        (x0, y0, x1, y0 + h, BOX_LINE_COLOR),
+        (x0, y1 - h, x1, y1, BOX_LINE_COLOR),
+        (x0, y0 + h, x0 + w, y1 - h, BOX_LINE_COLOR),
+        (x1 - w, y0 + h, x1, y1 - h, BOX_LINE_COLOR),
+    ];
+
+    // screen y goes down, clip y goes up
+    let clip = |x: f64, y: f64, color: [f32; 4]| OverlayVertex {
+        position: [(2.0 * x / screen.width - 1.0) as f32, (1.0 - 2.0 * y / screen.height) as f32],
+        color,
+    };
+
+    let mut vertices = Vec::with_capacity(BOX_VERTICES);
+    for (x0, y0, x1, y1, color) in rects {
+        vertices.extend([
+            clip(x0, y0, color), clip(x1, y0, color), clip(x1, y1, color),
+            clip(x0, y0, color), clip(x1, y1, color), clip(x0, y1, color),
+        ]);
+    }
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::draw::overlay::render::{box_vertices, BOX_VERTICES};
+    use crate::geometry::point::Point;
+    use crate::geometry::size::Size;
+
+    #[test]
+    fn test_box_vertices() {
+        let screen = Size { width: 200.0, height: 100.0 };
+        let vertices = box_vertices(Point { x: 150.0, y: 75.0 }, Point { x: 50.0, y: 25.0 }, screen);
+        assert_eq!(vertices.len(), BOX_VERTICES);
+
+        // the fill covers the box from the top left corner to the bottom right one
+        assert_eq!(vertices[0].position, [-0.5, 0.5]);
+        assert_eq!(vertices[2].position, [0.5, -0.5]);
+
+        // the top line is one pixel high
+        assert_eq!(vertices[6].position, [-0.5, 0.5]);
+        assert_eq!(vertices[8].position, [0.5, 0.48]);
+    }
+}
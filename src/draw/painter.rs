@@ -1,12 +1,28 @@
+use crate::control::layers::LayerCommand;
 use crate::control::navigation::NavigationEvent;
+use crate::control::view::ViewCommand;
 use crate::draw::context::DrawContext;
 use crate::draw::geometry::GeometryPainter;
+use crate::eye::bookmark::Bookmark;
 use crate::geometry::size::Size;
 
 pub(crate) trait Painter {
     fn draw(&mut self, context: &mut DrawContext);
+    /// changes every time the view moves
+    fn camera_timestamp(&self) -> usize;
     fn update_size(&mut self, size: Size);
+    /// moves an animated view on to the current time
+    fn animate(&mut self);
     fn navigation_event(&mut self, navigation_event: NavigationEvent);
+    /// true if the picture changed
+    fn layer_command(&mut self, command: LayerCommand) -> bool;
+    fn view_command(&mut self, command: ViewCommand);
+    /// the index of the cell called `name` for `ViewCommand::ZoomToCell`
+    fn cell_index(&self, name: &str) -> Option<usize>;
+    /// the shown rect and layers under `name`
+    fn bookmark(&self, name: String) -> Bookmark;
+    /// true if the layers changed, the camera has its own timestamp
+    fn show_bookmark(&mut self, bookmark: &Bookmark) -> bool;
 }
 
 pub(crate) enum PainterLibrary {
@@ -22,6 +38,12 @@ impl Painter for PainterLibrary {
         }
     }
 
+    fn camera_timestamp(&self) -> usize {
+        match self {
+            PainterLibrary::Geometry(painter) => painter.camera_timestamp(),
+        }
+    }
+
     fn update_size(&mut self, size: Size) {
         match self {
             PainterLibrary::Geometry(painter) => {
@@ -30,6 +52,12 @@ impl Painter for PainterLibrary {
         }
     }
 
+    fn animate(&mut self) {
+        match self {
+            PainterLibrary::Geometry(painter) => painter.animate(),
+        }
+    }
+
     fn navigation_event(&mut self, navigation_event: NavigationEvent) {
         match self {
             PainterLibrary::Geometry(painter) => {
@@ -37,4 +65,34 @@ impl Painter for PainterLibrary {
             }
         }
     }
+
+    fn layer_command(&mut self, command: LayerCommand) -> bool {
+        match self {
+            PainterLibrary::Geometry(painter) => painter.layer_command(command),
+        }
+    }
+
+    fn view_command(&mut self, command: ViewCommand) {
+        match self {
+            PainterLibrary::Geometry(painter) => painter.view_command(command),
+        }
+    }
+
+    fn cell_index(&self, name: &str) -> Option<usize> {
+        match self {
+            PainterLibrary::Geometry(painter) => painter.cell_index(name),
+        }
+    }
+
+    fn bookmark(&self, name: String) -> Bookmark {
+        match self {
+            PainterLibrary::Geometry(painter) => painter.bookmark(name),
+        }
+    }
+
+    fn show_bookmark(&mut self, bookmark: &Bookmark) -> bool {
+        match self {
+            PainterLibrary::Geometry(painter) => painter.show_bookmark(bookmark),
+        }
+    }
 }
\ No newline at end of file
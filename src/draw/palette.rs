@@ -0,0 +1,38 @@
+use crate::draw::fill::brush::Brush;
+use crate::draw::fill::pattern::SOLID_PATTERN;
+use crate::draw::stroke::pencil::Pencil;
+
+const COLORS: [[f32; 3]; 12] = [
+    [0.90, 0.10, 0.10],
+    [0.10, 0.30, 0.90],
+    [0.10, 0.65, 0.20],
+    [0.95, 0.55, 0.05],
+    [0.60, 0.20, 0.80],
+    [0.05, 0.70, 0.75],
+    [0.85, 0.20, 0.60],
+    [0.55, 0.45, 0.10],
+    [0.35, 0.35, 0.35],
+    [0.70, 0.75, 0.05],
+    [0.40, 0.15, 0.15],
+    [0.15, 0.40, 0.55],
+];
+
+// hatches and dots of the built-in set, the cycle is shorter than the colors one,
+// so neighbour layers differ in both
+const PATTERNS: [u32; 8] = [4, 8, 12, 2, 5, 9, 13, 3];
+
+pub(crate) fn layer_brush(index: usize) -> Brush {
+    let [red, green, blue] = COLORS[index % COLORS.len()];
+    let pattern = PATTERNS[index % PATTERNS.len()];
+    Brush::new(red, green, blue, pattern_alpha(pattern)).with_pattern(pattern)
+}
+
+/// A solid fill is see-through, a pattern leaves gaps and can be denser.
+pub(crate) fn pattern_alpha(pattern: u32) -> f32 {
+    if pattern == SOLID_PATTERN { 0.2 } else { 0.6 }
+}
+
+pub(crate) fn layer_pencil(index: usize) -> Pencil {
+    let [red, green, blue] = COLORS[index % COLORS.len()];
+    Pencil::new(red, green, blue, 1.0)
+}
@@ -0,0 +1,125 @@
+// Minimal PNG encoder: 8-bit RGBA, no filtering, deflate with stored blocks only.
+// The output is bigger than a real compressor gives, but it is exact and has no dependencies.
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+// a stored deflate block holds at most 0xFFFF bytes
+const MAX_STORED_BLOCK: usize = 0xFFFF;
+
+/// Encodes `pixels` (RGBA, row by row from the top) of a `width` x `height` image.
+pub(crate) fn encode_rgba(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    debug_assert_eq!(pixels.len(), 4 * width as usize * height as usize);
+
+    let mut png = Vec::with_capacity(pixels.len() + pixels.len() / MAX_STORED_BLOCK * 5 + 128);
+    png.extend_from_slice(&SIGNATURE);
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // bit depth 8, color type 6 (RGBA), compression 0, filter 0, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+
+    // every row starts with its filter type, 0 is none
+    let row = 4 * width as usize;
+    let mut raw = Vec::with_capacity(pixels.len() + height as usize);
+    for line in pixels.chunks_exact(row.max(1)).take(height as usize) {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    }
+
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_STORED_BLOCK * 5 + 11);
+    // deflate, 32K window, no preset dictionary, header checksum makes it a multiple of 31
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let length = block.len() as u16;
+        out.push(if last { 1 } else { 0 });
+        out.extend_from_slice(&length.to_le_bytes());
+        out.extend_from_slice(&(!length).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data.iter() {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest chunk that can not overflow b
+    for chunk in data.chunks(5552) {
+        for &byte in chunk.iter() {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::draw::png::{adler32, crc32, encode_rgba, zlib_stored};
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn test_stored_blocks() {
+        let data = vec![7u8; 70_000];
+        let z = zlib_stored(&data);
+        assert_eq!((z[0] as u16 * 256 + z[1] as u16) % 31, 0);
+        // two blocks, the first one full and not final
+        assert_eq!(&z[2..7], &[0, 0xFF, 0xFF, 0, 0]);
+        let second = 2 + 5 + 0xFFFF;
+        assert_eq!(z[second], 1);
+        assert_eq!(z.len(), 2 + 2 * 5 + 70_000 + 4);
+    }
+
+    #[test]
+    fn test_encode() {
+        let png = encode_rgba(2, 1, &[255, 0, 0, 255, 0, 0, 255, 128]);
+        assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[png.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+    }
+}
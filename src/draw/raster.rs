@@ -0,0 +1,304 @@
+use crate::draw::document::Document;
+use crate::draw::fill::pattern::FillPattern;
+use crate::draw::index_mesh::ListMesh;
+use crate::geometry::affine::Affine;
+use crate::geometry::rect::Rect;
+use crate::geometry::size::Size;
+
+/// RGBA image rendered on the CPU, rows go from the top.
+pub(crate) struct Canvas {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        // cleared to white like the render pass
+        Self { width, height, pixels: vec![255; 4 * width as usize * height as usize] }
+    }
+
+    /// Fills a triangle given in pixel coordinates with `color` (straight alpha).
+    /// Pixel centers on a shared edge belong to one triangle only, so meshes do not blend twice.
+    fn fill_triangle(&mut self, a: (f64, f64), b: (f64, f64), c: (f64, f64), color: [f32; 4], pattern: Option<(&FillPattern, f32)>) {
+        let area = cross(a, b, c);
+        if area == 0.0 {
+            return;
+        }
+        let (b, c) = if area < 0.0 { (c, b) } else { (b, c) };
+
+        let min_x = a.0.min(b.0).min(c.0).floor().max(0.0) as i64;
+        let max_x = a.0.max(b.0).max(c.0).ceil().min(self.width as f64) as i64;
+        let min_y = a.1.min(b.1).min(c.1).floor().max(0.0) as i64;
+        let max_y = a.1.max(b.1).max(c.1).ceil().min(self.height as f64) as i64;
+
+        let edges = [(b, c), (c, a), (a, b)];
+        let owned = edges.map(|(p, q)| is_top_left(p, q));
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = (x as f64 + 0.5, y as f64 + 0.5);
+                let inside = edges.iter().zip(owned.iter()).all(|(&(e0, e1), &owned)| {
+                    let w = cross(e0, e1, p);
+                    w > 0.0 || w == 0.0 && owned
+                });
+                // the same screen space lookup as the fill shader
+                let drawn = pattern.is_none_or(|(pattern, scale)| pattern.is_set(
+                    (x as f32 / scale).floor() as usize,
+                    (y as f32 / scale).floor() as usize,
+                ));
+                if inside && drawn {
+                    self.blend(x as usize, y as usize, color);
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: [f32; 4]) {
+        let i = 4 * (y * self.width as usize + x);
+        let alpha = color[3];
+        for (k, &channel) in color[..3].iter().enumerate() {
+            let dst = self.pixels[i + k] as f32 / 255.0;
+            self.pixels[i + k] = to_byte(channel * alpha + dst * (1.0 - alpha));
+        }
+        let dst = self.pixels[i + 3] as f32 / 255.0;
+        self.pixels[i + 3] = to_byte(alpha + dst * (1.0 - alpha));
+    }
+
+    fn fill_mesh(&mut self, mesh: &ListMesh, transform: &Affine, color: [f32; 4], pattern: Option<(&FillPattern, f32)>) {
+        let points: Vec<(f64, f64)> = mesh.points.iter()
+            .map(|p| transform.transform(p[0] as f64, p[1] as f64))
+            .collect();
+
+        let (width, height) = (self.width as f64, self.height as f64);
+        for triangle in mesh.indices.chunks_exact(3) {
+            let a = points[triangle[0] as usize];
+            let b = points[triangle[1] as usize];
+            let c = points[triangle[2] as usize];
+            // a triangle off the image covers no pixel
+            let outside = a.0.max(b.0).max(c.0) < 0.0 || a.0.min(b.0).min(c.0) > width
+                || a.1.max(b.1).max(c.1) < 0.0 || a.1.min(b.1).min(c.1) > height;
+            if !outside {
+                self.fill_triangle(a, b, c, color, pattern);
+            }
+        }
+    }
+}
+
+/// Renders the document the way the window does: `view` in world coordinates is fitted
+/// into `width` x `height` pixels and centered, visible layers are drawn in order, fill then outline.
+pub(crate) fn render(document: &Document, view: &Rect, width: u32, height: u32) -> Canvas {
+    let mut canvas = Canvas::new(width, height);
+    if width == 0 || height == 0 {
+        return canvas;
+    }
+
+    // world units per pixel, the larger side of the view fits the image
+    let scale = (view.size.width / width as f64).max(view.size.height / height as f64);
+    let scale = if scale > 0.0 { scale } else { 1.0 };
+
+    // world to pixels, y goes down in the image
+    let world_to_pixel = Affine {
+        a: 1.0 / scale,
+        b: 0.0,
+        c: 0.0,
+        d: -1.0 / scale,
+        tx: 0.5 * width as f64 - view.center.x / scale,
+        ty: 0.5 * height as f64 + view.center.y / scale,
+    };
+
+    // the world under the image, grown by the widest outline
+    let width_in_pixels = document.layers.iter().map(|l| l.pencil.width as f64).fold(0.0, f64::max);
+    let image = Rect { center: view.center, size: Size { width: width as f64 * scale, height: height as f64 * scale } };
+    let visible = image.int_rect(width_in_pixels * scale);
+
+    // only the columns and rows of an array that reach the image are unrolled
+    let placements = document.mesh_placements();
+    let transforms: Vec<Vec<Affine>> = placements.iter().zip(document.cells.iter())
+        .map(|(cell_placements, cell)| {
+            let Some(rect) = cell.mesh_rect() else {
+                return Vec::new();
+            };
+            let mut transforms = Vec::new();
+            for placement in cell_placements.iter().filter_map(|p| p.clipped(&rect, &visible)) {
+                transforms.extend((0..placement.count() as u32).map(|i| world_to_pixel.multiply(&placement.transform_at(i))));
+            }
+            transforms
+        })
+        .collect();
+
+    for index in document.layers.draw_order() {
+        let layer = &document.layers[index];
+        let brush = [layer.brush.red, layer.brush.green, layer.brush.blue, layer.brush.alpha];
+        let pencil = [layer.pencil.red, layer.pencil.green, layer.pencil.blue, layer.pencil.alpha];
+        let pattern = document.layers.patterns().get(layer.brush.pattern as usize).map(|p| (p, layer.brush.pattern_scale));
+
+        for (cell, cell_transforms) in document.cells.iter().zip(transforms.iter()) {
+            for cell_layer in cell.layers.iter().filter(|l| l.layer == index) {
+                for transform in cell_transforms.iter() {
+                    canvas.fill_mesh(&cell_layer.fill_mesh, transform, brush, pattern);
+                }
+            }
+        }
+
+        for (cell, cell_transforms) in document.cells.iter().zip(transforms.iter()) {
+            for cell_layer in cell.layers.iter().filter(|l| l.layer == index) {
+                if cell_transforms.is_empty() {
+                    continue;
+                }
+                // the width is in pixels whatever the magnification of the placement
+                let mut stroke_mesh: Option<(f64, ListMesh)> = None;
+                for transform in cell_transforms.iter() {
+                    let r = 0.5 * layer.pencil.width as f64 / transform.scale();
+                    if stroke_mesh.as_ref().is_none_or(|(last, _)| *last != r) {
+                        stroke_mesh = Some((r, cell_layer.stroke_mesh.expanded(r as f32)));
+                    }
+                    if let Some((_, mesh)) = &stroke_mesh {
+                        canvas.fill_mesh(mesh, transform, pencil, None);
+                    }
+                }
+            }
+        }
+    }
+
+    canvas
+}
+
+#[inline]
+fn cross(a: (f64, f64), b: (f64, f64), p: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+// for a triangle with positive area in y-down coordinates
+#[inline]
+fn is_top_left(p: (f64, f64), q: (f64, f64)) -> bool {
+    let top = p.1 == q.1 && q.0 > p.0;
+    let left = q.1 < p.1;
+    top || left
+}
+
+#[inline]
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use i_triangle::i_overlay::i_float::point::IntPoint;
+    use crate::draw::document::Document;
+    use crate::draw::png::encode_rgba;
+    use crate::draw::raster::{render, Canvas};
+    use crate::gds::library::{ArrayRef, Boundary, Element, Library, STrans, StructRef, Structure, Units};
+    use crate::gds::reader::tests::{read_strict, sample_stream};
+    use crate::geometry::point::Point;
+    use crate::geometry::rect::Rect;
+
+    fn pixel(canvas: &Canvas, x: u32, y: u32) -> [u8; 4] {
+        let i = 4 * (y * canvas.width + x) as usize;
+        canvas.pixels[i..i + 4].try_into().unwrap()
+    }
+
+    // compares with `tests/golden/<name>`, run with UPDATE_GOLDEN=1 to accept a new picture
+    fn assert_golden(name: &str, png: &[u8]) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, png).unwrap();
+            return;
+        }
+        let golden = std::fs::read(&path).unwrap_or_else(|_| panic!("missing golden image {}", path.display()));
+        assert!(golden == png, "{} differs from the rendered image", path.display());
+    }
+
+    #[test]
+    fn test_fill_colors() {
+        let document = Document::polygons();
+        let view = Rect::new(Point { x: 0.0, y: 0.0 }, Point { x: 8.0, y: 4.0 });
+        let canvas = render(&document, &view, 80, 40);
+
+        // the middle of the plus is covered by the first layer only
+        let brush = &document.layers[0].brush;
+        let expected = [brush.red, brush.green, brush.blue].map(|c| (255.0 * (c * brush.alpha + 1.0 - brush.alpha)).round() as u8);
+        assert_eq!(pixel(&canvas, 15, 25)[..3], expected);
+
+        // the hole of the tor stays white
+        assert_eq!(pixel(&canvas, 55, 24), [255; 4]);
+    }
+
+    #[test]
+    fn test_magnified_stroke() {
+        // the same square placed once as it is and once twice as large
+        let square = vec![IntPoint::new(0, 0), IntPoint::new(0, 10), IntPoint::new(10, 10), IntPoint::new(10, 0), IntPoint::new(0, 0)];
+        let reference = |x: i32, magnification: f64| Element::StructRef(StructRef {
+            name: "SQUARE".to_string(),
+            strans: STrans { magnification, ..STrans::default() },
+            position: IntPoint::new(x, 0),
+        });
+        let library = Library {
+            name: "MAG".to_string(),
+            units: Units::default(),
+            structures: vec![
+                Structure { name: "SQUARE".to_string(), elements: vec![Element::Boundary(Boundary { layer: 1, datatype: 0, points: square })] },
+                Structure { name: "TOP".to_string(), elements: vec![reference(0, 1.0), reference(20, 2.0)] },
+            ],
+        };
+        let document = Document::with_library(&library);
+        let view = Rect::new(Point { x: -5.0, y: -15.0 }, Point { x: 45.0, y: 35.0 });
+        // two pixels per unit
+        let canvas = render(&document, &view, 100, 100);
+
+        // the left edges of both squares on the row at y = 5
+        let pencil = &document.layers[0].pencil;
+        let color = [pencil.red, pencil.green, pencil.blue].map(|c| (255.0 * c).round() as u8);
+        let edge = |from: u32| (from..100).skip_while(|&x| pixel(&canvas, x, 60)[..3] != color)
+            .take_while(|&x| pixel(&canvas, x, 60)[..3] == color)
+            .count();
+        assert_eq!(edge(0), pencil.width as usize);
+        assert_eq!(edge(40), pencil.width as usize);
+    }
+
+    #[test]
+    fn test_huge_array_view() {
+        // 50000 x 50000 squares of 10 at a pitch of 20, far too many to unroll
+        let square = vec![IntPoint::new(0, 0), IntPoint::new(0, 10), IntPoint::new(10, 10), IntPoint::new(10, 0), IntPoint::new(0, 0)];
+        let array = Element::ArrayRef(ArrayRef {
+            name: "SQUARE".to_string(),
+            strans: STrans::default(),
+            columns: 50_000,
+            rows: 50_000,
+            position: IntPoint::new(0, 0),
+            column_point: IntPoint::new(1_000_000, 0),
+            row_point: IntPoint::new(0, 1_000_000),
+        });
+        let library = Library {
+            name: "HUGE".to_string(),
+            units: Units::default(),
+            structures: vec![
+                Structure { name: "SQUARE".to_string(), elements: vec![Element::Boundary(Boundary { layer: 1, datatype: 0, points: square })] },
+                Structure { name: "TOP".to_string(), elements: vec![array] },
+            ],
+        };
+        let document = Document::with_library(&library);
+
+        // two pixels per unit over the square at (400000, 600000) and its neighbours
+        let view = Rect::new(Point { x: 399_995.0, y: 599_995.0 }, Point { x: 400_045.0, y: 600_045.0 });
+        let canvas = render(&document, &view, 100, 100);
+
+        let brush = &document.layers[0].brush;
+        let expected = [brush.red, brush.green, brush.blue].map(|c| (255.0 * (c * brush.alpha + 1.0 - brush.alpha)).round() as u8);
+        // inside the squares at x 400000 and 400020, between them and between the rows
+        assert_eq!(pixel(&canvas, 20, 80)[..3], expected);
+        assert_eq!(pixel(&canvas, 60, 80)[..3], expected);
+        assert_eq!(pixel(&canvas, 40, 80), [255; 4]);
+        assert_eq!(pixel(&canvas, 20, 60), [255; 4]);
+    }
+
+    #[test]
+    fn test_golden_sample() {
+        let library = read_strict(&sample_stream()).unwrap();
+        let document = Document::with_library(&library);
+        let canvas = render(&document, &document.rect, 160, 120);
+        assert_golden("sample.png", &encode_rgba(canvas.width, canvas.height, &canvas.pixels));
+    }
+}
@@ -7,6 +7,16 @@ pub struct Pencil {
     pub green: f32,
     pub blue: f32,
     pub alpha: f32,
+    /// outline width in pixels, it does not change with the zoom
+    pub width: f32,
+    // uniforms are aligned to 16 bytes
+    _padding: [f32; 3],
+}
+
+impl Pencil {
+    pub(crate) fn new(red: f32, green: f32, blue: f32, alpha: f32) -> Self {
+        Self { red, green, blue, alpha, ..Self::default() }
+    }
 }
 
 impl Default for Pencil {
@@ -15,7 +25,9 @@ impl Default for Pencil {
             red: 1.0,
             green: 1.0,
             blue: 1.0,
-            alpha: 1.0
+            alpha: 1.0,
+            width: 2.0,
+            _padding: [0.0; 3],
         }
     }
-}
\ No newline at end of file
+}
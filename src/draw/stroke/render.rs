@@ -1,25 +1,36 @@
 use std::mem;
-use wgpu::{BindGroup, Buffer, BufferAddress, BufferBindingType, BufferUsages, ColorTargetState, Face, RenderPipeline, ShaderStages};
+use wgpu::{BindGroup, BindGroupLayout, BufferAddress, BufferBindingType, BufferSize, BufferUsages, ColorTargetState, RenderPass, RenderPipeline, ShaderModule, ShaderStages};
 use wgpu::Device;
 use wgpu::util::DeviceExt;
-use crate::draw::buffers::GeometryCommonBuffers;
-use crate::draw::context::DrawContext;
-use crate::draw::index_mesh::ListMesh;
+use crate::draw::buffers::{GeometryCommonBuffers, LayerMesh};
+use crate::draw::index_mesh::StrokeVertex;
+use crate::draw::instance::{CellInstances, InstanceTransform};
 use crate::draw::stroke::pencil::Pencil;
+use crate::draw::visibility::PartDraw;
 
 pub(crate) struct StrokeRender {
-    pencil_buffer: Buffer,
+    uniform_stride: usize,
     bind_group: BindGroup,
+    // single placements read the transform from the instance buffer
     pipeline: RenderPipeline,
+    // array placements read it from the lattice uniform
+    array_pipeline: RenderPipeline,
 }
 
 impl StrokeRender {
-    pub(crate) fn new(color: &ColorTargetState, common_buffers: &GeometryCommonBuffers, device: &Device) -> Self {
-        let pencil_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+    pub(crate) fn new(color: &ColorTargetState, pencils: &[Pencil], common_buffers: &GeometryCommonBuffers, device: &Device) -> Self {
+        let uniform_stride = common_buffers.uniform_stride;
+        let mut pencil_data = vec![0u8; uniform_stride * pencils.len().max(1)];
+        for (i, pencil) in pencils.iter().enumerate() {
+            let offset = i * uniform_stride;
+            pencil_data[offset..offset + mem::size_of::<Pencil>()].copy_from_slice(bytemuck::bytes_of(pencil));
+        }
+
+        // one pencil per layer, the bind group keeps the buffer alive
+        let pencil_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Pencil Buffer"),
-            size: mem::size_of::<Pencil>() as BufferAddress,
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST | BufferUsages::VERTEX,
-            mapped_at_creation: false,
+            contents: &pencil_data,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
 
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -37,7 +48,27 @@ impl StrokeRender {
                 },
                 wgpu::BindGroupLayoutEntry {
                     binding: 1,
-                    visibility: ShaderStages::FRAGMENT,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: BufferSize::new(mem::size_of::<Pencil>() as u64),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: BufferSize::new(GeometryCommonBuffers::lattice_size()),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::VERTEX,
                     ty: wgpu::BindingType::Buffer {
                         ty: BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -58,63 +89,81 @@ impl StrokeRender {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: pencil_buffer.as_entire_binding(),
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &pencil_buffer,
+                        offset: 0,
+                        size: BufferSize::new(mem::size_of::<Pencil>() as u64),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &common_buffers.lattice,
+                        offset: 0,
+                        size: BufferSize::new(GeometryCommonBuffers::lattice_size()),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: common_buffers.pixel_size.as_entire_binding(),
                 },
             ],
         });
 
         let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
 
+        let pipeline = Self::create_pipeline(color, &bind_group_layout, &shader, "vs_main", true, device);
+        let array_pipeline = Self::create_pipeline(color, &bind_group_layout, &shader, "vs_array", false, device);
+
+        Self { uniform_stride, bind_group, pipeline, array_pipeline }
+    }
+
+    fn create_pipeline(color: &ColorTargetState, bind_group_layout: &BindGroupLayout, shader: &ShaderModule, entry_point: &str, instanced: bool, device: &Device) -> RenderPipeline {
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[&bind_group_layout],
+            bind_group_layouts: &[bind_group_layout],
             push_constant_ranges: &[],
         });
 
-        let color_target_state = ColorTargetState {
-            format: color.format,
-            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-            write_mask: wgpu::ColorWrites::ALL,
+        let vertex_layout = wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<StrokeVertex>() as BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                // the instance transform takes the locations 1 to 3
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+            ],
+        };
+
+        let buffers = if instanced {
+            vec![vertex_layout, InstanceTransform::layout()]
+        } else {
+            vec![vertex_layout]
         };
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: None,
             layout: Some(&pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
+                module: shader,
+                entry_point,
                 compilation_options: Default::default(),
-                buffers: &[
-                    wgpu::VertexBufferLayout {
-                        array_stride: mem::size_of::<[f32; 2]>() as BufferAddress,
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &[
-                            wgpu::VertexAttribute {
-                                offset: 0,
-                                shader_location: 0,
-                                format: wgpu::VertexFormat::Float32x2,
-                            },
-                        ],
-                    },
-                    wgpu::VertexBufferLayout {
-                        array_stride: mem::size_of::<u32>() as BufferAddress,
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &[
-                            wgpu::VertexAttribute {
-                                offset: 0,
-                                shader_location: 1,
-                                format: wgpu::VertexFormat::Uint32,
-                            },
-                        ],
-                    },
-                ],
+                buffers: &buffers,
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
+                module: shader,
                 entry_point: "fs_main",
                 compilation_options: Default::default(),
                 targets: &[Some(ColorTargetState {
-                    format: color_target_state.format,
+                    format: color.format,
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -123,7 +172,8 @@ impl StrokeRender {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Cw,
-                cull_mode: Some(Face::Back),
+                // mirrored placements flip the winding
+                cull_mode: None,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 unclipped_depth: false,
                 conservative: false,
@@ -135,92 +185,40 @@ impl StrokeRender {
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
-        });
-
-        Self { pencil_buffer, bind_group, pipeline }
+        })
     }
 
-    pub(crate) fn draw(&self, mesh: &ListMesh, pencil: Pencil, buffers: &GeometryCommonBuffers, context: &DrawContext) {
-
-        // update_buffers
-
-        let vertex_staging_buffer = context.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Staging Buffer"),
-            contents: bytemuck::cast_slice(&mesh.points),
-            usage: BufferUsages::MAP_WRITE | BufferUsages::COPY_SRC,
-        });
-
-        let index_staging_buffer = context.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Staging Buffer"),
-            contents: bytemuck::cast_slice(&mesh.indices),
-            usage: BufferUsages::MAP_WRITE | BufferUsages::COPY_SRC,
-        });
-
-        let staging_brush_buffer = context.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Staging Brush Buffer"),
-            contents: bytemuck::cast_slice(&[pencil]),
-            usage: BufferUsages::MAP_WRITE | BufferUsages::COPY_SRC,
-        });
-
-        let mut encoder = context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Update Stroke Buffers Encoder"),
-        });
-
-        encoder.copy_buffer_to_buffer(
-            &vertex_staging_buffer,
-            0,
-            &buffers.vertex,
-            0,
-            (mesh.points.len() * mem::size_of::<[f32; 2]>()) as BufferAddress,
-        );
-
-        encoder.copy_buffer_to_buffer(
-            &index_staging_buffer,
-            0,
-            &buffers.index,
-            0,
-            (mesh.indices.len() * mem::size_of::<u32>()) as BufferAddress,
-        );
-
-        encoder.copy_buffer_to_buffer(
-            &staging_brush_buffer,
-            0,
-            &self.pencil_buffer,
-            0,
-            mem::size_of::<Pencil>() as BufferAddress,
-        );
-
-        context.queue.submit(Some(encoder.finish()));
-
-        // set pipeline and draw
-
-        let mut encoder = context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: None,
-        });
-        {
-            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &context.view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            rpass.set_pipeline(&self.pipeline);
-            rpass.set_vertex_buffer(0, buffers.vertex_slice(mesh.points.len()));
-            rpass.set_vertex_buffer(1, self.pencil_buffer.slice(..));
-            rpass.set_index_buffer(buffers.index_slice(mesh.indices.len()), wgpu::IndexFormat::Uint32);
-            rpass.set_bind_group(0, &self.bind_group, &[]);
-            rpass.draw_indexed(0..mesh.indices.len() as u32, 0, 0..1);
+    /// Records the draw calls of a layer into an already started render pass,
+    /// `draws` are the visible instances and indices of every mesh part.
+    pub(crate) fn draw<'a>(&'a self, rpass: &mut RenderPass<'a>, layer: usize, mesh: &'a LayerMesh, draws: &[Vec<PartDraw>], instances: &'a [CellInstances]) {
+        let pencil_offset = (layer * self.uniform_stride) as u32;
+
+        rpass.set_vertex_buffer(0, mesh.vertex.slice(..));
+        rpass.set_index_buffer(mesh.index.slice(..), wgpu::IndexFormat::Uint32);
+
+        for (part, part_draws) in mesh.parts.iter().zip(draws.iter()) {
+            let cell_instances = &instances[part.cell];
+            for draw in part_draws.iter() {
+                match draw.array {
+                    None => {
+                        let Some(transforms) = &cell_instances.transforms else {
+                            continue;
+                        };
+                        rpass.set_pipeline(&self.pipeline);
+                        rpass.set_bind_group(0, &self.bind_group, &[pencil_offset, 0]);
+                        rpass.set_vertex_buffer(1, transforms.slice(..));
+                    }
+                    Some(array) => {
+                        rpass.set_pipeline(&self.array_pipeline);
+                        rpass.set_bind_group(0, &self.bind_group, &[pencil_offset, cell_instances.arrays[array]]);
+                    }
+                }
+                for instances in draw.instances.iter() {
+                    for range in draw.indices.iter() {
+                        rpass.draw_indexed(range.clone(), part.base_vertex, instances.clone());
+                    }
+                }
+            }
         }
-
-        context.queue.submit(Some(encoder.finish()));
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,25 @@
 struct VertexInput {
     @location(0) position: vec2<f32>,
+    @location(4) normal: vec2<f32>,
+};
+
+struct InstanceInput {
+    @location(1) x_axis: vec2<f32>,
+    @location(2) y_axis: vec2<f32>,
+    @location(3) translate: vec2<f32>,
+};
+
+struct Lattice {
+    x_axis: vec2<f32>,
+    y_axis: vec2<f32>,
+    translate: vec2<f32>,
+    column_step: vec2<f32>,
+    row_step: vec2<f32>,
+    outer_column_step: vec2<f32>,
+    outer_row_step: vec2<f32>,
+    columns: u32,
+    rows: u32,
+    outer_columns: u32,
 };
 
 struct VertexOutput {
@@ -12,6 +32,11 @@ struct Pencil {
     g: f32,
     b: f32,
     a: f32,
+    width: f32,
+};
+
+struct PixelSize {
+    size: f32,
 };
 
 @group(0) @binding(0)
@@ -20,14 +45,50 @@ var<uniform> transform: mat4x4<f32>;
 @group(0) @binding(1)
 var<uniform> pencil: Pencil;
 
-@vertex
-fn vs_main(in: VertexInput) -> VertexOutput {
+@group(0) @binding(2)
+var<uniform> lattice: Lattice;
+
+@group(0) @binding(3)
+var<uniform> pixel_size: PixelSize;
+
+// the outline is offset in cell coordinates, the zoom only changes the pixel size and
+// the offset is divided by the magnification of the instance to keep the width in pixels
+fn outline_vertex(in: VertexInput, x_axis: vec2<f32>, y_axis: vec2<f32>) -> vec2<f32> {
+    let scale = sqrt(abs(x_axis.x * y_axis.y - x_axis.y * y_axis.x));
+    return in.position + in.normal * (0.5 * pencil.width * pixel_size.size / scale);
+}
+
+fn world_vertex(position: vec2<f32>) -> VertexOutput {
     var out: VertexOutput;
-    out.position = transform * vec4<f32>(in.position, 0.0, 1.0);
-    out.frag_position = in.position;
+    out.position = transform * vec4<f32>(position, 0.0, 1.0);
+    out.frag_position = position;
     return out;
 }
 
+@vertex
+fn vs_main(in: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let p = outline_vertex(in, instance.x_axis, instance.y_axis);
+    let position = p.x * instance.x_axis + p.y * instance.y_axis + instance.translate;
+    return world_vertex(position);
+}
+
+// the instance index runs over the inner lattice first, then over the outer one
+fn lattice_offset(index: u32) -> vec2<f32> {
+    let count = lattice.columns * lattice.rows;
+    let inner = index % count;
+    let outer = index / count;
+    return f32(inner % lattice.columns) * lattice.column_step + f32(inner / lattice.columns) * lattice.row_step
+        + f32(outer % lattice.outer_columns) * lattice.outer_column_step + f32(outer / lattice.outer_columns) * lattice.outer_row_step;
+}
+
+@vertex
+fn vs_array(in: VertexInput, @builtin(instance_index) index: u32) -> VertexOutput {
+    let offset = lattice_offset(index);
+    let p = outline_vertex(in, lattice.x_axis, lattice.y_axis);
+    let position = p.x * lattice.x_axis + p.y * lattice.y_axis + lattice.translate + offset;
+    return world_vertex(position);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     return vec4<f32>(pencil.r, pencil.g, pencil.b, pencil.a);
@@ -1,10 +1,12 @@
+use i_triangle::i_overlay::i_float::point::IntPoint;
 use i_triangle::i_overlay::i_shape::int::path::IntPath;
 use i_triangle::i_overlay::i_shape::int::shape::{IntShape, IntShapes};
-use crate::draw::index_mesh::ListMesh;
+use crate::draw::index_mesh::{ListMesh, StrokeVertex};
 use crate::geometry::point::Point;
 
 pub(crate) trait PathTriangulation {
-    fn triangulate_path(&self, width: f32) -> ListMesh;
+    /// Outlines of the contours relative to `anchor`, the width is applied when they are drawn.
+    fn triangulate_path(&self, anchor: IntPoint) -> ListMesh<StrokeVertex>;
 }
 
 pub(crate) trait PolygonSize {
@@ -12,27 +14,25 @@ pub(crate) trait PolygonSize {
 }
 
 impl PathTriangulation for IntPath {
-    fn triangulate_path(&self, width: f32) -> ListMesh {
+    fn triangulate_path(&self, anchor: IntPoint) -> ListMesh<StrokeVertex> {
         let n = self.vertices_count();
         let mut points = Vec::with_capacity(4 * n);
         let mut indices = Vec::with_capacity(6 * n);
-        let r = 0.5 * width;
 
-        feed(&mut points, &mut indices, self, r);
+        feed(&mut points, &mut indices, self, anchor);
 
         ListMesh { points, indices }
     }
 }
 
 impl PathTriangulation for IntShape {
-    fn triangulate_path(&self, width: f32) -> ListMesh {
+    fn triangulate_path(&self, anchor: IntPoint) -> ListMesh<StrokeVertex> {
         let n = self.vertices_count();
         let mut points = Vec::with_capacity(4 * n);
         let mut indices = Vec::with_capacity(6 * n);
-        let r = 0.5 * width;
 
         for path in self {
-            feed(&mut points, &mut indices, path, r);
+            feed(&mut points, &mut indices, path, anchor);
         }
 
         ListMesh { points, indices }
@@ -40,15 +40,14 @@ impl PathTriangulation for IntShape {
 }
 
 impl PathTriangulation for IntShapes {
-    fn triangulate_path(&self, width: f32) -> ListMesh {
+    fn triangulate_path(&self, anchor: IntPoint) -> ListMesh<StrokeVertex> {
         let n = self.vertices_count();
         let mut points = Vec::with_capacity(4 * n);
         let mut indices = Vec::with_capacity(6 * n);
-        let r = 0.5 * width;
 
         for shape in self {
             for path in shape {
-                feed(&mut points, &mut indices, path, r);
+                feed(&mut points, &mut indices, path, anchor);
             }
         }
 
@@ -56,36 +55,80 @@ impl PathTriangulation for IntShapes {
     }
 }
 
-fn feed(points: &mut Vec<[f32; 2]>, indices: &mut Vec<u32>, path: &IntPath, r: f32) {
-    let mut a = Point::with_int_point(&path[path.len() - 1]);
-    let mut n = points.len() as u32;
-    for p in path.iter() {
-        let b = Point::with_int_point(p);
-        let t = (b - a).tangent(r);
+// screen strokes bevel sooner than the GDS path outlines, right angles stay sharp without long spikes
+const STROKE_MITER_LIMIT: f64 = 2.0;
 
-        let p0 = a - t;
-        let p1 = a + t;
-        let p2 = b + t;
-        let p3 = b - t;
+// a closed outline of unit half width: one quad per edge between the join points of its ends,
+// neighbour edges share vertices, so no pixel is covered twice
+fn feed(points: &mut Vec<StrokeVertex>, indices: &mut Vec<u32>, path: &IntPath, anchor: IntPoint) {
+    let anchor = Point::with_int_point(&anchor);
+    let mut contour: Vec<Point> = path.iter().map(|p| Point::with_int_point(p) - anchor).collect();
+    contour.dedup_by(|a, b| a.x == b.x && a.y == b.y);
+    if contour.len() > 1 && contour[0].x == contour[contour.len() - 1].x && contour[0].y == contour[contour.len() - 1].y {
+        contour.pop();
+    }
+    let n = contour.len();
+    if n < 2 {
+        return;
+    }
+
+    // per vertex: left and right points of the incoming edge, then of the outgoing one
+    let mut joins = Vec::with_capacity(n);
+    for i in 0..n {
+        let p = contour[i];
+        let a = p - contour[(i + n - 1) % n];
+        let b = contour[(i + 1) % n] - p;
+        let na = a.tangent(1.0);
+        let nb = b.tangent(1.0);
+        let cos = na.x * nb.x + na.y * nb.y;
+        let base = points.len() as u32;
 
-        points.push([p0.x, p0.y]);
-        points.push([p1.x, p1.y]);
-        points.push([p2.x, p2.y]);
-        points.push([p3.x, p3.y]);
+        if 1.0 + cos > 2.0 / (STROKE_MITER_LIMIT * STROKE_MITER_LIMIT) {
+            let k = 1.0 / (1.0 + cos);
+            let m = Point { x: (na.x + nb.x) * k, y: (na.y + nb.y) * k };
+            push(points, p, m);
+            push(points, p, -m);
+            joins.push([base, base + 1, base, base + 1]);
+            continue;
+        }
 
+        // the outer side is beveled, the inner side keeps the real intersection of the offset
+        // edges, so both quads end on it; a reversal has no intersection and ends square
+        let m = if 1.0 + cos > 1e-9 {
+            let k = 1.0 / (1.0 + cos);
+            Point { x: (na.x + nb.x) * k, y: (na.y + nb.y) * k }
+        } else {
+            Point { x: 0.0, y: 0.0 }
+        };
 
-        indices.push(n);
-        indices.push(n + 2);
-        indices.push(n + 1);
-        indices.push(n);
-        indices.push(n + 3);
-        indices.push(n + 2);
+        let left_turn = a.x * b.y - a.y * b.x > 0.0;
+        if left_turn {
+            push(points, p, m);
+            push(points, p, -na);
+            push(points, p, -nb);
+            indices.extend_from_slice(&[base, base + 1, base + 2]);
+            joins.push([base, base + 1, base, base + 2]);
+        } else {
+            push(points, p, -m);
+            push(points, p, na);
+            push(points, p, nb);
+            indices.extend_from_slice(&[base, base + 1, base + 2]);
+            joins.push([base + 1, base, base + 2, base]);
+        }
+    }
 
-        a = b;
-        n += 4;
+    for i in 0..n {
+        let [_, _, l0, r0] = joins[i];
+        let [l1, r1, _, _] = joins[(i + 1) % n];
+        indices.extend_from_slice(&[l0, r0, r1, l0, r1, l1]);
     }
 }
 
+#[inline]
+fn push(points: &mut Vec<StrokeVertex>, p: Point, normal: Point) {
+    points.push(StrokeVertex { position: [p.x as f32, p.y as f32], normal: [normal.x as f32, normal.y as f32] });
+}
+
 impl PolygonSize for IntPath {
     fn vertices_count(&self) -> usize {
         self.len()
@@ -112,4 +155,120 @@ impl PolygonSize for IntShapes {
 
         n
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use i_triangle::i_overlay::i_float::point::IntPoint;
+    use i_triangle::i_overlay::i_shape::int::path::IntPath;
+    use crate::draw::triangulation::{PathTriangulation, STROKE_MITER_LIMIT};
+
+    type Triangle = [[f32; 2]; 3];
+
+    fn signed_area(t: &Triangle) -> f32 {
+        0.5 * ((t[1][0] - t[0][0]) * (t[2][1] - t[0][1]) - (t[2][0] - t[0][0]) * (t[1][1] - t[0][1]))
+    }
+
+    // true if some edge direction separates the triangles, touching edges do not overlap
+    fn is_separated(a: &Triangle, b: &Triangle) -> bool {
+        a.iter().zip(a.iter().cycle().skip(1)).chain(b.iter().zip(b.iter().cycle().skip(1)))
+            .any(|(p, q)| {
+                let axis = [q[1] - p[1], p[0] - q[0]];
+                let project = |t: &Triangle| t.iter()
+                    .map(|v| v[0] * axis[0] + v[1] * axis[1])
+                    .fold((f32::MAX, f32::MIN), |(min, max), d| (min.min(d), max.max(d)));
+                let (a0, a1) = project(a);
+                let (b0, b1) = project(b);
+                a1 <= b0 + 1e-4 || b1 <= a0 + 1e-4
+            })
+    }
+
+    fn outline_triangles(path: &IntPath, r: f32) -> Vec<Triangle> {
+        let mesh = path.triangulate_path(IntPoint::new(0, 0)).expanded(r);
+        mesh.indices.chunks_exact(3)
+            .map(|t| [0, 1, 2].map(|k| mesh.points[t[k] as usize]))
+            .collect()
+    }
+
+    fn assert_no_overlap(triangles: &[Triangle]) {
+        for (i, a) in triangles.iter().enumerate() {
+            for b in triangles[i + 1..].iter() {
+                assert!(is_separated(a, b), "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+
+    // the area of the unit outline of a triangle: the triangle grown by the edges and the
+    // corners, without the inset triangle, which shrinks to the incenter
+    fn triangle_outline_area(path: &IntPath) -> f32 {
+        let p: Vec<(f64, f64)> = path.iter().map(|p| (p.x as f64, p.y as f64)).collect();
+        let length = |a: (f64, f64), b: (f64, f64)| ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+        let area = 0.5 * ((p[1].0 - p[0].0) * (p[2].1 - p[0].1) - (p[2].0 - p[0].0) * (p[1].1 - p[0].1)).abs();
+        let perimeter: f64 = (0..3).map(|i| length(p[i], p[(i + 1) % 3])).sum();
+        let inradius = 2.0 * area / perimeter;
+
+        let corners: f64 = (0..3).map(|i| {
+            let (a, b, c) = (p[(i + 2) % 3], p[i], p[(i + 1) % 3]);
+            let dot = (a.0 - b.0) * (c.0 - b.0) + (a.1 - b.1) * (c.1 - b.1);
+            let turn = std::f64::consts::PI - (dot / (length(a, b) * length(b, c))).clamp(-1.0, 1.0).acos();
+            // a miter while it is not longer than the limit, a bevel after
+            if 1.0 + turn.cos() > 2.0 / (STROKE_MITER_LIMIT * STROKE_MITER_LIMIT) { (0.5 * turn).tan() } else { 0.5 * turn.sin() }
+        }).sum();
+
+        (area + perimeter + corners - area * ((inradius - 1.0) / inradius).powi(2)) as f32
+    }
+
+    #[test]
+    fn test_sharp_outline() {
+        // two turns of 135 degrees and a right one
+        let wedge: IntPath = vec![IntPoint::new(0, 0), IntPoint::new(100, 0), IntPoint::new(0, 100)];
+        // a near reversal of 177 degrees
+        let spike: IntPath = vec![IntPoint::new(0, 0), IntPoint::new(200, 0), IntPoint::new(0, 10)];
+
+        for path in [wedge, spike] {
+            let mut reversed = path.clone();
+            reversed.reverse();
+            for path in [path, reversed] {
+                let triangles = outline_triangles(&path, 1.0);
+                assert_no_overlap(&triangles);
+
+                // no gaps either
+                let area: f32 = triangles.iter().map(|t| signed_area(t).abs()).sum();
+                let expected = triangle_outline_area(&path);
+                assert!((area - expected).abs() < 0.01, "{area} != {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_square_outline() {
+        let square: IntPath = vec![IntPoint::new(0, 0), IntPoint::new(10, 0), IntPoint::new(10, 10), IntPoint::new(0, 10)];
+        let mesh = square.triangulate_path(IntPoint::new(0, 0)).expanded(1.0);
+
+        let triangles: Vec<Triangle> = mesh.indices.chunks_exact(3)
+            .map(|t| [0, 1, 2].map(|k| mesh.points[t[k] as usize]))
+            .collect();
+        assert_eq!(triangles.len(), 8);
+
+        for (i, a) in triangles.iter().enumerate() {
+            for b in triangles[i + 1..].iter() {
+                assert!(is_separated(a, b), "{a:?} overlaps {b:?}");
+            }
+        }
+
+        // no gaps: the triangles cover the 12x12 square without the 8x8 hole
+        let area: f32 = triangles.iter().map(|t| signed_area(t).abs()).sum();
+        assert_eq!(area, 144.0 - 64.0);
+
+        let (min, max) = mesh.points.iter().fold((f32::MAX, f32::MIN), |(min, max), p| (min.min(p[0]).min(p[1]), max.max(p[0]).max(p[1])));
+        assert_eq!((min, max), (-1.0, 11.0));
+
+        // a wider stroke moves the same vertices further out
+        let wide = square.triangulate_path(IntPoint::new(0, 0)).expanded(2.0);
+        assert_eq!(wide.indices, mesh.indices);
+        let area: f32 = wide.indices.chunks_exact(3)
+            .map(|t| signed_area(&[0, 1, 2].map(|k| wide.points[t[k] as usize])).abs())
+            .sum();
+        assert_eq!(area, 196.0 - 36.0);
+    }
+}
@@ -0,0 +1,152 @@
+use std::ops::Range;
+use i_triangle::i_overlay::i_float::rect::IntRect;
+use crate::draw::cell::{Cell, CellLayer, Culling, Placement};
+use crate::draw::document::Document;
+use crate::geometry::affine::Affine;
+use crate::geometry::spatial::SpatialIndex;
+
+// more instance ranges than this are drawn as one span, the GPU clips the places between them
+const MAX_INSTANCE_RANGES: usize = 64;
+
+/// One instanced draw of a cell: the places that show in the view and the part of the cell
+/// they show.
+#[derive(Debug)]
+pub(crate) struct InstanceDraw {
+    /// index of the array placement after the single ones, None for the single transforms
+    pub(crate) array: Option<usize>,
+    pub(crate) instances: Vec<Range<u32>>,
+    pub(crate) culling: Culling,
+}
+
+/// The indices of a cell layer mesh drawn for one `InstanceDraw`.
+#[derive(Debug)]
+pub(crate) struct PartDraw {
+    pub(crate) array: Option<usize>,
+    pub(crate) instances: Vec<Range<u32>>,
+    /// indices in the layer mesh
+    pub(crate) indices: Vec<Range<u32>>,
+}
+
+/// What of every cell shows in a view.
+pub(crate) struct Draws {
+    pub(crate) cells: Vec<Vec<InstanceDraw>>,
+    /// the placements with every array cut to the columns and rows that show,
+    /// the lattices of the draws
+    pub(crate) placements: Vec<Vec<Placement>>,
+}
+
+/// Finds the places of every cell that show in a view without walking all of them.
+pub(crate) struct Visibility {
+    // per cell: the world rects of the single placements in the order of the instance buffer
+    singles: Vec<SpatialIndex>,
+}
+
+impl Visibility {
+    /// Puts the single placements of every cell first, ordered so nearby ones are next to each
+    /// other, the instance buffers have to be built from `placements` after this.
+    pub(crate) fn new(document: &Document, placements: &mut [Vec<Placement>]) -> Self {
+        let singles = document.cells.iter().zip(placements.iter_mut())
+            .map(|(cell, cell_placements)| {
+                let (singles, arrays): (Vec<Placement>, Vec<Placement>) = cell_placements.iter()
+                    .partition(|p| p.repetition.is_none());
+                let rects: Vec<IntRect> = match cell.mesh_rect() {
+                    Some(rect) => singles.iter().map(|p| p.transform.transform_rect(&rect)).collect(),
+                    None => Vec::new(),
+                };
+                let index = SpatialIndex::new(&rects);
+                let sorted: Vec<Placement> = index.order().iter().map(|&i| singles[i as usize]).collect();
+                // a cell without shapes keeps its order, it is never drawn
+                *cell_placements = if sorted.len() == singles.len() { sorted } else { singles };
+                cell_placements.extend(arrays);
+                index
+            })
+            .collect();
+
+        Self { singles }
+    }
+
+    /// The draws of every cell that show in the world rect `view`, for the placements
+    /// ordered by `new`.
+    pub(crate) fn draws(&self, document: &Document, placements: &[Vec<Placement>], view: &IntRect) -> Draws {
+        let mut draws = Draws { cells: Vec::with_capacity(placements.len()), placements: placements.to_vec() };
+        let cells = document.cells.iter().zip(draws.placements.iter_mut()).zip(self.singles.iter());
+        for ((cell, cell_placements), singles) in cells {
+            let mut cell_draws = Vec::new();
+            if let Some(rect) = cell.mesh_rect() {
+                // whole buckets of the single placements, every one sees its own part of the cell
+                let instances = singles.ranges(view).into_iter().map(|r| r.start as u32..r.end as u32).collect();
+                let local = singles.intersecting(view).into_iter()
+                    .map(|position| local_rect(cell, &cell_placements[position].transform, view))
+                    .reduce(|a, b| IntRect::with_rects(&a, &b));
+                if let Some(local) = local {
+                    cell_draws.push(InstanceDraw::new(cell, None, instances, &local));
+                }
+
+                // an array is drawn cut to the columns and rows that show, its places see the
+                // view moved back by their offsets
+                for (array, placement) in cell_placements[singles.order().len()..].iter_mut().enumerate() {
+                    let Some(clipped) = placement.clipped(&rect, view) else {
+                        continue;
+                    };
+                    let origin = IntRect { min_x: 0, max_x: 0, min_y: 0, max_y: 0 };
+                    let offsets = Placement { transform: Affine::IDENTITY, ..clipped }.rect(&origin);
+                    let world = IntRect {
+                        min_x: view.min_x.saturating_sub(offsets.max_x),
+                        max_x: view.max_x.saturating_sub(offsets.min_x),
+                        min_y: view.min_y.saturating_sub(offsets.max_y),
+                        max_y: view.max_y.saturating_sub(offsets.min_y),
+                    };
+                    let local = local_rect(cell, &clipped.transform, &world);
+                    let instances = std::iter::once(0..clipped.count() as u32).collect();
+                    cell_draws.push(InstanceDraw::new(cell, Some(array), instances, &local));
+                    *placement = clipped;
+                }
+            }
+            draws.cells.push(cell_draws);
+        }
+
+        draws
+    }
+}
+
+impl InstanceDraw {
+    // `local` is the part of the cell in cell coordinates
+    fn new(cell: &Cell, array: Option<usize>, mut instances: Vec<Range<u32>>, local: &IntRect) -> Self {
+        if instances.len() > MAX_INSTANCE_RANGES {
+            let span = instances[0].start..instances[instances.len() - 1].end;
+            instances.truncate(1);
+            instances[0] = span;
+        }
+        let covered = cell.rect.as_ref().is_none_or(|r| {
+            local.min_x <= r.min_x && r.max_x <= local.max_x && local.min_y <= r.min_y && r.max_y <= local.max_y
+        });
+        let culling = if covered { Culling::All } else { Culling::Rect(local.clone()) };
+
+        Self { array, instances, culling }
+    }
+
+    /// The indices of `cell_layer` to draw, `offsets` are its fill or stroke offsets and
+    /// `all` are the indices of its whole mesh in the layer mesh.
+    pub(crate) fn part(&self, cell_layer: &CellLayer, offsets: &[u32], all: Range<u32>) -> PartDraw {
+        let indices = match &self.culling {
+            Culling::All => vec![all],
+            Culling::Rect(rect) => cell_layer.culled(rect, offsets).into_iter()
+                .map(|r| all.start + r.start..all.start + r.end)
+                .collect(),
+        };
+        PartDraw { array: self.array, instances: self.instances.clone(), indices }
+    }
+}
+
+// the world rect seen through the mesh placement `transform` in cell coordinates,
+// the whole cell if the transform can not be inverted
+fn local_rect(cell: &Cell, transform: &Affine, world: &IntRect) -> IntRect {
+    match (transform.inverse(), &cell.rect) {
+        (Some(inverse), _) => {
+            let anchor = Affine::translation(cell.anchor.x as f64, cell.anchor.y as f64);
+            anchor.multiply(&inverse).transform_rect(world)
+        }
+        (None, Some(rect)) => rect.clone(),
+        (None, None) => world.clone(),
+    }
+}
@@ -0,0 +1,209 @@
+use crate::control::clock::Clock;
+use crate::eye::camera::{OrthoCamera, ViewState};
+use crate::geometry::point::Point;
+
+// seconds a transition takes
+const TRANSITION_TIME: f64 = 0.25;
+// a glide keeps exp(-FRICTION * t) of its speed after t seconds
+const FRICTION: f64 = 4.0;
+// a slower glide stops, in pixels per second
+const MIN_GLIDE_SPEED: f64 = 20.0;
+// the release speed is taken over this last part of a drag, in seconds
+const VELOCITY_WINDOW: f64 = 0.1;
+
+enum Motion {
+    None,
+    // world units per second at `time`
+    Glide { velocity: Point, time: f64 },
+    // `ratio` is the pixel size of `from` over the one of `to`
+    Transition { from: ViewState, to: ViewState, ratio: f64, start: f64 },
+}
+
+/// Moves the camera over time: a released drag glides on and slows down, jumps of the view
+/// become short transitions. The camera itself stays immediate, `update` is called before
+/// every frame and the frames are requested only while it returns true.
+pub(crate) struct CameraAnimation {
+    clock: Box<dyn Clock>,
+    motion: Motion,
+    // recent view centers of a drag and their time
+    samples: Vec<(f64, Point)>,
+}
+
+impl CameraAnimation {
+    pub(crate) fn new(clock: Box<dyn Clock>) -> Self {
+        Self { clock, motion: Motion::None, samples: Vec::new() }
+    }
+
+    #[inline]
+    pub(crate) fn is_running(&self) -> bool {
+        !matches!(self.motion, Motion::None)
+    }
+
+    /// Leaves the view where it is now, a gesture takes over.
+    pub(crate) fn stop(&mut self) {
+        self.motion = Motion::None;
+        self.samples.clear();
+    }
+
+    /// Jumps to the end of a running transition, so the next change starts from its target.
+    /// Returns the shown state, the next transition starts from there.
+    pub(crate) fn finish(&mut self, camera: &mut OrthoCamera) -> ViewState {
+        let shown = camera.view_state();
+        if let Motion::Transition { to, .. } = self.motion {
+            camera.set_view_state(to);
+        }
+        self.motion = Motion::None;
+        shown
+    }
+
+    /// Puts the camera back to `from` and moves it to the state it has now.
+    /// A turn or a flip is not animated.
+    pub(crate) fn transition(&mut self, camera: &mut OrthoCamera, from: ViewState) {
+        let to = camera.view_state();
+        if to == from || to.orientation() != from.orientation() {
+            return;
+        }
+        let pixel = camera.convert_size_screen_to_world(1.0);
+        camera.set_view_state(from);
+        let ratio = camera.convert_size_screen_to_world(1.0) / pixel;
+        self.motion = Motion::Transition { from, to, ratio, start: self.clock.now() };
+    }
+
+    /// Records the view center while it is dragged.
+    pub(crate) fn track(&mut self, position: Point) {
+        let now = self.clock.now();
+        self.samples.retain(|(t, _)| now - t <= VELOCITY_WINDOW);
+        self.samples.push((now, position));
+    }
+
+    /// The dragged view glides on with the speed it had when released.
+    pub(crate) fn release(&mut self, camera: &OrthoCamera) {
+        let now = self.clock.now();
+        let samples = std::mem::take(&mut self.samples);
+        self.motion = Motion::None;
+
+        let (Some(&(t0, p0)), Some(&(t1, p1))) = (samples.first(), samples.last()) else {
+            return;
+        };
+        // a drag that stopped before the release stays
+        if t1 <= t0 || now - t1 > VELOCITY_WINDOW {
+            return;
+        }
+        let velocity = Point { x: (p1.x - p0.x) / (t1 - t0), y: (p1.y - p0.y) / (t1 - t0) };
+        if length(velocity) >= MIN_GLIDE_SPEED * camera.convert_size_screen_to_world(1.0) {
+            self.motion = Motion::Glide { velocity, time: now };
+        }
+    }
+
+    /// Moves the camera to the current time, false once the animation is over.
+    pub(crate) fn update(&mut self, camera: &mut OrthoCamera) -> bool {
+        let now = self.clock.now();
+        match self.motion {
+            Motion::None => false,
+            Motion::Glide { velocity, time } => {
+                let dt = (now - time).max(0.0);
+                let decay = (-FRICTION * dt).exp();
+                // the distance of the slowing glide over dt
+                let shift = (1.0 - decay) / FRICTION;
+                let position = camera.world_position();
+                camera.move_to(Point { x: position.x + velocity.x * shift, y: position.y + velocity.y * shift });
+
+                let velocity = Point { x: velocity.x * decay, y: velocity.y * decay };
+                if length(velocity) < MIN_GLIDE_SPEED * camera.convert_size_screen_to_world(1.0) {
+                    self.motion = Motion::None;
+                    return false;
+                }
+                self.motion = Motion::Glide { velocity, time: now };
+                true
+            }
+            Motion::Transition { from, to, ratio, start } => {
+                let t = (now - start) / TRANSITION_TIME;
+                if t >= 1.0 {
+                    camera.set_view_state(to);
+                    self.motion = Motion::None;
+                    return false;
+                }
+                // fast at first and slow at the end
+                let e = 1.0 - (1.0 - t.max(0.0)).powi(3);
+                camera.set_view_state(from.interpolate(&to, ratio, e));
+                true
+            }
+        }
+    }
+}
+
+fn length(v: Point) -> f64 {
+    (v.x * v.x + v.y * v.y).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control::clock::Clock;
+    use crate::control::clock::tests::TestClock;
+    use crate::eye::animation::CameraAnimation;
+    use crate::eye::camera::OrthoCamera;
+    use crate::geometry::point::Point;
+    use crate::geometry::rect::Rect;
+    use crate::geometry::size::Size;
+
+    fn assert_points_eq(a: Point, b: Point, epsilon: f64) {
+        assert!((a.x - b.x).abs() < epsilon && (a.y - b.y).abs() < epsilon, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_animation() {
+        let time = TestClock::new();
+        let mut animation = CameraAnimation::new(Box::new(time.clone()));
+        // one world unit per pixel
+        let view_box = Rect::new(Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 });
+        let mut camera = OrthoCamera::new(Size { width: 100.0, height: 100.0 }, view_box);
+
+        // a zoom in by 4 is half done in pixel size when the eased time is half way
+        let from = animation.finish(&mut camera);
+        camera.zoom_to_rect(Rect::new(Point { x: 100.0, y: 0.0 }, Point { x: 125.0, y: 25.0 }));
+        animation.transition(&mut camera, from);
+        assert_points_eq(camera.world_position(), Point { x: 50.0, y: 50.0 }, 0.0001);
+        assert!(animation.update(&mut camera));
+
+        time.set(0.25 * (1.0 - 0.5f64.cbrt()));
+        assert!(animation.update(&mut camera));
+        assert!((camera.convert_size_screen_to_world(1.0) - 0.5).abs() < 0.0001);
+        assert_points_eq(camera.world_position(), Point { x: 81.25, y: 31.25 }, 0.0001);
+
+        // a new jump starts from the shown state and adds to the target
+        let from = animation.finish(&mut camera);
+        camera.move_to(Point { x: 212.5, y: 12.5 });
+        animation.transition(&mut camera, from);
+        assert_points_eq(camera.world_position(), Point { x: 81.25, y: 31.25 }, 0.0001);
+        time.set(1.0);
+        assert!(!animation.update(&mut camera));
+        assert!(!animation.is_running());
+        assert_points_eq(camera.world_position(), Point { x: 212.5, y: 12.5 }, 0.0001);
+        assert!((camera.convert_size_screen_to_world(1.0) - 0.25).abs() < 0.0001);
+
+        // a drag released at 100 units per second glides 100 / FRICTION units in total
+        camera.zoom_to_rect(view_box);
+        for i in 0..=10 {
+            time.set(1.0 + 0.01 * i as f64);
+            animation.track(Point { x: 50.0 + i as f64, y: 50.0 });
+        }
+        camera.move_to(Point { x: 60.0, y: 50.0 });
+        animation.release(&camera);
+        let mut frames = 0;
+        while animation.update(&mut camera) {
+            time.set(time.now() + 1.0 / 60.0);
+            frames += 1;
+        }
+        assert!(frames > 10 && frames < 60, "{frames}");
+        let x = camera.world_position().x;
+        assert!(x > 79.0 && x < 85.0, "{x}");
+
+        // a drag that stopped does not glide
+        animation.track(Point { x: 0.0, y: 0.0 });
+        time.set(time.now() + 0.01);
+        animation.track(Point { x: 10.0, y: 0.0 });
+        time.set(time.now() + 0.5);
+        animation.release(&camera);
+        assert!(!animation.is_running());
+    }
+}
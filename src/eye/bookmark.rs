@@ -0,0 +1,206 @@
+use std::fmt;
+use std::fmt::Write;
+use crate::geometry::point::Point;
+use crate::geometry::rect::Rect;
+
+/// A named view, the world rect it shows and the layers hidden there.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Bookmark {
+    pub(crate) name: String,
+    pub(crate) rect: Rect,
+    /// layer/datatype pairs, the other layers are shown
+    pub(crate) hidden: Vec<(u16, u16)>,
+}
+
+/// A bookmarks file that can not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookmarkError {
+    /// 1-based line in the file
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for BookmarkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}", self.message, self.line)
+    }
+}
+
+impl std::error::Error for BookmarkError {}
+
+/// Bookmarks in the order they were added, the names are unique.
+#[derive(Default)]
+pub(crate) struct Bookmarks {
+    items: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    #[inline]
+    pub(crate) fn get(&self, index: usize) -> Option<&Bookmark> {
+        self.items.get(index)
+    }
+
+    /// A name no bookmark has yet.
+    pub(crate) fn next_name(&self) -> String {
+        (self.items.len() + 1..)
+            .map(|i| format!("view {i}"))
+            .find(|name| self.items.iter().all(|b| &b.name != name))
+            .unwrap_or_default()
+    }
+
+    /// Replaces the bookmark of the same name or adds a new one at the end.
+    pub(crate) fn add(&mut self, bookmark: Bookmark) {
+        match self.items.iter_mut().find(|b| b.name == bookmark.name) {
+            Some(item) => *item = bookmark,
+            None => self.items.push(bookmark),
+        }
+    }
+
+    /// Reads the text written by `to_text`:
+    ///
+    /// ```text
+    /// [PLL lock detector]
+    /// rect = 1200 3400 1800 3900
+    /// hidden = 1/0 5/2
+    /// ```
+    ///
+    /// The rect is min x, min y, max x, max y in database units. Empty lines and lines
+    /// starting with `#` are skipped, unknown keys are ignored.
+    pub(crate) fn with_text(text: &str) -> Result<Self, BookmarkError> {
+        let mut bookmarks = Self::default();
+        let mut open: Option<Section> = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let error = |message: String| BookmarkError { line: line_number, message };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name.strip_suffix(']').ok_or_else(|| error("expected ]".to_string()))?.trim();
+                if name.is_empty() {
+                    return Err(error("empty name".to_string()));
+                }
+                if let Some(section) = open.take() {
+                    bookmarks.add(section.close()?);
+                }
+                open = Some(Section { line: line_number, name: name.to_string(), rect: None, hidden: Vec::new() });
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| error(format!("expected key = value, got {line}")))?;
+            let Some(section) = &mut open else {
+                return Err(error("expected [name] before the first key".to_string()));
+            };
+            match key.trim() {
+                "rect" => {
+                    let numbers: Vec<f64> = value.split_whitespace()
+                        .map(|v| v.parse().map_err(|_| error(format!("bad number {v}"))))
+                        .collect::<Result<_, _>>()?;
+                    let &[x0, y0, x1, y1] = numbers.as_slice() else {
+                        return Err(error("expected 4 numbers".to_string()));
+                    };
+                    if x0 > x1 || y0 > y1 {
+                        return Err(error("min is above max".to_string()));
+                    }
+                    section.rect = Some(Rect::new(Point { x: x0, y: y0 }, Point { x: x1, y: y1 }));
+                }
+                "hidden" => {
+                    for pair in value.split_whitespace() {
+                        section.hidden.push(parse_layer(pair).ok_or_else(|| error(format!("expected layer/datatype, got {pair}")))?);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(section) = open {
+            bookmarks.add(section.close()?);
+        }
+
+        Ok(bookmarks)
+    }
+
+    pub(crate) fn to_text(&self) -> String {
+        let mut text = String::from("# view bookmarks, rect is min x, min y, max x, max y\n");
+        for bookmark in self.items.iter() {
+            let min = Point { x: bookmark.rect.center.x - 0.5 * bookmark.rect.size.width, y: bookmark.rect.center.y - 0.5 * bookmark.rect.size.height };
+            let max = Point { x: min.x + bookmark.rect.size.width, y: min.y + bookmark.rect.size.height };
+            let _ = write!(text, "\n[{}]\nrect = {} {} {} {}\n", bookmark.name, min.x, min.y, max.x, max.y);
+            if !bookmark.hidden.is_empty() {
+                let hidden: Vec<String> = bookmark.hidden.iter().map(|(l, d)| format!("{l}/{d}")).collect();
+                let _ = writeln!(text, "hidden = {}", hidden.join(" "));
+            }
+        }
+        text
+    }
+}
+
+// a bookmark being read, it starts at `line`
+struct Section {
+    line: usize,
+    name: String,
+    rect: Option<Rect>,
+    hidden: Vec<(u16, u16)>,
+}
+
+impl Section {
+    fn close(self) -> Result<Bookmark, BookmarkError> {
+        let rect = self.rect.ok_or_else(|| BookmarkError { line: self.line, message: format!("{} has no rect", self.name) })?;
+        Ok(Bookmark { name: self.name, rect, hidden: self.hidden })
+    }
+}
+
+fn parse_layer(value: &str) -> Option<(u16, u16)> {
+    let (layer, datatype) = value.split_once('/')?;
+    Some((layer.parse().ok()?, datatype.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eye::bookmark::{Bookmark, Bookmarks};
+    use crate::geometry::point::Point;
+    use crate::geometry::rect::Rect;
+
+    #[test]
+    fn test_text() {
+        let mut bookmarks = Bookmarks::default();
+        bookmarks.add(Bookmark {
+            name: "PLL lock detector".to_string(),
+            rect: Rect::new(Point { x: -1200.5, y: 3400.0 }, Point { x: 1800.0, y: 3900.25 }),
+            hidden: vec![(1, 0), (5, 2)],
+        });
+        bookmarks.add(Bookmark {
+            name: "pad ring NW".to_string(),
+            rect: Rect::new(Point { x: 0.0, y: 0.0 }, Point { x: 10.0, y: 10.0 }),
+            hidden: Vec::new(),
+        });
+
+        let text = bookmarks.to_text();
+        assert!(text.contains("[PLL lock detector]\nrect = -1200.5 3400 1800 3900.25\nhidden = 1/0 5/2\n"));
+        let mut read = Bookmarks::with_text(&text).unwrap();
+        assert_eq!(read.get(0), bookmarks.get(0));
+        assert_eq!(read.get(1), bookmarks.get(1));
+        assert_eq!(read.get(2), None);
+
+        // the same name replaces the bookmark in place
+        read.add(Bookmark { hidden: vec![(7, 7)], ..bookmarks.get(0).unwrap().clone() });
+        assert_eq!(read.get(2), None);
+        assert_eq!(read.get(0).unwrap().hidden, vec![(7, 7)]);
+        read.add(Bookmark { name: "view 3".to_string(), ..bookmarks.get(1).unwrap().clone() });
+        assert_eq!(read.next_name(), "view 4");
+
+        let text = "# shared\n[a]\nnote = unknown keys are skipped\n\nrect = 0 0 1 1\n";
+        assert_eq!(Bookmarks::with_text(text).unwrap().get(0).unwrap().name, "a");
+
+        let error = |text: &str| Bookmarks::with_text(text).err().map(|e| e.line);
+        assert_eq!(error("rect = 0 0 1 1"), Some(1));
+        assert_eq!(error("[a]\nrect = 0 0 1"), Some(2));
+        assert_eq!(error("[a]\nrect = 2 0 1 1"), Some(2));
+        assert_eq!(error("[a]\nrect = 0 0 1 1\nhidden = 1-0"), Some(3));
+        assert_eq!(error("[a]\n\n[b]\nrect = 0 0 1 1"), Some(1));
+        assert_eq!(error("[a\nrect = 0 0 1 1"), Some(1));
+    }
+}
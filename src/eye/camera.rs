@@ -1,25 +1,61 @@
-use crate::eye::transform::{Matrix4x4, OrthoNoRotTransformer};
+use crate::eye::orientation::Orientation;
+use crate::eye::transform::{Matrix4x4, OrthoTransformer};
 use crate::geometry::point::Point;
 use crate::geometry::rect::Rect;
 use crate::geometry::size::Size;
 
-pub(crate) struct OrthoNoRotCamera {
+// the origin follows the view when it is farther than this many view sizes
+const ORIGIN_DISTANCE: f64 = 16.0;
+
+/// Everything the view history restores, the screen size is not part of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ViewState {
+    view_box: Rect,
+    zoom: f64,
+    orientation: Orientation,
+}
+
+impl ViewState {
+    #[inline]
+    pub(crate) fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// The state a part `t` of the way to `to`, the center moves linearly and the pixel size
+    /// exponentially. `ratio` is the pixel size of this state over the one of `to`.
+    pub(crate) fn interpolate(&self, to: &ViewState, ratio: f64, t: f64) -> ViewState {
+        let (a, b) = (self.view_box.center, to.view_box.center);
+        let center = Point { x: a.x + t * (b.x - a.x), y: a.y + t * (b.y - a.y) };
+        ViewState {
+            view_box: Rect { center, size: to.view_box.size },
+            zoom: to.zoom * ratio.powf(1.0 - t),
+            orientation: to.orientation,
+        }
+    }
+}
+
+pub(crate) struct OrthoCamera {
     screen: Size,
     view_box: Rect,
-    zoom: f32,
-    screen_to_world: OrthoNoRotTransformer,
-    world_to_clip: OrthoNoRotTransformer,
+    zoom: f64,
+    orientation: Orientation,
+    screen_to_world: OrthoTransformer,
+    world_to_clip: OrthoTransformer,
+    // a whole point near the view, the GPU gets coordinates relative to it
+    origin: Point,
     timestamp: usize,
 }
 
-impl OrthoNoRotCamera {
+impl OrthoCamera {
     pub(crate) fn new(screen: Size, view_box: Rect) -> Self {
         let mut camera = Self {
             screen,
             view_box,
             zoom: 1.0,
-            screen_to_world: OrthoNoRotTransformer::empty(),
-            world_to_clip: OrthoNoRotTransformer::empty(),
+            orientation: Orientation::default(),
+            screen_to_world: OrthoTransformer::empty(),
+            world_to_clip: OrthoTransformer::empty(),
+            origin: Point { x: view_box.center.x.round(), y: view_box.center.y.round() },
             timestamp: 0,
         };
 
@@ -32,7 +68,7 @@ impl OrthoNoRotCamera {
         self.timestamp
     }
 
-    pub(crate) fn zoom(&self) -> f32 {
+    pub(crate) fn zoom(&self) -> f64 {
         self.zoom
     }
 
@@ -40,20 +76,75 @@ impl OrthoNoRotCamera {
         self.view_box.center
     }
 
+    /// World coordinates are uploaded relative to this point, it changes rarely.
+    pub(crate) fn origin(&self) -> Point {
+        self.origin
+    }
+
+    /// The world rect on the screen.
+    pub(crate) fn view_rect(&self) -> Rect {
+        let a = self.convert_point_screen_to_world(Point { x: 0.0, y: 0.0 });
+        let b = self.convert_point_screen_to_world(Point { x: self.screen.width, y: self.screen.height });
+        Rect::new(Point { x: a.x.min(b.x), y: a.y.min(b.y) }, Point { x: a.x.max(b.x), y: a.y.max(b.y) })
+    }
+
+    pub(crate) fn view_state(&self) -> ViewState {
+        ViewState { view_box: self.view_box, zoom: self.zoom, orientation: self.orientation }
+    }
+
+    pub(crate) fn set_view_state(&mut self, state: ViewState) {
+        self.view_box = state.view_box;
+        self.zoom = state.zoom;
+        self.orientation = state.orientation;
+        self.update();
+    }
+
+    pub(crate) fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Turns or mirrors the view around its center, the zoom stays.
+    pub(crate) fn set_orientation(&mut self, orientation: Orientation) {
+        let pixel = self.convert_size_screen_to_world(1.0);
+        self.orientation = orientation;
+        self.update();
+
+        self.zoom *= pixel / self.convert_size_screen_to_world(1.0);
+        self.update();
+    }
+
+    #[inline]
+    pub(crate) fn screen(&self) -> Size {
+        self.screen
+    }
+
     pub(crate) fn set_screen(&mut self, screen: Size) {
         self.screen = screen;
         self.update();
     }
 
-    // pub(crate) fn set_view_box(&mut self, view_box: Rect) {
-    //     self.view_box = view_box;
-    //     self.update();
-    // }
+    /// Shows `rect` as large as the screen allows, centered. An empty rect only moves the view.
+    pub(crate) fn zoom_to_rect(&mut self, rect: Rect) {
+        if rect.size.width <= 0.0 && rect.size.height <= 0.0 {
+            self.move_to(rect.center);
+            return;
+        }
+        self.view_box = rect;
+        self.zoom = 1.0;
+        self.update();
+    }
+
+    /// Zooms around the view center so that one world unit covers `pixels` screen pixels.
+    pub(crate) fn set_pixels_per_unit(&mut self, pixels: f64) {
+        let center = Point { x: 0.5 * self.screen.width, y: 0.5 * self.screen.height };
+        let zoom = self.zoom / (pixels * self.convert_size_screen_to_world(1.0));
+        self.set_zoom(zoom, center);
+    }
 
-    pub(crate) fn set_zoom(&mut self, zoom: f32, cursor: Point) {
+    pub(crate) fn set_zoom(&mut self, zoom: f64, cursor: Point) {
         let cursor_world_before = self.convert_point_screen_to_world(cursor);
 
-        self.zoom = zoom.clamp(0.000_0001, 1000_000.0);
+        self.zoom = zoom.clamp(0.000_000_1, 1_000_000.0);
         self.update();
 
         let cursor_world_after = self.convert_point_screen_to_world(cursor);
@@ -74,8 +165,8 @@ impl OrthoNoRotCamera {
         self.update();
     }
 
-    pub(crate) fn convert_size_screen_to_world(&self, screen: f32) -> f32 {
-        self.screen_to_world.sy * screen
+    pub(crate) fn convert_size_screen_to_world(&self, screen: f64) -> f64 {
+        self.screen_to_world.scale() * screen
     }
 
     pub(crate) fn convert_point_screen_to_world(&self, point: Point) -> Point {
@@ -86,68 +177,78 @@ impl OrthoNoRotCamera {
         self.screen_to_world.transform_vector(point)
     }
 
+    /// Clip transform of coordinates relative to `origin`, the f32 matrix keeps only small values.
     pub(crate) fn clip_matrix(&self) -> Matrix4x4 {
-        self.world_to_clip.to_matrix()
+        self.world_to_clip.relative_to(self.origin).to_matrix()
     }
 
     fn update(&mut self) {
-        // fit the view_box to screen
-        let is_horizontal = self.screen.width * self.view_box.size.height < self.screen.height * self.view_box.size.width;
-        self.screen_to_world = self.calculate_screen_to_world(is_horizontal);
-        self.world_to_clip = self.calculate_world_to_clip(is_horizontal);
-        self.timestamp += 1;
-    }
-
-    fn calculate_screen_to_world(&self, is_horizontal: bool) -> OrthoNoRotTransformer {
         let view_box = self.view_box.scaled(self.zoom);
-        let s = if is_horizontal {
-            view_box.size.width / self.screen.width
+
+        // fit the view_box to screen, a quarter turn swaps its sides
+        let (width, height) = if self.orientation.is_transposed() {
+            (view_box.size.height, view_box.size.width)
         } else {
-            view_box.size.height / self.screen.height
+            (view_box.size.width, view_box.size.height)
         };
+        let s = (width / self.screen.width).max(height / self.screen.height);
 
+        self.screen_to_world = self.calculate_screen_to_world(&view_box, s);
+        self.world_to_clip = self.calculate_world_to_clip(&view_box, s);
 
-        let sx = s;
-        let sy = -s;
+        let distance = (view_box.center.x - self.origin.x).abs().max((view_box.center.y - self.origin.y).abs());
+        if distance > ORIGIN_DISTANCE * view_box.size.width.max(view_box.size.height) {
+            self.origin = Point { x: view_box.center.x.round(), y: view_box.center.y.round() };
+        }
 
-        let tx = view_box.min_x();
-        let ty = view_box.min_y() + s * self.screen.height;
+        self.timestamp += 1;
+    }
 
-        OrthoNoRotTransformer {
-            sx,
-            sy,
+    // screen y goes down, the view is turned back into the world by the transposed orientation
+    fn calculate_screen_to_world(&self, view_box: &Rect, s: f64) -> OrthoTransformer {
+        let [[a, b], [c, d]] = self.orientation.matrix();
+
+        // the view center is in the middle of the screen, like in the clip transform
+        let hw = 0.5 * s * self.screen.width;
+        let hh = 0.5 * s * self.screen.height;
+        let tx = view_box.center.x - a * hw + c * hh;
+        let ty = view_box.center.y - b * hw + d * hh;
+
+        OrthoTransformer {
+            a: a * s,
+            b: -c * s,
+            c: b * s,
+            d: -d * s,
             tx,
             ty,
         }
     }
 
-    fn calculate_world_to_clip(&self, is_horizontal: bool) -> OrthoNoRotTransformer {
-        let view_box = self.view_box.scaled(self.zoom);
-        let (sx, sy) = if is_horizontal {
-            let sx = 2.0 / view_box.size.width;
-            let sy = 2.0 * self.screen.width / (view_box.size.width * self.screen.height);
-            (sx, sy)
-        } else {
-            let sx = 2.0 * self.screen.height / (view_box.size.height * self.screen.width);
-            let sy = 2.0 / view_box.size.height;
-            (sx, sy)
+    fn calculate_world_to_clip(&self, view_box: &Rect, s: f64) -> OrthoTransformer {
+        let [[a, b], [c, d]] = self.orientation.matrix();
+        let kx = 2.0 / (s * self.screen.width);
+        let ky = 2.0 / (s * self.screen.height);
+
+        let transform = OrthoTransformer {
+            a: kx * a,
+            b: kx * b,
+            c: ky * c,
+            d: ky * d,
+            tx: 0.0,
+            ty: 0.0,
         };
 
-        let tx = -sx * view_box.center.x;
-        let ty = -sy * view_box.center.y;
-
-        OrthoNoRotTransformer {
-            sx,
-            sy,
-            tx,
-            ty,
-        }
+        let t = transform.transform_vector(view_box.center);
+        OrthoTransformer { tx: -t.x, ty: -t.y, ..transform }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::eye::camera::OrthoNoRotCamera;
+    use crate::draw::document::Document;
+    use crate::eye::camera::OrthoCamera;
+    use crate::eye::orientation::Orientation;
+    use crate::gds::reader::tests::{read_strict, sample_stream};
     use crate::geometry::point::Point;
     use crate::geometry::rect::Rect;
     use crate::geometry::size::Size;
@@ -164,7 +265,7 @@ mod tests {
             height: 2.0,
         };
 
-        let camera = OrthoNoRotCamera::new(screen, view_box);
+        let camera = OrthoCamera::new(screen, view_box);
 
         let world = camera.screen_to_world.transform_point(Point { x: 1.0, y: 0.5 });
 
@@ -183,7 +284,7 @@ mod tests {
             height: 2.0,
         };
 
-        let mut camera = OrthoNoRotCamera::new(screen, view_box);
+        let mut camera = OrthoCamera::new(screen, view_box);
         camera.set_zoom(2.0, Point { x: 0.5 * screen.width, y: 0.5 * screen.height });
 
         let world = camera.screen_to_world.transform_point(Point { x: 1.25, y: 0.75 });
@@ -203,7 +304,7 @@ mod tests {
             height: 2.0,
         };
 
-        let camera = OrthoNoRotCamera::new(screen, view_box);
+        let camera = OrthoCamera::new(screen, view_box);
 
         let clip = camera.world_to_clip.transform_point(Point { x: 5.0, y: 4.0 });
 
@@ -222,7 +323,7 @@ mod tests {
             height: 2.0,
         };
 
-        let mut camera = OrthoNoRotCamera::new(screen, view_box);
+        let mut camera = OrthoCamera::new(screen, view_box);
         camera.set_zoom(2.0, Point { x: 0.5 * screen.width, y: 0.5 * screen.height });
 
         let clip = camera.world_to_clip.transform_point(Point { x: 5.0, y: 4.0 });
@@ -230,7 +331,122 @@ mod tests {
         assert_points_eq(clip, Point { x: -1.0 / 6.0, y: 0.25 }, 0.0001);
     }
 
-    pub(crate) fn assert_points_eq(p1: Point, p2: Point, epsilon: f32) {
+    #[test]
+    fn test_4() {
+        let view_box = Rect::new(
+            Point { x: 3.0, y: 1.0 },
+            Point { x: 9.0, y: 5.0 },
+        );
+
+        let screen = Size {
+            width: 3.0,
+            height: 2.0,
+        };
+
+        // a quarter turn shows the 6 x 4 box standing, its width fits the height of the screen
+        let mut camera = OrthoCamera::new(screen, view_box);
+        camera.orientation = Orientation::default().rotated_left();
+        camera.update();
+
+        let world = camera.screen_to_world.transform_point(Point { x: 1.0, y: 0.5 });
+        assert_points_eq(world, Point { x: 7.5, y: 4.5 }, 0.0001);
+
+        let clip = camera.world_to_clip.transform_point(Point { x: 5.0, y: 4.0 });
+        assert_points_eq(clip, Point { x: -2.0 / 9.0, y: -1.0 / 3.0 }, 0.0001);
+
+        // the matrix the shaders get takes points relative to the origin
+        let m = camera.clip_matrix();
+        let (x, y) = ((5.0 - camera.origin().x) as f32, (4.0 - camera.origin().y) as f32);
+        let clip = Point { x: (m[0] * x + m[4] * y + m[12]) as f64, y: (m[1] * x + m[5] * y + m[13]) as f64 };
+        assert_points_eq(clip, Point { x: -2.0 / 9.0, y: -1.0 / 3.0 }, 0.0001);
+    }
+
+    #[test]
+    fn test_5() {
+        let view_box = Rect::new(
+            Point { x: 3.0, y: 1.0 },
+            Point { x: 9.0, y: 5.0 },
+        );
+
+        let screen = Size {
+            width: 3.0,
+            height: 2.0,
+        };
+
+        // test_2 mirrored about the x axis
+        let mut camera = OrthoCamera::new(screen, view_box);
+        camera.orientation = Orientation::default().flipped().rotated_left().rotated_left();
+        camera.update();
+
+        let clip = camera.world_to_clip.transform_point(Point { x: 5.0, y: 4.0 });
+        assert_points_eq(clip, Point { x: -1.0 / 3.0, y: -0.5 }, 0.0001);
+
+        // the zoom keeps the point under the cursor in every orientation
+        let cursor = Point { x: 0.5, y: 1.5 };
+        let mut orientation = Orientation::default();
+        for i in 0..8 {
+            orientation = if i == 4 { orientation.flipped() } else { orientation.rotated_left() };
+            camera.set_orientation(orientation);
+            assert!((camera.convert_size_screen_to_world(1.0) - 2.0).abs() < 0.0001);
+
+            let world = camera.convert_point_screen_to_world(cursor);
+            let clip = camera.world_to_clip.transform_point(world);
+            assert_points_eq(clip, Point { x: 2.0 * cursor.x / screen.width - 1.0, y: 1.0 - 2.0 * cursor.y / screen.height }, 0.0001);
+
+            let zoom = camera.zoom();
+            camera.set_zoom(0.5 * zoom, cursor);
+            assert_points_eq(camera.convert_point_screen_to_world(cursor), world, 0.0001);
+            camera.set_zoom(zoom, cursor);
+        }
+    }
+
+    #[test]
+    fn test_zoom_to_rect() {
+        let view_box = Rect::new(
+            Point { x: 3.0, y: 1.0 },
+            Point { x: 9.0, y: 5.0 },
+        );
+
+        let screen = Size {
+            width: 3.0,
+            height: 2.0,
+        };
+
+        let mut camera = OrthoCamera::new(screen, view_box);
+        camera.set_zoom(0.5, Point { x: 0.0, y: 0.0 });
+
+        // a tall rect fills the height and is centered horizontally
+        camera.zoom_to_rect(Rect::new(Point { x: 10.0, y: 10.0 }, Point { x: 12.0, y: 14.0 }));
+        assert_points_eq(camera.convert_point_screen_to_world(Point { x: 1.5, y: 0.0 }), Point { x: 11.0, y: 14.0 }, 0.0001);
+        assert_points_eq(camera.convert_point_screen_to_world(Point { x: 0.0, y: 2.0 }), Point { x: 8.0, y: 10.0 }, 0.0001);
+        let rect = camera.view_rect();
+        assert_points_eq(rect.center, Point { x: 11.0, y: 12.0 }, 0.0001);
+        assert!((rect.size.width - 6.0).abs() < 0.0001 && (rect.size.height - 4.0).abs() < 0.0001);
+
+        // a point keeps the zoom
+        camera.zoom_to_rect(Rect::new(Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: 1.0 }));
+        assert_points_eq(camera.world_position(), Point { x: 1.0, y: 1.0 }, 0.0001);
+        assert!((camera.convert_size_screen_to_world(1.0) - 2.0).abs() < 0.0001);
+
+        camera.set_pixels_per_unit(4.0);
+        assert!((camera.convert_size_screen_to_world(1.0) - 0.25).abs() < 0.0001);
+        assert_points_eq(camera.world_position(), Point { x: 1.0, y: 1.0 }, 0.0001);
+    }
+
+    #[test]
+    fn test_zoom_to_cell() {
+        let document = Document::with_library(&read_strict(&sample_stream()).unwrap());
+        let mut camera = OrthoCamera::new(Size { width: 200.0, height: 100.0 }, document.rect);
+
+        // the square under the rotated SREF fills the height of the screen
+        let index = document.cell_index("SQUARE").unwrap();
+        camera.zoom_to_rect(document.cell_view(index).unwrap());
+        let rect = camera.view_rect();
+        assert_points_eq(rect.center, Point { x: 95.0, y: 5.0 }, 0.0001);
+        assert!((rect.size.width - 20.0).abs() < 0.0001 && (rect.size.height - 10.0).abs() < 0.0001);
+    }
+
+    pub(crate) fn assert_points_eq(p1: Point, p2: Point, epsilon: f64) {
         assert!(
             (p1.x - p2.x).abs() < epsilon,
             "X coordinates differ: left = {}, right = {}",
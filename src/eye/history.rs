@@ -0,0 +1,153 @@
+use crate::control::clock::Clock;
+use crate::eye::camera::ViewState;
+
+// the oldest views are forgotten
+const MAX_HISTORY: usize = 64;
+// steps closer than this in seconds are one gesture, longer than the delay before a key repeats
+const STEP_PAUSE: f64 = 0.75;
+
+/// Views the user stopped at, back and forward move through them like in a browser.
+pub(crate) struct ViewHistory {
+    states: Vec<ViewState>,
+    // index of the shown state
+    current: usize,
+    clock: Box<dyn Clock>,
+    // time of the last step while the current state is the one its steps move
+    last_step: Option<f64>,
+}
+
+impl ViewHistory {
+    pub(crate) fn new(state: ViewState, clock: Box<dyn Clock>) -> Self {
+        Self { states: vec![state], current: 0, clock, last_step: None }
+    }
+
+    /// Records a view reached by a single step like a wheel notch or an arrow key. Steps that
+    /// follow each other quickly are one gesture, it keeps only the view where it stopped.
+    pub(crate) fn step(&mut self, state: ViewState) {
+        let now = self.clock.now();
+        if self.last_step.is_some_and(|time| now - time < STEP_PAUSE) {
+            self.states[self.current] = state;
+            self.last_step = Some(now);
+            return;
+        }
+
+        // a step that did not move the view starts no gesture
+        let is_new = self.states[self.current] != state;
+        self.push(state);
+        if is_new {
+            self.last_step = Some(now);
+        }
+    }
+
+    /// Records a new view, the views after the current one are dropped.
+    pub(crate) fn push(&mut self, state: ViewState) {
+        self.last_step = None;
+        if self.states[self.current] == state {
+            return;
+        }
+        self.states.truncate(self.current + 1);
+        self.states.push(state);
+        if self.states.len() > MAX_HISTORY {
+            self.states.remove(0);
+        }
+        self.current = self.states.len() - 1;
+    }
+
+    pub(crate) fn back(&mut self) -> Option<ViewState> {
+        self.last_step = None;
+        if self.current == 0 {
+            return None;
+        }
+        self.current -= 1;
+        Some(self.states[self.current])
+    }
+
+    pub(crate) fn forward(&mut self) -> Option<ViewState> {
+        self.last_step = None;
+        if self.current + 1 >= self.states.len() {
+            return None;
+        }
+        self.current += 1;
+        Some(self.states[self.current])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control::clock::tests::TestClock;
+    use crate::eye::camera::OrthoCamera;
+    use crate::eye::history::{ViewHistory, MAX_HISTORY};
+    use crate::geometry::point::Point;
+    use crate::geometry::rect::Rect;
+    use crate::geometry::size::Size;
+
+    #[test]
+    fn test_back_forward() {
+        let view_box = Rect::new(Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 });
+        let mut camera = OrthoCamera::new(Size { width: 800.0, height: 600.0 }, view_box);
+        let mut state_at = |x: f64| {
+            camera.move_to(Point { x, y: 0.0 });
+            camera.view_state()
+        };
+        let (a, b, c, d) = (state_at(1.0), state_at(2.0), state_at(3.0), state_at(4.0));
+
+        let mut history = ViewHistory::new(a, Box::new(TestClock::new()));
+        history.push(b);
+        history.push(b);
+        history.push(c);
+        assert_eq!(history.back(), Some(b));
+        assert_eq!(history.back(), Some(a));
+        assert_eq!(history.back(), None);
+        assert_eq!(history.forward(), Some(b));
+
+        // a new view drops the forward part
+        history.push(d);
+        assert_eq!(history.forward(), None);
+        assert_eq!(history.back(), Some(b));
+        assert_eq!(history.back(), Some(a));
+
+        for i in 0..2 * MAX_HISTORY {
+            history.push(state_at(10.0 + i as f64));
+        }
+        let mut count = 0;
+        while history.back().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, MAX_HISTORY - 1);
+    }
+
+    #[test]
+    fn test_steps() {
+        let view_box = Rect::new(Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 });
+        let mut camera = OrthoCamera::new(Size { width: 800.0, height: 600.0 }, view_box);
+        let start = camera.view_state();
+        let time = TestClock::new();
+        let mut history = ViewHistory::new(start, Box::new(time.clone()));
+
+        // ten steps a tenth of a second apart are one entry
+        for i in 1..=10 {
+            time.set(0.1 * i as f64);
+            camera.set_zoom(camera.zoom() * 0.8, Point { x: 400.0, y: 300.0 });
+            history.step(camera.view_state());
+        }
+        let zoomed = camera.view_state();
+
+        // after a pause the steps are a new gesture
+        time.set(3.0);
+        camera.move_to(Point { x: 10.0, y: 0.0 });
+        history.step(camera.view_state());
+        time.set(3.5);
+        camera.move_to(Point { x: 20.0, y: 0.0 });
+        history.step(camera.view_state());
+
+        assert_eq!(history.back(), Some(zoomed));
+        assert_eq!(history.back(), Some(start));
+        assert_eq!(history.back(), None);
+
+        // a step after moving back does not replace the view moved back to
+        time.set(3.6);
+        camera.move_to(Point { x: 30.0, y: 0.0 });
+        history.step(camera.view_state());
+        assert_eq!(history.back(), Some(start));
+    }
+}
@@ -1,2 +1,6 @@
+pub(crate) mod animation;
+pub(crate) mod bookmark;
 pub(crate) mod camera;
+pub(crate) mod history;
+pub(crate) mod orientation;
 pub(crate) mod transform;
\ No newline at end of file
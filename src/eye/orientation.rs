@@ -0,0 +1,80 @@
+/// One of the eight Manhattan orientations of the view: an optional mirror about the
+/// x axis followed by quarter turns counterclockwise, the order of a GDS placement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Orientation {
+    quarter_turns: u8,
+    mirror: bool,
+}
+
+impl Orientation {
+    /// The picture turns by 90° counterclockwise.
+    pub(crate) fn rotated_left(self) -> Self {
+        Self { quarter_turns: (self.quarter_turns + 1) % 4, ..self }
+    }
+
+    pub(crate) fn rotated_right(self) -> Self {
+        Self { quarter_turns: (self.quarter_turns + 3) % 4, ..self }
+    }
+
+    /// The picture is mirrored left to right.
+    pub(crate) fn flipped(self) -> Self {
+        // a mirror about the y axis is a mirror about the x axis turned by 180°
+        Self { quarter_turns: (6 - self.quarter_turns) % 4, mirror: !self.mirror }
+    }
+
+    /// true if the world x axis is vertical on the screen
+    pub(crate) fn is_transposed(&self) -> bool {
+        self.quarter_turns % 2 == 1
+    }
+
+    /// World to view matrix as rows `[[a, b], [c, d]]`.
+    pub(crate) fn matrix(&self) -> [[f64; 2]; 2] {
+        let (cos, sin) = [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)][self.quarter_turns as usize];
+        let f = if self.mirror { -1.0 } else { 1.0 };
+        [[cos, -sin * f], [sin, cos * f]]
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match (self.mirror, self.quarter_turns) {
+            (false, 0) => "R0",
+            (false, 1) => "R90",
+            (false, 2) => "R180",
+            (false, _) => "R270",
+            (true, 0) => "MX",
+            (true, 1) => "MXR90",
+            (true, 2) => "MXR180",
+            (true, _) => "MXR270",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::eye::orientation::Orientation;
+
+    #[test]
+    fn test_orientations() {
+        let r0 = Orientation::default();
+        assert_eq!(r0.matrix(), [[1.0, 0.0], [0.0, 1.0]]);
+        assert_eq!(r0.rotated_left().matrix(), [[0.0, -1.0], [1.0, 0.0]]);
+        assert_eq!(r0.flipped().matrix(), [[-1.0, 0.0], [0.0, 1.0]]);
+        assert_eq!(r0.rotated_left().rotated_right(), r0);
+        assert_eq!(r0.rotated_left().flipped().flipped(), r0.rotated_left());
+
+        // a flip after a turn undoes a turn the other way after a flip
+        let a = r0.rotated_left().flipped();
+        let b = r0.flipped().rotated_right();
+        assert_eq!(a, b);
+
+        let mut all = HashSet::new();
+        let mut o = r0;
+        for _ in 0..4 {
+            all.insert(o.name());
+            all.insert(o.flipped().name());
+            o = o.rotated_left();
+        }
+        assert_eq!(o, r0);
+        assert_eq!(all.len(), 8);
+    }
+}
@@ -2,47 +2,74 @@ use crate::geometry::point::Point;
 
 pub(crate) type Matrix4x4 = [f32; 16];
 
-pub(super) struct OrthoNoRotTransformer {
-    pub(super) sx: f32,
-    pub(super) sy: f32,
-    pub(super) tx: f32,
-    pub(super) ty: f32,
+/// x' = a * x + b * y + tx
+/// y' = c * x + d * y + ty
+pub(super) struct OrthoTransformer {
+    pub(super) a: f64,
+    pub(super) b: f64,
+    pub(super) c: f64,
+    pub(super) d: f64,
+    pub(super) tx: f64,
+    pub(super) ty: f64,
 }
 
-impl OrthoNoRotTransformer {
+impl OrthoTransformer {
     pub(super) fn empty() -> Self {
         Self {
-            sx: 1.0,
-            sy: 1.0,
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 1.0,
             tx: 0.0,
             ty: 0.0,
         }
     }
 
     pub(super) fn transform_point(&self, point: Point) -> Point {
-        let x = self.sx * point.x + self.tx;
-        let y = self.sy * point.y + self.ty;
+        let x = self.a * point.x + self.b * point.y + self.tx;
+        let y = self.c * point.x + self.d * point.y + self.ty;
 
         Point { x, y }
     }
 
     pub(super) fn transform_vector(&self, point: Point) -> Point {
-        let x = self.sx * point.x;
-        let y = self.sy * point.y;
+        let x = self.a * point.x + self.b * point.y;
+        let y = self.c * point.x + self.d * point.y;
 
         Point { x, y }
     }
 
+    /// Length of a unit vector after the transform, the same in every direction.
+    pub(super) fn scale(&self) -> f64 {
+        self.a.hypot(self.c)
+    }
+
+    /// The same transform for points given relative to `origin`.
+    pub(super) fn relative_to(&self, origin: Point) -> Self {
+        let t = self.transform_point(origin);
+        Self {
+            a: self.a,
+            b: self.b,
+            c: self.c,
+            d: self.d,
+            tx: t.x,
+            ty: t.y,
+        }
+    }
+
     pub(super) fn to_matrix(&self) -> Matrix4x4 {
-        let s_x = self.sx;
-        let s_y = self.sy;
-        let t_x = self.tx;
-        let t_y = self.ty;
+        let a = self.a as f32;
+        let b = self.b as f32;
+        let c = self.c as f32;
+        let d = self.d as f32;
+        let t_x = self.tx as f32;
+        let t_y = self.ty as f32;
+        // column-major, the first column is the image of the x axis
         [
-            s_x, 0.0, 0.0, 0.0,
-            0.0, s_y, 0.0, 0.0,
+            a, c, 0.0, 0.0,
+            b, d, 0.0, 0.0,
             0.0, 0.0, 1.0, 0.0,
             t_x, t_y, 0.0, 1.0,
         ]
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,126 @@
+use std::fmt;
+
+/// Where in the stream an error was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    /// byte offset of the record header
+    pub offset: usize,
+    pub record: &'static str,
+    /// name of the enclosing structure, if any
+    pub structure: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GdsError {
+    /// the stream ends inside a record or before ENDLIB
+    TruncatedRecord { location: Location, length: usize },
+    UnknownRecordType { location: Location, code: u8 },
+    /// unknown data type code or a code that does not fit the record
+    BadDataType { location: Location, code: u8 },
+    /// record length is odd, too short or does not fit its data type
+    OddRecordLength { location: Location, length: usize },
+    /// BGNSTR inside a structure or ENDSTR outside of one
+    UnmatchedStructure { location: Location },
+    BadReal { location: Location, value: f64 },
+    /// a known record in a place where it is not allowed
+    UnexpectedRecord { location: Location },
+    /// an element or record misses mandatory data
+    MissingData { location: Location, message: String },
+    /// the writer can not fit the data into a single record
+    RecordTooLong { location: Location, length: usize },
+}
+
+impl GdsError {
+    pub fn location(&self) -> &Location {
+        match self {
+            GdsError::TruncatedRecord { location, .. }
+            | GdsError::UnknownRecordType { location, .. }
+            | GdsError::BadDataType { location, .. }
+            | GdsError::OddRecordLength { location, .. }
+            | GdsError::UnmatchedStructure { location }
+            | GdsError::BadReal { location, .. }
+            | GdsError::UnexpectedRecord { location }
+            | GdsError::MissingData { location, .. }
+            | GdsError::RecordTooLong { location, .. } => location,
+        }
+    }
+
+    fn location_mut(&mut self) -> &mut Location {
+        match self {
+            GdsError::TruncatedRecord { location, .. }
+            | GdsError::UnknownRecordType { location, .. }
+            | GdsError::BadDataType { location, .. }
+            | GdsError::OddRecordLength { location, .. }
+            | GdsError::UnmatchedStructure { location }
+            | GdsError::BadReal { location, .. }
+            | GdsError::UnexpectedRecord { location }
+            | GdsError::MissingData { location, .. }
+            | GdsError::RecordTooLong { location, .. } => location,
+        }
+    }
+
+    /// Stream errors do not know the structure they are found in, the parser adds it.
+    pub(crate) fn in_structure(mut self, structure: Option<&str>) -> Self {
+        let location = self.location_mut();
+        if location.structure.is_none() {
+            location.structure = structure.map(|s| s.to_string());
+        }
+        self
+    }
+
+    /// true if the stream is broken and the reader can not continue after this error
+    pub(crate) fn is_fatal(&self) -> bool {
+        match self {
+            GdsError::TruncatedRecord { .. } => true,
+            // a record shorter than its header can not be skipped
+            GdsError::OddRecordLength { length, .. } => *length < 4,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.record, self.offset)?;
+        if let Some(structure) = &self.structure {
+            write!(f, " in structure {structure}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GdsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GdsError::TruncatedRecord { location, length } => {
+                write!(f, "truncated record {location}, {length} bytes expected")
+            }
+            GdsError::UnknownRecordType { location, code } => {
+                write!(f, "unknown record type 0x{code:02X} {location}")
+            }
+            GdsError::BadDataType { location, code } => {
+                write!(f, "bad data type 0x{code:02X} {location}")
+            }
+            GdsError::OddRecordLength { location, length } => {
+                write!(f, "bad record length {length} {location}")
+            }
+            GdsError::UnmatchedStructure { location } => {
+                write!(f, "unmatched BGNSTR/ENDSTR {location}")
+            }
+            GdsError::BadReal { location, value } => {
+                write!(f, "bad real number {value} {location}")
+            }
+            GdsError::UnexpectedRecord { location } => {
+                write!(f, "unexpected record {location}")
+            }
+            GdsError::MissingData { location, message } => {
+                write!(f, "{message} {location}")
+            }
+            GdsError::RecordTooLong { location, length } => {
+                write!(f, "record of {length} bytes does not fit {location}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GdsError {}
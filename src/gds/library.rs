@@ -0,0 +1,139 @@
+use i_triangle::i_overlay::i_float::point::IntPoint;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Units {
+    /// size of a database unit in user units
+    pub(crate) user: f64,
+    /// size of a database unit in meters
+    pub(crate) meter: f64,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self { user: 1e-3, meter: 1e-9 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Library {
+    pub(crate) name: String,
+    pub(crate) units: Units,
+    pub(crate) structures: Vec<Structure>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Structure {
+    pub(crate) name: String,
+    pub(crate) elements: Vec<Element>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct STrans {
+    pub(crate) reflection: bool,
+    pub(crate) absolute_magnification: bool,
+    pub(crate) absolute_angle: bool,
+    pub(crate) magnification: f64,
+    /// counterclockwise rotation in degrees
+    pub(crate) angle: f64,
+}
+
+impl Default for STrans {
+    fn default() -> Self {
+        Self {
+            reflection: false,
+            absolute_magnification: false,
+            absolute_angle: false,
+            magnification: 1.0,
+            angle: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Boundary {
+    pub(crate) layer: u16,
+    pub(crate) datatype: u16,
+    pub(crate) points: Vec<IntPoint>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Path {
+    pub(crate) layer: u16,
+    pub(crate) datatype: u16,
+    pub(crate) path_type: u16,
+    /// negative width means absolute (not affected by magnification)
+    pub(crate) width: i32,
+    pub(crate) begin_extension: i32,
+    pub(crate) end_extension: i32,
+    pub(crate) points: Vec<IntPoint>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StructRef {
+    pub(crate) name: String,
+    pub(crate) strans: STrans,
+    pub(crate) position: IntPoint,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ArrayRef {
+    pub(crate) name: String,
+    pub(crate) strans: STrans,
+    pub(crate) columns: u16,
+    pub(crate) rows: u16,
+    /// origin, column displacement point and row displacement point
+    pub(crate) position: IntPoint,
+    pub(crate) column_point: IntPoint,
+    pub(crate) row_point: IntPoint,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Text {
+    pub(crate) layer: u16,
+    pub(crate) text_type: u16,
+    pub(crate) presentation: u16,
+    pub(crate) strans: STrans,
+    pub(crate) position: IntPoint,
+    pub(crate) string: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GdsBox {
+    pub(crate) layer: u16,
+    pub(crate) box_type: u16,
+    pub(crate) points: Vec<IntPoint>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Node {
+    pub(crate) layer: u16,
+    pub(crate) node_type: u16,
+    pub(crate) points: Vec<IntPoint>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Element {
+    Boundary(Boundary),
+    Path(Path),
+    StructRef(StructRef),
+    ArrayRef(ArrayRef),
+    Text(Text),
+    Box(GdsBox),
+    Node(Node),
+}
+
+impl Library {
+    /// Drops every shape and text whose (layer, datatype) is not in `layers`, references are kept.
+    pub(crate) fn retain_layers(&mut self, layers: &[(u16, u16)]) {
+        for structure in self.structures.iter_mut() {
+            structure.elements.retain(|element| match element {
+                Element::Boundary(e) => layers.contains(&(e.layer, e.datatype)),
+                Element::Path(e) => layers.contains(&(e.layer, e.datatype)),
+                Element::Text(e) => layers.contains(&(e.layer, e.text_type)),
+                Element::Box(e) => layers.contains(&(e.layer, e.box_type)),
+                Element::Node(e) => layers.contains(&(e.layer, e.node_type)),
+                Element::StructRef(_) | Element::ArrayRef(_) => true,
+            });
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub(crate) mod error;
+pub(crate) mod library;
+pub(crate) mod reader;
+pub(crate) mod record;
+pub(crate) mod writer;
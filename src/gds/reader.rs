@@ -0,0 +1,778 @@
+use i_triangle::i_overlay::i_float::point::IntPoint;
+use crate::gds::error::{GdsError, Location};
+use crate::gds::library::{ArrayRef, Boundary, Element, GdsBox, Library, Node, Path, STrans, Structure, StructRef, Text, Units};
+use crate::gds::record::{real8_to_f64, DataType, RecordType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReadMode {
+    /// fail on the first error
+    Strict,
+    /// skip bad records and elements, collect the errors as warnings
+    Lenient,
+}
+
+pub(crate) struct Record<'a> {
+    pub(crate) offset: usize,
+    pub(crate) record_type: RecordType,
+    pub(crate) data_type: DataType,
+    pub(crate) data: &'a [u8],
+}
+
+pub(crate) struct RecordReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> RecordReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    /// Reads the next record. The reader always moves past a record whose length is valid,
+    /// so every error except a fatal one can be skipped.
+    pub(crate) fn next_record(&mut self) -> Result<Record<'a>, GdsError> {
+        let offset = self.offset;
+        let rest = &self.data[offset..];
+
+        let record = rest.get(2).and_then(|&code| RecordType::with_code(code)).map_or("UNKNOWN", |r| r.name());
+        let location = Location { offset, record, structure: None };
+
+        if rest.len() < 4 {
+            return Err(GdsError::TruncatedRecord { location, length: 4 });
+        }
+
+        let length = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+        if length < 4 {
+            return Err(GdsError::OddRecordLength { location, length });
+        }
+
+        if length > rest.len() {
+            return Err(GdsError::TruncatedRecord { location, length });
+        }
+
+        self.offset += length;
+
+        if length % 2 == 1 {
+            return Err(GdsError::OddRecordLength { location, length });
+        }
+
+        let record_type = RecordType::with_code(rest[2])
+            .ok_or(GdsError::UnknownRecordType { location: location.clone(), code: rest[2] })?;
+
+        let data_type = DataType::with_code(rest[3])
+            .ok_or(GdsError::BadDataType { location: location.clone(), code: rest[3] })?;
+
+        let data = &rest[4..length];
+        let item_size = match data_type {
+            DataType::NoData | DataType::Ascii => 1,
+            DataType::BitArray | DataType::Int2 => 2,
+            DataType::Int4 | DataType::Real4 => 4,
+            DataType::Real8 => 8,
+        };
+
+        if !data.len().is_multiple_of(item_size) {
+            return Err(GdsError::OddRecordLength { location, length });
+        }
+
+        Ok(Record { offset, record_type, data_type, data })
+    }
+}
+
+impl Record<'_> {
+    fn location(&self) -> Location {
+        Location { offset: self.offset, record: self.record_type.name(), structure: None }
+    }
+
+    fn expect_data_type(&self, data_type: DataType) -> Result<(), GdsError> {
+        if self.data_type == data_type {
+            Ok(())
+        } else {
+            Err(GdsError::BadDataType { location: self.location(), code: self.data_type as u8 })
+        }
+    }
+
+    pub(crate) fn int2_values(&self) -> Result<Vec<i16>, GdsError> {
+        if self.data_type != DataType::BitArray {
+            self.expect_data_type(DataType::Int2)?;
+        }
+        Ok(self.data.chunks_exact(2).map(|b| i16::from_be_bytes([b[0], b[1]])).collect())
+    }
+
+    pub(crate) fn int4_values(&self) -> Result<Vec<i32>, GdsError> {
+        self.expect_data_type(DataType::Int4)?;
+        Ok(self.data.chunks_exact(4).map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]])).collect())
+    }
+
+    pub(crate) fn real8_values(&self) -> Result<Vec<f64>, GdsError> {
+        self.expect_data_type(DataType::Real8)?;
+        Ok(self.data.chunks_exact(8).map(|b| real8_to_f64([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])).collect())
+    }
+
+    pub(crate) fn ascii(&self) -> Result<String, GdsError> {
+        self.expect_data_type(DataType::Ascii)?;
+        // strings are padded with zero to an even length
+        let end = self.data.iter().position(|&b| b == 0).unwrap_or(self.data.len());
+        Ok(String::from_utf8_lossy(&self.data[..end]).into_owned())
+    }
+
+    fn first<T: Copy>(&self, values: Vec<T>) -> Result<T, GdsError> {
+        values.first().copied().ok_or_else(|| self.missing("no data"))
+    }
+
+    pub(crate) fn int2(&self) -> Result<i16, GdsError> {
+        let values = self.int2_values()?;
+        self.first(values)
+    }
+
+    pub(crate) fn int4(&self) -> Result<i32, GdsError> {
+        let values = self.int4_values()?;
+        self.first(values)
+    }
+
+    pub(crate) fn real8(&self) -> Result<f64, GdsError> {
+        let values = self.real8_values()?;
+        self.first(values)
+    }
+
+    /// a real that must be finite and greater than zero
+    fn positive_real8(&self, value: f64) -> Result<f64, GdsError> {
+        if value.is_finite() && value > 0.0 {
+            Ok(value)
+        } else {
+            Err(GdsError::BadReal { location: self.location(), value })
+        }
+    }
+
+    pub(crate) fn points(&self) -> Result<Vec<IntPoint>, GdsError> {
+        let values = self.int4_values()?;
+        if values.len() % 2 == 1 {
+            return Err(GdsError::OddRecordLength { location: self.location(), length: self.data.len() + 4 });
+        }
+        Ok(values.chunks_exact(2).map(|p| IntPoint::new(p[0], p[1])).collect())
+    }
+
+    fn missing(&self, message: &str) -> GdsError {
+        GdsError::MissingData { location: self.location(), message: message.to_string() }
+    }
+
+    fn unexpected(&self) -> GdsError {
+        GdsError::UnexpectedRecord { location: self.location() }
+    }
+}
+
+/// Collects the records of a single element until ENDEL.
+struct ElementBuilder {
+    kind: RecordType,
+    offset: usize,
+    layer: u16,
+    datatype: u16,
+    path_type: u16,
+    width: i32,
+    begin_extension: i32,
+    end_extension: i32,
+    presentation: u16,
+    strans: STrans,
+    columns: u16,
+    rows: u16,
+    name: String,
+    string: String,
+    points: Vec<IntPoint>,
+}
+
+impl ElementBuilder {
+    fn new(kind: RecordType, offset: usize) -> Self {
+        Self {
+            kind,
+            offset,
+            layer: 0,
+            datatype: 0,
+            path_type: 0,
+            width: 0,
+            begin_extension: 0,
+            end_extension: 0,
+            presentation: 0,
+            strans: STrans::default(),
+            columns: 1,
+            rows: 1,
+            name: String::new(),
+            string: String::new(),
+            points: Vec::new(),
+        }
+    }
+
+    fn apply(&mut self, record: &Record) -> Result<(), GdsError> {
+        match record.record_type {
+            RecordType::Layer => self.layer = record.int2()? as u16,
+            RecordType::DataType | RecordType::TextType | RecordType::BoxType | RecordType::NodeType => {
+                self.datatype = record.int2()? as u16
+            }
+            RecordType::PathType => self.path_type = record.int2()? as u16,
+            RecordType::Width => self.width = record.int4()?,
+            RecordType::BgnExtn => self.begin_extension = record.int4()?,
+            RecordType::EndExtn => self.end_extension = record.int4()?,
+            RecordType::Presentation => self.presentation = record.int2()? as u16,
+            RecordType::STrans => {
+                let flags = record.int2()? as u16;
+                self.strans.reflection = flags & 0x8000 != 0;
+                self.strans.absolute_magnification = flags & 0x0004 != 0;
+                self.strans.absolute_angle = flags & 0x0002 != 0;
+            }
+            RecordType::Mag => self.strans.magnification = record.positive_real8(record.real8()?)?,
+            RecordType::Angle => self.strans.angle = record.real8()?,
+            RecordType::ColRow => {
+                let values = record.int2_values()?;
+                if values.len() < 2 {
+                    return Err(record.missing("COLROW needs 2 values"));
+                }
+                self.columns = values[0] as u16;
+                self.rows = values[1] as u16;
+            }
+            RecordType::SName => self.name = record.ascii()?,
+            RecordType::String => self.string = record.ascii()?,
+            RecordType::XY => self.points = record.points()?,
+            RecordType::ElFlags | RecordType::Plex | RecordType::PropAttr | RecordType::PropValue | RecordType::Spacing => {}
+            _ => return Err(record.unexpected()),
+        }
+
+        Ok(())
+    }
+
+    fn build(self) -> Result<Element, GdsError> {
+        let min_points = match self.kind {
+            RecordType::Boundary => 4,
+            RecordType::Path | RecordType::Node => 1,
+            RecordType::Box => 5,
+            RecordType::ARef => 3,
+            _ => 1,
+        };
+
+        if self.points.len() < min_points {
+            return Err(GdsError::MissingData {
+                location: Location { offset: self.offset, record: self.kind.name(), structure: None },
+                message: format!("{} points, at least {min_points} expected", self.points.len()),
+            });
+        }
+
+        let element = match self.kind {
+            RecordType::Boundary => Element::Boundary(Boundary {
+                layer: self.layer,
+                datatype: self.datatype,
+                points: self.points,
+            }),
+            RecordType::Path => Element::Path(Path {
+                layer: self.layer,
+                datatype: self.datatype,
+                path_type: self.path_type,
+                width: self.width,
+                begin_extension: self.begin_extension,
+                end_extension: self.end_extension,
+                points: self.points,
+            }),
+            RecordType::SRef => Element::StructRef(StructRef {
+                name: self.name,
+                strans: self.strans,
+                position: self.points[0],
+            }),
+            RecordType::ARef => Element::ArrayRef(ArrayRef {
+                name: self.name,
+                strans: self.strans,
+                columns: self.columns,
+                rows: self.rows,
+                position: self.points[0],
+                column_point: self.points[1],
+                row_point: self.points[2],
+            }),
+            RecordType::Text => Element::Text(Text {
+                layer: self.layer,
+                text_type: self.datatype,
+                presentation: self.presentation,
+                strans: self.strans,
+                position: self.points[0],
+                string: self.string,
+            }),
+            RecordType::Box => Element::Box(GdsBox {
+                layer: self.layer,
+                box_type: self.datatype,
+                points: self.points,
+            }),
+            _ => Element::Node(Node {
+                layer: self.layer,
+                node_type: self.datatype,
+                points: self.points,
+            }),
+        };
+
+        Ok(element)
+    }
+}
+
+struct Parser<'a> {
+    reader: RecordReader<'a>,
+    mode: ReadMode,
+    structure: Option<String>,
+    warnings: Vec<GdsError>,
+    // a record that ended the previous element or structure too early
+    pending: Option<Record<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Result<Record<'a>, GdsError> {
+        if let Some(record) = self.pending.take() {
+            return Ok(record);
+        }
+        self.reader.next_record()
+    }
+
+    /// Keeps the error as a warning in lenient mode, returns it otherwise.
+    fn recover(&mut self, error: GdsError) -> Result<(), GdsError> {
+        let error = error.in_structure(self.structure.as_deref());
+        if self.mode == ReadMode::Lenient && !error.is_fatal() {
+            self.warnings.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    fn read_library(&mut self, library: &mut Library) -> Result<(), GdsError> {
+        loop {
+            let record = match self.next() {
+                Ok(record) => record,
+                Err(error) => {
+                    self.recover(error)?;
+                    continue;
+                }
+            };
+
+            let result = match record.record_type {
+                RecordType::BgnLib
+                | RecordType::RefLibs
+                | RecordType::Fonts
+                | RecordType::AttrTable
+                | RecordType::Generations
+                | RecordType::Format
+                | RecordType::Mask
+                | RecordType::EndMasks
+                | RecordType::LibDirSize
+                | RecordType::SrfName
+                | RecordType::LibSecur => Ok(()),
+                RecordType::LibName => record.ascii().map(|name| library.name = name),
+                RecordType::Units => Self::read_units(&record).map(|units| library.units = units),
+                RecordType::BgnStr => {
+                    library.structures.push(Structure { name: String::new(), elements: Vec::new() });
+                    let index = library.structures.len() - 1;
+                    let result = self.read_structure(&mut library.structures[index]);
+                    self.structure = None;
+                    result
+                }
+                RecordType::EndStr => Err(GdsError::UnmatchedStructure { location: record.location() }),
+                RecordType::EndLib => break,
+                _ => Err(record.unexpected()),
+            };
+
+            if let Err(error) = result {
+                self.recover(error)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_units(record: &Record) -> Result<Units, GdsError> {
+        let values = record.real8_values()?;
+        if values.len() < 2 {
+            return Err(record.missing("UNITS needs 2 values"));
+        }
+        let user = record.positive_real8(values[0])?;
+        let meter = record.positive_real8(values[1])?;
+
+        Ok(Units { user, meter })
+    }
+
+    fn read_structure(&mut self, structure: &mut Structure) -> Result<(), GdsError> {
+        loop {
+            let record = match self.next() {
+                Ok(record) => record,
+                Err(error) => {
+                    self.recover(error)?;
+                    continue;
+                }
+            };
+
+            let result = match record.record_type {
+                RecordType::StrName => record.ascii().map(|name| {
+                    self.structure = Some(name.clone());
+                    structure.name = name;
+                }),
+                RecordType::StrClass => Ok(()),
+                RecordType::Boundary
+                | RecordType::Path
+                | RecordType::SRef
+                | RecordType::ARef
+                | RecordType::Text
+                | RecordType::Box
+                | RecordType::Node => {
+                    if let Some(element) = self.read_element(&record)? {
+                        structure.elements.push(element);
+                    }
+                    Ok(())
+                }
+                RecordType::EndStr => break,
+                RecordType::BgnStr | RecordType::EndLib => {
+                    // ENDSTR is missing, close the structure and let the library handle the record
+                    let location = record.location();
+                    self.pending = Some(record);
+                    self.recover(GdsError::UnmatchedStructure { location })?;
+                    break;
+                }
+                _ => Err(record.unexpected()),
+            };
+
+            if let Err(error) = result {
+                self.recover(error)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns None if the element was skipped in lenient mode.
+    fn read_element(&mut self, start: &Record) -> Result<Option<Element>, GdsError> {
+        let mut builder = ElementBuilder::new(start.record_type, start.offset);
+        let mut is_valid = true;
+
+        loop {
+            let record = match self.next() {
+                Ok(record) => record,
+                Err(error) => {
+                    self.recover(error)?;
+                    is_valid = false;
+                    continue;
+                }
+            };
+
+            match record.record_type {
+                RecordType::EndEl => break,
+                RecordType::EndStr
+                | RecordType::BgnStr
+                | RecordType::EndLib
+                | RecordType::Boundary
+                | RecordType::Path
+                | RecordType::SRef
+                | RecordType::ARef
+                | RecordType::Text
+                | RecordType::Box
+                | RecordType::Node => {
+                    // ENDEL is missing
+                    let error = GdsError::MissingData { location: record.location(), message: "ENDEL missing before".to_string() };
+                    self.pending = Some(record);
+                    self.recover(error)?;
+                    is_valid = false;
+                    break;
+                }
+                _ => {
+                    if let Err(error) = builder.apply(&record) {
+                        self.recover(error)?;
+                        is_valid = false;
+                    }
+                }
+            }
+        }
+
+        if !is_valid {
+            return Ok(None);
+        }
+
+        match builder.build() {
+            Ok(element) => Ok(Some(element)),
+            Err(error) => {
+                self.recover(error)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Reads a library, in lenient mode returns every skipped problem as a warning.
+/// A truncated stream gives the structures read so far.
+pub(crate) fn read_library(data: &[u8], mode: ReadMode) -> Result<(Library, Vec<GdsError>), GdsError> {
+    let mut parser = Parser { reader: RecordReader::new(data), mode, structure: None, warnings: Vec::new(), pending: None };
+
+    // a stream that does not start with HEADER is not a GDS file at all
+    let header = parser.next()?;
+    if header.record_type != RecordType::Header {
+        return Err(header.unexpected());
+    }
+
+    let mut library = Library { name: String::new(), units: Units::default(), structures: Vec::new() };
+
+    match parser.read_library(&mut library) {
+        Ok(_) => {}
+        Err(error) if mode == ReadMode::Lenient => parser.warnings.push(error),
+        Err(error) => return Err(error),
+    }
+
+    Ok((library, parser.warnings))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::gds::error::GdsError;
+    use crate::gds::library::Element;
+    use crate::gds::library::Library;
+    use crate::gds::reader::{read_library, ReadMode};
+    use crate::gds::record::{DataType, RecordType};
+
+    pub(crate) fn read_strict(data: &[u8]) -> Result<Library, GdsError> {
+        read_library(data, ReadMode::Strict).map(|(library, _)| library)
+    }
+
+    pub(crate) fn push_record(buffer: &mut Vec<u8>, record_type: RecordType, data_type: DataType, data: &[u8]) {
+        let length = (4 + data.len()) as u16;
+        buffer.extend_from_slice(&length.to_be_bytes());
+        buffer.push(record_type as u8);
+        buffer.push(data_type as u8);
+        buffer.extend_from_slice(data);
+    }
+
+    pub(crate) fn int2(values: &[i16]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    pub(crate) fn int4(values: &[i32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    pub(crate) fn ascii(value: &str) -> Vec<u8> {
+        let mut data = value.as_bytes().to_vec();
+        if data.len() % 2 == 1 {
+            data.push(0);
+        }
+        data
+    }
+
+    pub(crate) fn sample_stream() -> Vec<u8> {
+        let mut s = Vec::new();
+        push_record(&mut s, RecordType::Header, DataType::Int2, &int2(&[600]));
+        push_record(&mut s, RecordType::BgnLib, DataType::Int2, &int2(&[0; 12]));
+        push_record(&mut s, RecordType::LibName, DataType::Ascii, &ascii("LIB"));
+        push_record(&mut s, RecordType::Units, DataType::Real8, &[
+            0x3E, 0x41, 0x89, 0x37, 0x4B, 0xC6, 0xA7, 0xF0,
+            0x39, 0x44, 0xB8, 0x2F, 0xA0, 0x9B, 0x5A, 0x54,
+        ]);
+
+        // cell with a single square
+        push_record(&mut s, RecordType::BgnStr, DataType::Int2, &int2(&[0; 12]));
+        push_record(&mut s, RecordType::StrName, DataType::Ascii, &ascii("SQUARE"));
+        push_record(&mut s, RecordType::Boundary, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::Layer, DataType::Int2, &int2(&[1]));
+        push_record(&mut s, RecordType::DataType, DataType::Int2, &int2(&[0]));
+        push_record(&mut s, RecordType::XY, DataType::Int4, &int4(&[0, 0, 0, 10, 10, 10, 10, 0, 0, 0]));
+        push_record(&mut s, RecordType::EndEl, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::EndStr, DataType::NoData, &[]);
+
+        // top cell with a path, a reference, an array and a text
+        push_record(&mut s, RecordType::BgnStr, DataType::Int2, &int2(&[0; 12]));
+        push_record(&mut s, RecordType::StrName, DataType::Ascii, &ascii("TOP"));
+        push_record(&mut s, RecordType::Path, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::Layer, DataType::Int2, &int2(&[2]));
+        push_record(&mut s, RecordType::DataType, DataType::Int2, &int2(&[5]));
+        push_record(&mut s, RecordType::PathType, DataType::Int2, &int2(&[2]));
+        push_record(&mut s, RecordType::Width, DataType::Int4, &int4(&[4]));
+        push_record(&mut s, RecordType::XY, DataType::Int4, &int4(&[0, 20, 40, 20]));
+        push_record(&mut s, RecordType::EndEl, DataType::NoData, &[]);
+
+        push_record(&mut s, RecordType::SRef, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::SName, DataType::Ascii, &ascii("SQUARE"));
+        push_record(&mut s, RecordType::STrans, DataType::BitArray, &int2(&[0]));
+        push_record(&mut s, RecordType::Angle, DataType::Real8, &[0x42, 0x5A, 0, 0, 0, 0, 0, 0]);
+        push_record(&mut s, RecordType::XY, DataType::Int4, &int4(&[100, 0]));
+        push_record(&mut s, RecordType::EndEl, DataType::NoData, &[]);
+
+        push_record(&mut s, RecordType::ARef, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::SName, DataType::Ascii, &ascii("SQUARE"));
+        push_record(&mut s, RecordType::ColRow, DataType::Int2, &int2(&[3, 2]));
+        push_record(&mut s, RecordType::XY, DataType::Int4, &int4(&[0, 100, 60, 100, 0, 140]));
+        push_record(&mut s, RecordType::EndEl, DataType::NoData, &[]);
+
+        push_record(&mut s, RecordType::Text, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::Layer, DataType::Int2, &int2(&[3]));
+        push_record(&mut s, RecordType::TextType, DataType::Int2, &int2(&[0]));
+        push_record(&mut s, RecordType::XY, DataType::Int4, &int4(&[5, 5]));
+        push_record(&mut s, RecordType::String, DataType::Ascii, &ascii("VDD"));
+        push_record(&mut s, RecordType::EndEl, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::EndStr, DataType::NoData, &[]);
+
+        push_record(&mut s, RecordType::EndLib, DataType::NoData, &[]);
+        s
+    }
+
+    #[test]
+    fn test_read_sample() {
+        let library = read_strict(&sample_stream()).unwrap();
+
+        assert_eq!(library.name, "LIB");
+        assert!((library.units.user - 1e-3).abs() < 1e-12);
+        assert!((library.units.meter - 1e-9).abs() < 1e-18);
+        assert_eq!(library.structures.len(), 2);
+        assert_eq!(library.structures[0].name, "SQUARE");
+
+        let top = &library.structures[1];
+        assert_eq!(top.elements.len(), 4);
+
+        match &top.elements[0] {
+            Element::Path(path) => {
+                assert_eq!((path.layer, path.datatype, path.path_type, path.width), (2, 5, 2, 4));
+                assert_eq!(path.points.len(), 2);
+            }
+            _ => panic!("expected PATH"),
+        }
+
+        match &top.elements[1] {
+            Element::StructRef(r) => {
+                assert_eq!(r.name, "SQUARE");
+                assert_eq!(r.strans.angle, 90.0);
+            }
+            _ => panic!("expected SREF"),
+        }
+
+        match &top.elements[2] {
+            Element::ArrayRef(r) => assert_eq!((r.columns, r.rows), (3, 2)),
+            _ => panic!("expected AREF"),
+        }
+
+        match &top.elements[3] {
+            Element::Text(t) => assert_eq!(t.string, "VDD"),
+            _ => panic!("expected TEXT"),
+        }
+    }
+
+    fn find_record(stream: &[u8], record_type: RecordType) -> usize {
+        let mut offset = 0;
+        loop {
+            if stream[offset + 2] == record_type as u8 {
+                return offset;
+            }
+            offset += u16::from_be_bytes([stream[offset], stream[offset + 1]]) as usize;
+        }
+    }
+
+    #[test]
+    fn test_truncated() {
+        let stream = sample_stream();
+        let end = stream.len() - 6;
+        match read_strict(&stream[..end]) {
+            Err(GdsError::TruncatedRecord { location, .. }) => {
+                assert_eq!(location.offset, end - 2);
+                assert_eq!(location.structure.as_deref(), Some("TOP"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // lenient mode keeps everything read before the cut
+        let cut = find_record(&stream, RecordType::Text) + 2;
+        let (library, warnings) = read_library(&stream[..cut], ReadMode::Lenient).unwrap();
+        assert_eq!(library.structures.len(), 2);
+        assert_eq!(library.structures[1].elements.len(), 3);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_record_type() {
+        let mut stream = sample_stream();
+        let offset = find_record(&stream, RecordType::PathType);
+        stream[offset + 2] = 0x70;
+
+        match read_strict(&stream) {
+            Err(GdsError::UnknownRecordType { location, code }) => {
+                assert_eq!(code, 0x70);
+                assert_eq!(location.offset, offset);
+                assert_eq!(location.structure.as_deref(), Some("TOP"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // the PATH is skipped, the rest of TOP survives
+        let (library, warnings) = read_library(&stream, ReadMode::Lenient).unwrap();
+        assert_eq!(library.structures[1].elements.len(), 3);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_bad_data_type() {
+        let mut stream = sample_stream();
+        let offset = find_record(&stream, RecordType::Width);
+        stream[offset + 3] = DataType::Int2 as u8;
+
+        match read_strict(&stream) {
+            Err(GdsError::BadDataType { location, code }) => {
+                assert_eq!(code, DataType::Int2 as u8);
+                assert_eq!(location.record, "WIDTH");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_odd_record_length() {
+        let mut s = Vec::new();
+        push_record(&mut s, RecordType::Header, DataType::Int2, &int2(&[600]));
+        push_record(&mut s, RecordType::BgnStr, DataType::Int2, &int2(&[0; 12]));
+        push_record(&mut s, RecordType::StrName, DataType::Ascii, &ascii("A"));
+        push_record(&mut s, RecordType::Boundary, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::XY, DataType::Int4, &int4(&[0, 0, 0, 1, 1, 1, 1, 0, 0]));
+        push_record(&mut s, RecordType::EndEl, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::EndStr, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::EndLib, DataType::NoData, &[]);
+
+        match read_strict(&s) {
+            Err(GdsError::OddRecordLength { location, length }) => {
+                assert_eq!(length, 40);
+                assert_eq!(location.record, "XY");
+                assert_eq!(location.structure.as_deref(), Some("A"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unmatched_structure() {
+        let mut s = Vec::new();
+        push_record(&mut s, RecordType::Header, DataType::Int2, &int2(&[600]));
+        push_record(&mut s, RecordType::BgnStr, DataType::Int2, &int2(&[0; 12]));
+        push_record(&mut s, RecordType::StrName, DataType::Ascii, &ascii("A"));
+        push_record(&mut s, RecordType::BgnStr, DataType::Int2, &int2(&[0; 12]));
+        push_record(&mut s, RecordType::StrName, DataType::Ascii, &ascii("B"));
+        push_record(&mut s, RecordType::EndStr, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::EndStr, DataType::NoData, &[]);
+        push_record(&mut s, RecordType::EndLib, DataType::NoData, &[]);
+
+        match read_strict(&s) {
+            Err(GdsError::UnmatchedStructure { location }) => {
+                assert_eq!(location.record, "BGNSTR");
+                assert_eq!(location.structure.as_deref(), Some("A"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let (library, warnings) = read_library(&s, ReadMode::Lenient).unwrap();
+        assert_eq!(library.structures.len(), 2);
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(warnings[1], GdsError::UnmatchedStructure { .. }));
+    }
+
+    #[test]
+    fn test_bad_real() {
+        let mut stream = sample_stream();
+        let offset = find_record(&stream, RecordType::Units);
+        stream[offset + 4..offset + 12].fill(0);
+
+        match read_strict(&stream) {
+            Err(GdsError::BadReal { location, value }) => {
+                assert_eq!(value, 0.0);
+                assert_eq!(location.record, "UNITS");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
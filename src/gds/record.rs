@@ -0,0 +1,321 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RecordType {
+    Header,
+    BgnLib,
+    LibName,
+    Units,
+    EndLib,
+    BgnStr,
+    StrName,
+    EndStr,
+    Boundary,
+    Path,
+    SRef,
+    ARef,
+    Text,
+    Layer,
+    DataType,
+    Width,
+    XY,
+    EndEl,
+    SName,
+    ColRow,
+    TextNode,
+    Node,
+    TextType,
+    Presentation,
+    Spacing,
+    String,
+    STrans,
+    Mag,
+    Angle,
+    UInteger,
+    UString,
+    RefLibs,
+    Fonts,
+    PathType,
+    Generations,
+    AttrTable,
+    StypTable,
+    StrType,
+    ElFlags,
+    ElKey,
+    LinkType,
+    LinkKeys,
+    NodeType,
+    PropAttr,
+    PropValue,
+    Box,
+    BoxType,
+    Plex,
+    BgnExtn,
+    EndExtn,
+    TapeNum,
+    TapeCode,
+    StrClass,
+    Reserved,
+    Format,
+    Mask,
+    EndMasks,
+    LibDirSize,
+    SrfName,
+    LibSecur,
+}
+
+const RECORD_TYPES: [RecordType; 60] = [
+    RecordType::Header,
+    RecordType::BgnLib,
+    RecordType::LibName,
+    RecordType::Units,
+    RecordType::EndLib,
+    RecordType::BgnStr,
+    RecordType::StrName,
+    RecordType::EndStr,
+    RecordType::Boundary,
+    RecordType::Path,
+    RecordType::SRef,
+    RecordType::ARef,
+    RecordType::Text,
+    RecordType::Layer,
+    RecordType::DataType,
+    RecordType::Width,
+    RecordType::XY,
+    RecordType::EndEl,
+    RecordType::SName,
+    RecordType::ColRow,
+    RecordType::TextNode,
+    RecordType::Node,
+    RecordType::TextType,
+    RecordType::Presentation,
+    RecordType::Spacing,
+    RecordType::String,
+    RecordType::STrans,
+    RecordType::Mag,
+    RecordType::Angle,
+    RecordType::UInteger,
+    RecordType::UString,
+    RecordType::RefLibs,
+    RecordType::Fonts,
+    RecordType::PathType,
+    RecordType::Generations,
+    RecordType::AttrTable,
+    RecordType::StypTable,
+    RecordType::StrType,
+    RecordType::ElFlags,
+    RecordType::ElKey,
+    RecordType::LinkType,
+    RecordType::LinkKeys,
+    RecordType::NodeType,
+    RecordType::PropAttr,
+    RecordType::PropValue,
+    RecordType::Box,
+    RecordType::BoxType,
+    RecordType::Plex,
+    RecordType::BgnExtn,
+    RecordType::EndExtn,
+    RecordType::TapeNum,
+    RecordType::TapeCode,
+    RecordType::StrClass,
+    RecordType::Reserved,
+    RecordType::Format,
+    RecordType::Mask,
+    RecordType::EndMasks,
+    RecordType::LibDirSize,
+    RecordType::SrfName,
+    RecordType::LibSecur,
+];
+
+impl RecordType {
+    pub(crate) fn with_code(code: u8) -> Option<Self> {
+        RECORD_TYPES.get(code as usize).copied()
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            RecordType::Header => "HEADER",
+            RecordType::BgnLib => "BGNLIB",
+            RecordType::LibName => "LIBNAME",
+            RecordType::Units => "UNITS",
+            RecordType::EndLib => "ENDLIB",
+            RecordType::BgnStr => "BGNSTR",
+            RecordType::StrName => "STRNAME",
+            RecordType::EndStr => "ENDSTR",
+            RecordType::Boundary => "BOUNDARY",
+            RecordType::Path => "PATH",
+            RecordType::SRef => "SREF",
+            RecordType::ARef => "AREF",
+            RecordType::Text => "TEXT",
+            RecordType::Layer => "LAYER",
+            RecordType::DataType => "DATATYPE",
+            RecordType::Width => "WIDTH",
+            RecordType::XY => "XY",
+            RecordType::EndEl => "ENDEL",
+            RecordType::SName => "SNAME",
+            RecordType::ColRow => "COLROW",
+            RecordType::TextNode => "TEXTNODE",
+            RecordType::Node => "NODE",
+            RecordType::TextType => "TEXTTYPE",
+            RecordType::Presentation => "PRESENTATION",
+            RecordType::Spacing => "SPACING",
+            RecordType::String => "STRING",
+            RecordType::STrans => "STRANS",
+            RecordType::Mag => "MAG",
+            RecordType::Angle => "ANGLE",
+            RecordType::UInteger => "UINTEGER",
+            RecordType::UString => "USTRING",
+            RecordType::RefLibs => "REFLIBS",
+            RecordType::Fonts => "FONTS",
+            RecordType::PathType => "PATHTYPE",
+            RecordType::Generations => "GENERATIONS",
+            RecordType::AttrTable => "ATTRTABLE",
+            RecordType::StypTable => "STYPTABLE",
+            RecordType::StrType => "STRTYPE",
+            RecordType::ElFlags => "ELFLAGS",
+            RecordType::ElKey => "ELKEY",
+            RecordType::LinkType => "LINKTYPE",
+            RecordType::LinkKeys => "LINKKEYS",
+            RecordType::NodeType => "NODETYPE",
+            RecordType::PropAttr => "PROPATTR",
+            RecordType::PropValue => "PROPVALUE",
+            RecordType::Box => "BOX",
+            RecordType::BoxType => "BOXTYPE",
+            RecordType::Plex => "PLEX",
+            RecordType::BgnExtn => "BGNEXTN",
+            RecordType::EndExtn => "ENDEXTN",
+            RecordType::TapeNum => "TAPENUM",
+            RecordType::TapeCode => "TAPECODE",
+            RecordType::StrClass => "STRCLASS",
+            RecordType::Reserved => "RESERVED",
+            RecordType::Format => "FORMAT",
+            RecordType::Mask => "MASK",
+            RecordType::EndMasks => "ENDMASKS",
+            RecordType::LibDirSize => "LIBDIRSIZE",
+            RecordType::SrfName => "SRFNAME",
+            RecordType::LibSecur => "LIBSECUR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DataType {
+    NoData,
+    BitArray,
+    Int2,
+    Int4,
+    Real4,
+    Real8,
+    Ascii,
+}
+
+impl DataType {
+    pub(crate) fn with_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(DataType::NoData),
+            1 => Some(DataType::BitArray),
+            2 => Some(DataType::Int2),
+            3 => Some(DataType::Int4),
+            4 => Some(DataType::Real4),
+            5 => Some(DataType::Real8),
+            6 => Some(DataType::Ascii),
+            _ => None,
+        }
+    }
+}
+
+/// Converts an 8-byte GDSII real (sign bit, excess-64 base-16 exponent, 56-bit mantissa).
+pub(crate) fn real8_to_f64(bytes: [u8; 8]) -> f64 {
+    let sign = bytes[0] & 0x80 != 0;
+    let exponent = (bytes[0] & 0x7F) as i32 - 64;
+
+    let mut mantissa: u64 = 0;
+    for &b in bytes[1..].iter() {
+        mantissa = (mantissa << 8) | b as u64;
+    }
+
+    // mantissa is a fraction 0.m in [1/16, 1)
+    let value = mantissa as f64 / (1u64 << 56) as f64 * 16f64.powi(exponent);
+
+    if sign { -value } else { value }
+}
+
+/// Converts to an 8-byte GDSII real, the inverse of `real8_to_f64`.
+pub(crate) fn f64_to_real8(value: f64) -> [u8; 8] {
+    if value == 0.0 || !value.is_finite() {
+        return [0; 8];
+    }
+
+    let mut fraction = value.abs();
+    let mut exponent: i32 = 64;
+    while fraction >= 1.0 {
+        fraction /= 16.0;
+        exponent += 1;
+    }
+    while fraction < 1.0 / 16.0 {
+        fraction *= 16.0;
+        exponent -= 1;
+    }
+
+    let mut mantissa = (fraction * (1u64 << 56) as f64).round() as u64;
+    if mantissa >= 1u64 << 56 {
+        // rounding overflow, 0.FFF... became 1.0
+        mantissa >>= 4;
+        exponent += 1;
+    }
+
+    if !(0..=127).contains(&exponent) {
+        return [0; 8];
+    }
+
+    let mut bytes = [0u8; 8];
+    bytes[0] = exponent as u8;
+    if value < 0.0 {
+        bytes[0] |= 0x80;
+    }
+    bytes[1..].copy_from_slice(&mantissa.to_be_bytes()[1..]);
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gds::record::{f64_to_real8, real8_to_f64, RecordType};
+
+    #[test]
+    fn test_real8_units() {
+        // 1e-3 and 1e-9, the most common UNITS values
+        let user = [0x3E, 0x41, 0x89, 0x37, 0x4B, 0xC6, 0xA7, 0xF0];
+        let meter = [0x39, 0x44, 0xB8, 0x2F, 0xA0, 0x9B, 0x5A, 0x54];
+
+        assert!((real8_to_f64(user) - 1e-3).abs() < 1e-15);
+        assert!((real8_to_f64(meter) - 1e-9).abs() < 1e-21);
+    }
+
+    #[test]
+    fn test_real8_simple() {
+        assert_eq!(real8_to_f64([0x41, 0x10, 0, 0, 0, 0, 0, 0]), 1.0);
+        assert_eq!(real8_to_f64([0xC1, 0x10, 0, 0, 0, 0, 0, 0]), -1.0);
+        assert_eq!(real8_to_f64([0x42, 0x5A, 0, 0, 0, 0, 0, 0]), 90.0);
+        assert_eq!(real8_to_f64([0; 8]), 0.0);
+    }
+
+    #[test]
+    fn test_real8_round_trip() {
+        assert_eq!(f64_to_real8(1.0), [0x41, 0x10, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(f64_to_real8(-1.0), [0xC1, 0x10, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(f64_to_real8(90.0), [0x42, 0x5A, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(f64_to_real8(0.0), [0; 8]);
+
+        for value in [1e-3, 1e-9, 2.5e-10, 0.5, 45.0, 270.0, 123456.789] {
+            let restored = real8_to_f64(f64_to_real8(value));
+            assert!((restored - value).abs() <= value * 1e-15, "{value} != {restored}");
+        }
+    }
+
+    #[test]
+    fn test_record_codes() {
+        assert_eq!(RecordType::with_code(0x08), Some(RecordType::Boundary));
+        assert_eq!(RecordType::with_code(0x2D), Some(RecordType::Box));
+        assert_eq!(RecordType::LibSecur as u8, 0x3B);
+        assert_eq!(RecordType::with_code(0x3C), None);
+    }
+}
@@ -0,0 +1,285 @@
+use i_triangle::i_overlay::i_float::point::IntPoint;
+use crate::gds::error::{GdsError, Location};
+use crate::gds::library::{Element, Library, STrans};
+use crate::gds::record::{f64_to_real8, DataType, RecordType};
+
+// a record length is stored in 16 bits
+const MAX_RECORD_LENGTH: usize = 0xFFFF - 1;
+
+struct RecordWriter {
+    buffer: Vec<u8>,
+    structure: Option<String>,
+}
+
+impl RecordWriter {
+    fn record(&mut self, record_type: RecordType, data_type: DataType, data: &[u8]) -> Result<(), GdsError> {
+        let length = 4 + data.len();
+        if length > MAX_RECORD_LENGTH {
+            return Err(GdsError::RecordTooLong {
+                location: Location { offset: self.buffer.len(), record: record_type.name(), structure: self.structure.clone() },
+                length,
+            });
+        }
+
+        self.buffer.extend_from_slice(&(length as u16).to_be_bytes());
+        self.buffer.push(record_type as u8);
+        self.buffer.push(data_type as u8);
+        self.buffer.extend_from_slice(data);
+
+        Ok(())
+    }
+
+    fn no_data(&mut self, record_type: RecordType) -> Result<(), GdsError> {
+        self.record(record_type, DataType::NoData, &[])
+    }
+
+    fn int2(&mut self, record_type: RecordType, values: &[i16]) -> Result<(), GdsError> {
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+        self.record(record_type, DataType::Int2, &data)
+    }
+
+    fn int4(&mut self, record_type: RecordType, values: &[i32]) -> Result<(), GdsError> {
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+        self.record(record_type, DataType::Int4, &data)
+    }
+
+    fn real8(&mut self, record_type: RecordType, values: &[f64]) -> Result<(), GdsError> {
+        let data: Vec<u8> = values.iter().flat_map(|&v| f64_to_real8(v)).collect();
+        self.record(record_type, DataType::Real8, &data)
+    }
+
+    fn ascii(&mut self, record_type: RecordType, value: &str) -> Result<(), GdsError> {
+        let mut data = value.as_bytes().to_vec();
+        if !data.len().is_multiple_of(2) {
+            data.push(0);
+        }
+        self.record(record_type, DataType::Ascii, &data)
+    }
+
+    fn points(&mut self, points: &[IntPoint]) -> Result<(), GdsError> {
+        let values: Vec<i32> = points.iter().flat_map(|p| [p.x, p.y]).collect();
+        self.int4(RecordType::XY, &values)
+    }
+
+    fn layer(&mut self, layer: u16, record_type: RecordType, datatype: u16) -> Result<(), GdsError> {
+        self.int2(RecordType::Layer, &[layer as i16])?;
+        self.int2(record_type, &[datatype as i16])
+    }
+
+    fn strans(&mut self, strans: &STrans) -> Result<(), GdsError> {
+        if *strans == STrans::default() {
+            return Ok(());
+        }
+
+        let mut flags: u16 = 0;
+        if strans.reflection {
+            flags |= 0x8000;
+        }
+        if strans.absolute_magnification {
+            flags |= 0x0004;
+        }
+        if strans.absolute_angle {
+            flags |= 0x0002;
+        }
+
+        let data = flags.to_be_bytes();
+        self.record(RecordType::STrans, DataType::BitArray, &data)?;
+
+        if strans.magnification != 1.0 {
+            self.real8(RecordType::Mag, &[strans.magnification])?;
+        }
+        if strans.angle != 0.0 {
+            self.real8(RecordType::Angle, &[strans.angle])?;
+        }
+
+        Ok(())
+    }
+
+    fn element(&mut self, element: &Element) -> Result<(), GdsError> {
+        match element {
+            Element::Boundary(boundary) => {
+                self.no_data(RecordType::Boundary)?;
+                self.layer(boundary.layer, RecordType::DataType, boundary.datatype)?;
+                self.points(&boundary.points)?;
+            }
+            Element::Path(path) => {
+                self.no_data(RecordType::Path)?;
+                self.layer(path.layer, RecordType::DataType, path.datatype)?;
+                if path.path_type != 0 {
+                    self.int2(RecordType::PathType, &[path.path_type as i16])?;
+                }
+                if path.width != 0 {
+                    self.int4(RecordType::Width, &[path.width])?;
+                }
+                if path.path_type == 4 {
+                    self.int4(RecordType::BgnExtn, &[path.begin_extension])?;
+                    self.int4(RecordType::EndExtn, &[path.end_extension])?;
+                }
+                self.points(&path.points)?;
+            }
+            Element::StructRef(reference) => {
+                self.no_data(RecordType::SRef)?;
+                self.ascii(RecordType::SName, &reference.name)?;
+                self.strans(&reference.strans)?;
+                self.points(&[reference.position])?;
+            }
+            Element::ArrayRef(reference) => {
+                self.no_data(RecordType::ARef)?;
+                self.ascii(RecordType::SName, &reference.name)?;
+                self.strans(&reference.strans)?;
+                self.int2(RecordType::ColRow, &[reference.columns as i16, reference.rows as i16])?;
+                self.points(&[reference.position, reference.column_point, reference.row_point])?;
+            }
+            Element::Text(text) => {
+                self.no_data(RecordType::Text)?;
+                self.layer(text.layer, RecordType::TextType, text.text_type)?;
+                if text.presentation != 0 {
+                    self.record(RecordType::Presentation, DataType::BitArray, &text.presentation.to_be_bytes())?;
+                }
+                self.strans(&text.strans)?;
+                self.points(&[text.position])?;
+                self.ascii(RecordType::String, &text.string)?;
+            }
+            Element::Box(gds_box) => {
+                self.no_data(RecordType::Box)?;
+                self.layer(gds_box.layer, RecordType::BoxType, gds_box.box_type)?;
+                self.points(&gds_box.points)?;
+            }
+            Element::Node(node) => {
+                self.no_data(RecordType::Node)?;
+                self.layer(node.layer, RecordType::NodeType, node.node_type)?;
+                self.points(&node.points)?;
+            }
+        }
+
+        self.no_data(RecordType::EndEl)
+    }
+}
+
+/// Serializes a library to a GDSII stream. Modification and access dates are written as zeros,
+/// so the same library always gives the same bytes.
+pub(crate) fn write_library(library: &Library) -> Result<Vec<u8>, GdsError> {
+    let mut writer = RecordWriter { buffer: Vec::new(), structure: None };
+
+    writer.int2(RecordType::Header, &[600])?;
+    writer.int2(RecordType::BgnLib, &[0; 12])?;
+    writer.ascii(RecordType::LibName, &library.name)?;
+    writer.real8(RecordType::Units, &[library.units.user, library.units.meter])?;
+
+    for structure in library.structures.iter() {
+        writer.int2(RecordType::BgnStr, &[0; 12])?;
+        writer.ascii(RecordType::StrName, &structure.name)?;
+        writer.structure = Some(structure.name.clone());
+
+        for element in structure.elements.iter() {
+            writer.element(element)?;
+        }
+
+        writer.no_data(RecordType::EndStr)?;
+        writer.structure = None;
+    }
+
+    writer.no_data(RecordType::EndLib)?;
+
+    Ok(writer.buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use i_triangle::i_overlay::i_float::point::IntPoint;
+    use crate::gds::error::GdsError;
+    use crate::gds::library::{Boundary, Element, GdsBox, Library, Node, Path, STrans, Structure, StructRef, Text, Units};
+    use crate::gds::reader::tests::{read_strict, sample_stream};
+    use crate::gds::writer::write_library;
+
+    #[test]
+    fn test_round_trip_sample() {
+        let library = read_strict(&sample_stream()).unwrap();
+        let stream = write_library(&library).unwrap();
+        let restored = read_strict(&stream).unwrap();
+
+        assert_eq!(library, restored);
+        assert_eq!(stream, write_library(&restored).unwrap());
+    }
+
+    #[test]
+    fn test_round_trip_docs_sample() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/docs/10t_cells.gds");
+        let data = std::fs::read(path).unwrap();
+        let library = read_strict(&data).unwrap();
+        let restored = read_strict(&write_library(&library).unwrap()).unwrap();
+
+        assert_eq!(library.structures.len(), 142);
+        assert_eq!(library, restored);
+    }
+
+    #[test]
+    fn test_round_trip_all_elements() {
+        let square = vec![IntPoint::new(0, 0), IntPoint::new(0, 5), IntPoint::new(5, 5), IntPoint::new(5, 0), IntPoint::new(0, 0)];
+        let library = Library {
+            name: "FIXTURE".to_string(),
+            units: Units { user: 1e-3, meter: 1e-9 },
+            structures: vec![
+                Structure {
+                    name: "CELL".to_string(),
+                    elements: vec![
+                        Element::Boundary(Boundary { layer: 10, datatype: 3, points: square.clone() }),
+                        Element::Path(Path {
+                            layer: 11,
+                            datatype: 0,
+                            path_type: 4,
+                            width: -20,
+                            begin_extension: 3,
+                            end_extension: 7,
+                            points: vec![IntPoint::new(-10, 0), IntPoint::new(10, 0), IntPoint::new(10, 30)],
+                        }),
+                        Element::Text(Text {
+                            layer: 63,
+                            text_type: 1,
+                            presentation: 0b1010,
+                            strans: STrans { magnification: 0.5, ..STrans::default() },
+                            position: IntPoint::new(1, 2),
+                            string: "clk".to_string(),
+                        }),
+                    ],
+                },
+                Structure {
+                    name: "TOP".to_string(),
+                    elements: vec![
+                        Element::StructRef(StructRef {
+                            name: "CELL".to_string(),
+                            strans: STrans { reflection: true, angle: 270.0, magnification: 2.0, ..STrans::default() },
+                            position: IntPoint::new(-1000, 2000),
+                        }),
+                        Element::Box(GdsBox { layer: 1, box_type: 2, points: square.clone() }),
+                        Element::Node(Node { layer: 4, node_type: 5, points: vec![IntPoint::new(3, 3)] }),
+                    ],
+                },
+            ],
+        };
+
+        let restored = read_strict(&write_library(&library).unwrap()).unwrap();
+        assert_eq!(library, restored);
+    }
+
+    #[test]
+    fn test_record_too_long() {
+        let points = (0..9000).map(|i| IntPoint::new(i, i % 2)).collect();
+        let library = Library {
+            name: "BIG".to_string(),
+            units: Units::default(),
+            structures: vec![Structure {
+                name: "A".to_string(),
+                elements: vec![Element::Boundary(Boundary { layer: 0, datatype: 0, points })],
+            }],
+        };
+
+        match write_library(&library) {
+            Err(GdsError::RecordTooLong { location, length }) => {
+                assert_eq!(length, 4 + 9000 * 8);
+                assert_eq!(location.structure.as_deref(), Some("A"));
+            }
+            other => panic!("unexpected {:?}", other.map(|s| s.len())),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use i_triangle::i_overlay::i_float::point::IntPoint;
+use i_triangle::i_overlay::i_float::rect::IntRect;
+use crate::gds::library::STrans;
+
+/// x' = a * x + b * y + tx
+/// y' = c * x + d * y + ty
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Affine {
+    pub(crate) a: f64,
+    pub(crate) b: f64,
+    pub(crate) c: f64,
+    pub(crate) d: f64,
+    pub(crate) tx: f64,
+    pub(crate) ty: f64,
+}
+
+impl Affine {
+    pub(crate) const IDENTITY: Self = Self { a: 1.0, b: 0.0, c: 0.0, d: 1.0, tx: 0.0, ty: 0.0 };
+
+    pub(crate) fn translation(x: f64, y: f64) -> Self {
+        Self { tx: x, ty: y, ..Self::IDENTITY }
+    }
+
+    /// GDS placement: mirror about x-axis, magnify, rotate counterclockwise and move to position.
+    pub(crate) fn with_strans(strans: &STrans, position: IntPoint) -> Self {
+        let radians = strans.angle.to_radians();
+        let (sin, cos) = snap_sin_cos(radians);
+        let m = strans.magnification;
+        let f = if strans.reflection { -1.0 } else { 1.0 };
+
+        Self {
+            a: m * cos,
+            b: -m * sin * f,
+            c: m * sin,
+            d: m * cos * f,
+            tx: position.x as f64,
+            ty: position.y as f64,
+        }
+    }
+
+    /// Returns the transform that applies `other` first and `self` after.
+    pub(crate) fn multiply(&self, other: &Affine) -> Affine {
+        Affine {
+            a: self.a * other.a + self.b * other.c,
+            b: self.a * other.b + self.b * other.d,
+            c: self.c * other.a + self.d * other.c,
+            d: self.c * other.b + self.d * other.d,
+            tx: self.a * other.tx + self.b * other.ty + self.tx,
+            ty: self.c * other.tx + self.d * other.ty + self.ty,
+        }
+    }
+
+    /// How much the linear part scales lengths, the magnification of a GDS placement.
+    #[inline]
+    pub(crate) fn scale(&self) -> f64 {
+        (self.a * self.d - self.b * self.c).abs().sqrt()
+    }
+
+    /// None for a transform that collapses the plane.
+    pub(crate) fn inverse(&self) -> Option<Affine> {
+        let det = self.a * self.d - self.b * self.c;
+        if det == 0.0 {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+        Some(Affine { a, b, c, d, tx: -(a * self.tx + b * self.ty), ty: -(c * self.tx + d * self.ty) })
+    }
+
+    #[inline]
+    pub(crate) fn transform(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.a * x + self.b * y + self.tx, self.c * x + self.d * y + self.ty)
+    }
+
+    #[inline]
+    pub(crate) fn transform_int_point(&self, point: IntPoint) -> IntPoint {
+        let (x, y) = self.transform(point.x as f64, point.y as f64);
+        IntPoint::new(x.round() as i32, y.round() as i32)
+    }
+
+    /// Bounding box of the transformed rectangle.
+    pub(crate) fn transform_rect(&self, rect: &IntRect) -> IntRect {
+        let corners = [
+            IntPoint::new(rect.min_x, rect.min_y),
+            IntPoint::new(rect.min_x, rect.max_y),
+            IntPoint::new(rect.max_x, rect.max_y),
+            IntPoint::new(rect.max_x, rect.min_y),
+        ].map(|p| self.transform_int_point(p));
+
+        IntRect {
+            min_x: corners.iter().map(|p| p.x).min().unwrap_or(0),
+            max_x: corners.iter().map(|p| p.x).max().unwrap_or(0),
+            min_y: corners.iter().map(|p| p.y).min().unwrap_or(0),
+            max_y: corners.iter().map(|p| p.y).max().unwrap_or(0),
+        }
+    }
+}
+
+// exact values for multiples of 90°, the only angles real layouts use
+fn snap_sin_cos(radians: f64) -> (f64, f64) {
+    let (sin, cos) = radians.sin_cos();
+    let snap = |v: f64| {
+        let r = v.round();
+        if (v - r).abs() < 1e-12 { r } else { v }
+    };
+    (snap(sin), snap(cos))
+}
+
+#[cfg(test)]
+mod tests {
+    use i_triangle::i_overlay::i_float::point::IntPoint;
+    use crate::gds::library::STrans;
+    use crate::geometry::affine::Affine;
+
+    #[test]
+    fn test_rotation() {
+        let strans = STrans { angle: 90.0, ..STrans::default() };
+        let t = Affine::with_strans(&strans, IntPoint::new(100, 0));
+        assert_eq!(t.transform_int_point(IntPoint::new(10, 0)), IntPoint::new(100, 10));
+    }
+
+    #[test]
+    fn test_reflection() {
+        let strans = STrans { reflection: true, angle: 90.0, ..STrans::default() };
+        let t = Affine::with_strans(&strans, IntPoint::new(0, 0));
+        // mirror first: (10, 5) -> (10, -5), then rotate: (5, 10)
+        assert_eq!(t.transform_int_point(IntPoint::new(10, 5)), IntPoint::new(5, 10));
+    }
+
+    #[test]
+    fn test_multiply() {
+        let parent = Affine::translation(5.0, 7.0);
+        let strans = STrans { magnification: 2.0, ..STrans::default() };
+        let child = Affine::with_strans(&strans, IntPoint::new(1, 1));
+        let t = parent.multiply(&child);
+        assert_eq!(t.transform_int_point(IntPoint::new(3, 4)), IntPoint::new(12, 16));
+    }
+
+    #[test]
+    fn test_inverse() {
+        let strans = STrans { reflection: true, angle: 90.0, magnification: 2.0, ..STrans::default() };
+        let t = Affine::with_strans(&strans, IntPoint::new(7, -3));
+        let inverse = t.inverse().unwrap();
+        assert_eq!(inverse.transform_int_point(t.transform_int_point(IntPoint::new(10, 5))), IntPoint::new(10, 5));
+        assert_eq!(t.multiply(&inverse).transform_int_point(IntPoint::new(-4, 9)), IntPoint::new(-4, 9));
+        assert_eq!(Affine { a: 0.0, d: 0.0, ..Affine::IDENTITY }.inverse(), None);
+    }
+}
@@ -1,3 +1,6 @@
 pub(crate) mod point;
 pub(crate) mod size;
 pub(crate) mod rect;
+pub(crate) mod affine;
+pub(crate) mod outline;
+pub(crate) mod spatial;
\ No newline at end of file
@@ -0,0 +1,256 @@
+use std::f64::consts::PI;
+use i_triangle::i_overlay::core::fill_rule::FillRule;
+use i_triangle::i_overlay::core::simplify::Simplify;
+use i_triangle::i_overlay::i_float::point::IntPoint;
+use i_triangle::i_overlay::i_shape::int::path::{IntPath, PointPathExtension};
+use i_triangle::i_overlay::i_shape::int::shape::IntShape;
+
+// longer miters are cut, this keeps the 45 degree turns of 135 degrees sharp
+const MITER_LIMIT: f64 = 2.7;
+
+// the largest distance between a round cap and its polygon in database units
+const ROUND_TOLERANCE: f64 = 0.5;
+
+/// How the ends of a path are finished.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PathCap {
+    /// pathtype 0, the path ends at its first and last point
+    Flush,
+    /// pathtype 1, a half circle around the end points
+    Round,
+    /// pathtype 2 with half width extensions and pathtype 4 with custom ones
+    Extended { begin: f64, end: f64 },
+}
+
+impl PathCap {
+    pub(crate) fn with_path_type(path_type: u16, width: f64, begin_extension: i32, end_extension: i32) -> Self {
+        match path_type {
+            1 => PathCap::Round,
+            2 => PathCap::Extended { begin: 0.5 * width, end: 0.5 * width },
+            4 => PathCap::Extended { begin: begin_extension as f64, end: end_extension as f64 },
+            _ => PathCap::Flush,
+        }
+    }
+}
+
+/// Outlines an open path of the given width. The result is the union of the segment
+/// rectangles, the outer corners of the joins and the caps, so it is exact for any turn,
+/// joins are mitered up to `MITER_LIMIT` and beveled after.
+pub(crate) fn outline_path(points: &[IntPoint], width: f64, cap: PathCap) -> Vec<IntShape> {
+    let mut centers: Vec<(f64, f64)> = points.iter().map(|p| (p.x as f64, p.y as f64)).collect();
+    centers.dedup();
+
+    let r = 0.5 * width;
+    if r <= 0.0 || centers.is_empty() {
+        return Vec::new();
+    }
+
+    let mut pieces: Vec<IntPath> = Vec::with_capacity(2 * centers.len() + 2);
+
+    if centers.len() == 1 {
+        // a single point has no direction, it is drawn along the x axis
+        let p = centers[0];
+        match cap {
+            PathCap::Flush => {}
+            PathCap::Round => {
+                pieces.push(arc(p, (0.0, 1.0), r, 2.0 * PI));
+            }
+            PathCap::Extended { begin, end } => {
+                pieces.push(vec![
+                    round_point(p.0 - begin, p.1 - r),
+                    round_point(p.0 + end, p.1 - r),
+                    round_point(p.0 + end, p.1 + r),
+                    round_point(p.0 - begin, p.1 + r),
+                ]);
+            }
+        }
+        return union(pieces);
+    }
+
+    let directions: Vec<(f64, f64)> = centers.windows(2)
+        .map(|s| {
+            let (dx, dy) = (s[1].0 - s[0].0, s[1].1 - s[0].1);
+            let l = (dx * dx + dy * dy).sqrt();
+            (dx / l, dy / l)
+        })
+        .collect();
+
+    let (begin, end) = match cap {
+        PathCap::Extended { begin, end } => (begin, end),
+        _ => (0.0, 0.0),
+    };
+
+    let last = directions.len() - 1;
+    for (i, &u) in directions.iter().enumerate() {
+        let n = (-u.1, u.0);
+        let e0 = if i == 0 { begin } else { 0.0 };
+        let e1 = if i == last { end } else { 0.0 };
+        let a = (centers[i].0 - u.0 * e0, centers[i].1 - u.1 * e0);
+        let b = (centers[i + 1].0 + u.0 * e1, centers[i + 1].1 + u.1 * e1);
+
+        pieces.push(vec![offset(a, n, -r), offset(b, n, -r), offset(b, n, r), offset(a, n, r)]);
+    }
+
+    for (i, window) in directions.windows(2).enumerate() {
+        if let Some(corner) = join(centers[i + 1], window[0], window[1], r) {
+            pieces.push(corner);
+        }
+    }
+
+    if cap == PathCap::Round {
+        let (u0, u1) = (directions[0], directions[last]);
+        // from the left side around the back to the right one, the end goes around the front
+        pieces.push(arc(centers[0], (-u0.1, u0.0), r, PI));
+        pieces.push(arc(centers[last + 1], (u1.1, -u1.0), r, PI));
+    }
+
+    union(pieces)
+}
+
+// the outer corner between two segments meeting at `p`, None for a straight continuation
+fn join(p: (f64, f64), ua: (f64, f64), ub: (f64, f64), r: f64) -> Option<IntPath> {
+    let cross = ua.0 * ub.1 - ua.1 * ub.0;
+    let cos = ua.0 * ub.0 + ua.1 * ub.1;
+    if cross.abs() < 1e-12 && cos > 0.0 {
+        return None;
+    }
+
+    // a left turn opens the right side
+    let side = if cross > 0.0 { -r } else { r };
+    let na = (-ua.1, ua.0);
+    let nb = (-ub.1, ub.0);
+    let qa = offset(p, na, side);
+    let qb = offset(p, nb, side);
+    let center = round_point(p.0, p.1);
+
+    // the miter is 1 / cos(turn / 2) half widths away from the center
+    if 1.0 + cos > 2.0 / (MITER_LIMIT * MITER_LIMIT) {
+        let k = side / (1.0 + cos);
+        let miter = round_point(p.0 + (na.0 + nb.0) * k, p.1 + (na.1 + nb.1) * k);
+        Some(vec![center, qa, miter, qb])
+    } else {
+        Some(vec![center, qa, qb])
+    }
+}
+
+// a circular arc of `angle` from the direction `start` counterclockwise, closed through the center
+fn arc(p: (f64, f64), start: (f64, f64), r: f64, angle: f64) -> IntPath {
+    let step = 2.0 * (1.0 - (ROUND_TOLERANCE / r).min(1.0)).acos();
+    let steps = ((angle / step).ceil() as usize).clamp(2, 128);
+    let a0 = start.1.atan2(start.0);
+
+    let mut path: IntPath = (0..=steps)
+        .map(|i| {
+            let a = a0 + angle * i as f64 / steps as f64;
+            round_point(p.0 + r * a.cos(), p.1 + r * a.sin())
+        })
+        .collect();
+
+    if angle < 2.0 * PI {
+        path.push(round_point(p.0, p.1));
+    } else {
+        path.pop();
+    }
+
+    path
+}
+
+fn union(pieces: Vec<IntPath>) -> Vec<IntShape> {
+    let mut paths = Vec::with_capacity(pieces.len());
+    for mut path in pieces {
+        path.dedup();
+        if path.len() > 1 && path.first() == path.last() {
+            path.pop();
+        }
+        if path.len() < 3 {
+            continue;
+        }
+        // all pieces turn the same way, so the non-zero rule gives their union
+        match path.unsafe_area() {
+            0 => continue,
+            area if area < 0 => path.reverse(),
+            _ => {}
+        }
+        paths.push(path);
+    }
+
+    paths.simplify(FillRule::NonZero, 0)
+}
+
+#[inline]
+fn offset(p: (f64, f64), n: (f64, f64), r: f64) -> IntPoint {
+    round_point(p.0 + n.0 * r, p.1 + n.1 * r)
+}
+
+#[inline]
+fn round_point(x: f64, y: f64) -> IntPoint {
+    IntPoint::new(x.round() as i32, y.round() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use i_triangle::i_overlay::i_float::point::IntPoint;
+    use i_triangle::i_overlay::i_shape::int::path::PointPathExtension;
+    use i_triangle::i_overlay::i_shape::int::shape::IntShape;
+    use crate::geometry::outline::{outline_path, PathCap};
+
+    fn area(shapes: &[IntShape]) -> f64 {
+        // outer contours and holes have opposite signs
+        let doubled: i64 = shapes.iter().flatten().map(|path| path.unsafe_area()).sum();
+        0.5 * doubled.abs() as f64
+    }
+
+    fn outline_area(points: &[(i32, i32)], width: f64, cap: PathCap) -> f64 {
+        let points: Vec<IntPoint> = points.iter().map(|&(x, y)| IntPoint::new(x, y)).collect();
+        area(&outline_path(&points, width, cap))
+    }
+
+    #[test]
+    fn test_caps() {
+        let line = [(0, 0), (100, 0)];
+        assert_eq!(outline_area(&line, 20.0, PathCap::Flush), 2000.0);
+        assert_eq!(outline_area(&line, 20.0, PathCap::with_path_type(2, 20.0, 0, 0)), 2400.0);
+        assert_eq!(outline_area(&line, 20.0, PathCap::with_path_type(4, 20.0, 5, 15)), 2400.0);
+        assert_eq!(outline_area(&line, 20.0, PathCap::with_path_type(4, 20.0, -10, 0)), 1800.0);
+
+        // a polygon inside the half circles
+        let round = outline_area(&line, 20.0, PathCap::Round);
+        let exact = 2000.0 + PI * 100.0;
+        assert!(round <= exact && round > 0.99 * exact, "{round}");
+
+        assert_eq!(outline_area(&[(5, 5)], 20.0, PathCap::Flush), 0.0);
+        assert_eq!(outline_area(&[(5, 5), (5, 5)], 20.0, PathCap::Extended { begin: 10.0, end: 10.0 }), 400.0);
+        assert_eq!(outline_area(&line, 0.0, PathCap::Round), 0.0);
+    }
+
+    #[test]
+    fn test_joins() {
+        // a mitered path covers its width times the center line length
+        let l_shape = [(0, 0), (100, 0), (100, 100)];
+        assert_eq!(outline_area(&l_shape, 20.0, PathCap::Flush), 4000.0);
+        let l_points: Vec<IntPoint> = l_shape.iter().map(|&(x, y)| IntPoint::new(x, y)).collect();
+        let shapes = outline_path(&l_points, 20.0, PathCap::Flush);
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(shapes[0].len(), 1);
+        assert_eq!(shapes[0][0].len(), 6);
+
+        let zigzag = [(0, 0), (100, 0), (100, 100), (200, 100), (200, 0)];
+        assert_eq!(outline_area(&zigzag, 20.0, PathCap::Flush), 8000.0);
+
+        // 45 degree turns, the offset points are snapped to the grid, which costs
+        // up to half a unit of width along the diagonal
+        let diagonal = [(0, 0), (100, 0), (200, 100)];
+        let exact = 20.0 * (100.0 + 100.0 * 2f64.sqrt());
+        let a = outline_area(&diagonal, 20.0, PathCap::Flush);
+        assert!((a - exact).abs() < 0.01 * exact, "{a}");
+        let sharp = [(0, 0), (100, 0), (0, 100)];
+        let exact = 20.0 * (100.0 + 100.0 * 2f64.sqrt());
+        let a = outline_area(&sharp, 20.0, PathCap::Flush);
+        assert!((a - exact).abs() < 0.01 * exact, "{a}");
+
+        // a turn back is beveled, the corner stays within the width
+        let back = outline_area(&[(0, 0), (100, 0), (0, 1)], 20.0, PathCap::Flush);
+        assert!(back > 2000.0 && back < 2300.0, "{back}");
+    }
+}
@@ -1,31 +1,41 @@
+use i_triangle::i_overlay::i_float::rect::IntRect;
 use crate::geometry::point::Point;
 use crate::geometry::size::Size;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) struct Rect {
     pub(crate) center: Point,
     pub(crate) size: Size,
 }
 
 impl Rect {
-    pub(crate) fn with_size(size: Size) -> Self {
-        let x = 0.5 * size.width;
-        let y = 0.5 * size.height;
+    pub(crate) fn new(min: Point, max: Point) -> Self {
+        let center = Point { x: 0.5 * (min.x + max.x), y: 0.5 * (min.y + max.y) };
+        let size = Size { width: max.x - min.x, height: max.y - min.y };
 
-        Self {
-            center: Point { x, y },
-            size,
-        }
+        Self { center, size }
     }
 
-    pub(crate) fn min_x(&self) -> f32 {
-        self.center.x - 0.5 * self.size.width
+    pub(crate) fn with_int_rect(rect: &IntRect) -> Self {
+        Self::new(
+            Point { x: rect.min_x as f64, y: rect.min_y as f64 },
+            Point { x: rect.max_x as f64, y: rect.max_y as f64 },
+        )
     }
 
-    pub(crate) fn min_y(&self) -> f32 {
-        self.center.y - 0.5 * self.size.height
+    pub(crate) fn scaled(&self, scale: f64) -> Rect {
+        Rect { center: self.center, size: self.size.scaled(scale) }
     }
 
-    pub(crate) fn scaled(&self, scale: f32) -> Rect {
-        Rect { center: self.center, size: self.size.scaled(scale) }
+    /// The smallest int rect that covers this one grown by `margin` on every side.
+    pub(crate) fn int_rect(&self, margin: f64) -> IntRect {
+        let w = 0.5 * self.size.width + margin;
+        let h = 0.5 * self.size.height + margin;
+        IntRect {
+            min_x: (self.center.x - w).floor() as i32,
+            max_x: (self.center.x + w).ceil() as i32,
+            min_y: (self.center.y - h).floor() as i32,
+            max_y: (self.center.y + h).ceil() as i32,
+        }
     }
 }
\ No newline at end of file
@@ -1,19 +1,19 @@
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) struct Size {
-    pub(crate) width: f32,
-    pub(crate) height: f32,
+    pub(crate) width: f64,
+    pub(crate) height: f64,
 }
 
 impl Size {
-    // pub(crate) fn new(width: f32, height: f32) -> Self {
+    // pub(crate) fn new(width: f64, height: f64) -> Self {
     //     Self { width, height }
     // }
 
     pub(crate) fn new_uint(width: u32, height: u32) -> Self {
-        Self { width: width as f32, height: height as f32 }
+        Self { width: width as f64, height: height as f64 }
     }
 
-    pub(crate) fn scaled(&self, scale: f32) -> Self {
+    pub(crate) fn scaled(&self, scale: f64) -> Self {
         let width = scale * self.width;
         let height = scale * self.height;
 
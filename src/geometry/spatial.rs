@@ -0,0 +1,319 @@
+use std::ops::Range;
+use i_triangle::i_overlay::i_float::point::IntPoint;
+use i_triangle::i_overlay::i_float::rect::IntRect;
+use i_triangle::i_overlay::i_shape::int::shape::IntShape;
+
+// a bucket holds about this many items
+const BUCKET_SIZE: usize = 64;
+// every coarser level has cells this many times wider and higher
+const LEVEL_SCALE: i64 = 4;
+
+/// A bucketed grid over bounding boxes. An item goes to the bucket of its center, so the items
+/// of a bucket stay next to each other in `order` and a query returns whole ranges of them.
+/// Items larger than a grid cell go to a grid coarser along the axis they are long in, up to
+/// a single cell over everything, so a long item is only checked by the queries near it.
+pub(crate) struct SpatialIndex {
+    // item indices grouped by bucket, row by row and level by level
+    order: Vec<u32>,
+    // bounding boxes in the same order
+    rects: Vec<IntRect>,
+    // buckets of all the levels
+    buckets: Vec<Bucket>,
+    // grids coarser along x first, then along y, from the finest one to the single cell
+    levels: Vec<Level>,
+    min_x: i64,
+    min_y: i64,
+}
+
+struct Bucket {
+    // positions in `order`
+    items: Range<usize>,
+    // bounds of the items, they may reach half a cell out of the bucket
+    bounds: Option<IntRect>,
+}
+
+struct Level {
+    cell_width: i64,
+    cell_height: i64,
+    columns: usize,
+    rows: usize,
+    // the first bucket of the level in `buckets`
+    first: usize,
+}
+
+impl SpatialIndex {
+    pub(crate) fn new(rects: &[IntRect]) -> Self {
+        let Some(bounds) = rects.iter().cloned().reduce(|a, b| IntRect::with_rects(&a, &b)) else {
+            return Self { order: Vec::new(), rects: Vec::new(), buckets: Vec::new(), levels: Vec::new(), min_x: 0, min_y: 0 };
+        };
+
+        // square cells as far as the bounds allow
+        let width = bounds.max_x as i64 - bounds.min_x as i64 + 1;
+        let height = bounds.max_y as i64 - bounds.min_y as i64 + 1;
+        let count = rects.len().div_ceil(BUCKET_SIZE);
+        let columns = ((count as f64 * width as f64 / height as f64).sqrt().round() as usize).clamp(1, count);
+        let rows = count.div_ceil(columns);
+
+        // the cell sizes and counts of every coarser grid along each axis, a long and thin item
+        // keeps fine cells across
+        let axis = |cell: i64, count: usize| {
+            let mut steps = vec![(cell, count)];
+            while let Some(&(cell, count)) = steps.last().filter(|&&(_, count)| count > 1) {
+                steps.push((cell * LEVEL_SCALE, count.div_ceil(LEVEL_SCALE as usize)));
+            }
+            steps
+        };
+        let xs = axis((width as u64).div_ceil(columns as u64) as i64, columns);
+        let ys = axis((height as u64).div_ceil(rows as u64) as i64, rows);
+
+        let mut levels = Vec::with_capacity(xs.len() * ys.len());
+        let mut bucket_count = 0;
+        for &(cell_height, rows) in ys.iter() {
+            for &(cell_width, columns) in xs.iter() {
+                levels.push(Level { cell_width, cell_height, columns, rows, first: bucket_count });
+                bucket_count += columns * rows;
+            }
+        }
+
+        let bucket_of = |rect: &IntRect| {
+            let w = rect.max_x as i64 - rect.min_x as i64;
+            let h = rect.max_y as i64 - rect.min_y as i64;
+            // the last step of an axis spans the bounds, so it holds everything
+            let x = xs.iter().position(|&(cell, _)| w <= cell).unwrap_or(xs.len() - 1);
+            let y = ys.iter().position(|&(cell, _)| h <= cell).unwrap_or(ys.len() - 1);
+            let level = &levels[y * xs.len() + x];
+            let column = ((rect.min_x as i64 + w / 2 - bounds.min_x as i64) / level.cell_width) as usize;
+            let row = ((rect.min_y as i64 + h / 2 - bounds.min_y as i64) / level.cell_height) as usize;
+            level.first + row.min(level.rows - 1) * level.columns + column.min(level.columns - 1)
+        };
+
+        // counting sort by bucket
+        let keys: Vec<usize> = rects.iter().map(bucket_of).collect();
+        let mut starts = vec![0; bucket_count + 2];
+        for &key in keys.iter() {
+            starts[key + 2] += 1;
+        }
+        for i in 2..starts.len() {
+            starts[i] += starts[i - 1];
+        }
+        let mut order = vec![0; rects.len()];
+        for (i, &key) in keys.iter().enumerate() {
+            order[starts[key + 1]] = i as u32;
+            starts[key + 1] += 1;
+        }
+
+        let rects: Vec<IntRect> = order.iter().map(|&i| rects[i as usize].clone()).collect();
+        let buckets = (0..bucket_count)
+            .map(|b| {
+                let items = starts[b]..starts[b + 1];
+                let bounds = rects[items.clone()].iter().cloned().reduce(|a, b| IntRect::with_rects(&a, &b));
+                Bucket { items, bounds }
+            })
+            .collect();
+
+        Self { order, rects, buckets, levels, min_x: bounds.min_x as i64, min_y: bounds.min_y as i64 }
+    }
+
+    /// Item indices in the order the queries refer to them.
+    #[inline]
+    pub(crate) fn order(&self) -> &[u32] {
+        &self.order
+    }
+
+    /// Bounds of all items, None for an empty index.
+    pub(crate) fn bounds(&self) -> Option<IntRect> {
+        self.buckets.iter().fold(None, |rect, bucket| IntRect::with_optional_rects(rect, bucket.bounds.clone()))
+    }
+
+    /// Positions in `order` of the items whose bounding box touches `rect`.
+    pub(crate) fn intersecting(&self, rect: &IntRect) -> Vec<usize> {
+        self.ranges(rect).into_iter()
+            .flatten()
+            .filter(|&i| overlaps(&self.rects[i], rect))
+            .collect()
+    }
+
+    /// Ranges of positions in `order` that hold every item touching `rect`. They are whole
+    /// buckets, so some items in them can be outside, neighbouring buckets are merged.
+    /// The ranges of a level come after the ones of the finer levels.
+    pub(crate) fn ranges(&self, rect: &IntRect) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        let mut add = |bucket: &Bucket| {
+            let touches = bucket.bounds.as_ref().is_some_and(|b| overlaps(b, rect));
+            if !touches {
+                return;
+            }
+            match ranges.last_mut() {
+                Some(last) if last.end == bucket.items.start => last.end = bucket.items.end,
+                _ => ranges.push(bucket.items.clone()),
+            }
+        };
+
+        // an item reaches at most half a cell into the neighbouring buckets
+        let span = |min: i32, max: i32, origin: i64, cell: i64, count: usize| {
+            let first = ((min as i64 - origin).div_euclid(cell) - 1).max(0);
+            let last = ((max as i64 - origin).div_euclid(cell) + 1).min(count as i64 - 1);
+            first as usize..(last + 1).max(first) as usize
+        };
+        for level in self.levels.iter() {
+            let columns = span(rect.min_x, rect.max_x, self.min_x, level.cell_width, level.columns);
+            for r in span(rect.min_y, rect.max_y, self.min_y, level.cell_height, level.rows) {
+                for c in columns.clone() {
+                    add(&self.buckets[level.first + r * level.columns + c]);
+                }
+            }
+        }
+
+        ranges
+    }
+}
+
+/// True if the rects share a point, the borders included.
+#[inline]
+pub(crate) fn overlaps(a: &IntRect, b: &IntRect) -> bool {
+    a.min_x <= b.max_x && b.min_x <= a.max_x && a.min_y <= b.max_y && b.min_y <= a.max_y
+}
+
+/// True if the point is inside the shape or on its border, holes are outside.
+pub(crate) fn shape_contains(shape: &IntShape, point: IntPoint) -> bool {
+    let (x, y) = (point.x as i64, point.y as i64);
+    let mut inside = false;
+    for path in shape.iter() {
+        let Some(last) = path.last() else {
+            continue;
+        };
+        let mut a = (last.x as i64, last.y as i64);
+        for p in path.iter() {
+            let b = (p.x as i64, p.y as i64);
+            let t = (b.0 - a.0) * (y - a.1) - (x - a.0) * (b.1 - a.1);
+            if t == 0 && a.0.min(b.0) <= x && x <= a.0.max(b.0) && a.1.min(b.1) <= y && y <= a.1.max(b.1) {
+                return true;
+            }
+            // the edge crosses the ray to the right of the point
+            if (a.1 > y) != (b.1 > y) && (t > 0) == (b.1 > a.1) {
+                inside = !inside;
+            }
+            a = b;
+        }
+    }
+    inside
+}
+
+/// True if the shape and the rect share a point.
+pub(crate) fn shape_intersects(shape: &IntShape, rect: &IntRect) -> bool {
+    let edge_touches = shape.iter().any(|path| {
+        path.iter().zip(path.iter().cycle().skip(1)).any(|(a, b)| segment_intersects(*a, *b, rect))
+    });
+
+    // otherwise the rect is either all inside or all outside
+    edge_touches || shape_contains(shape, IntPoint::new(rect.min_x, rect.min_y))
+}
+
+// Liang-Barsky clipping of the segment by the rect
+fn segment_intersects(a: IntPoint, b: IntPoint, rect: &IntRect) -> bool {
+    let (x, y) = (a.x as f64, a.y as f64);
+    let (dx, dy) = (b.x as f64 - x, b.y as f64 - y);
+    let (mut t0, mut t1) = (0.0, 1.0);
+    let sides = [
+        (-dx, x - rect.min_x as f64),
+        (dx, rect.max_x as f64 - x),
+        (-dy, y - rect.min_y as f64),
+        (dy, rect.max_y as f64 - y),
+    ];
+    for (p, q) in sides {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = f64::max(t0, t);
+            } else {
+                t1 = f64::min(t1, t);
+            }
+        }
+    }
+    t0 <= t1
+}
+
+#[cfg(test)]
+mod tests {
+    use i_triangle::i_overlay::i_float::point::IntPoint;
+    use i_triangle::i_overlay::i_float::rect::IntRect;
+    use crate::geometry::spatial::{shape_contains, shape_intersects, SpatialIndex};
+
+    fn rect(min_x: i32, min_y: i32, max_x: i32, max_y: i32) -> IntRect {
+        IntRect { min_x, max_x, min_y, max_y }
+    }
+
+    #[test]
+    fn test_index() {
+        // a 100 x 100 grid of unit squares at a pitch of 10 and one square over all of them
+        let mut rects: Vec<IntRect> = (0..10_000).map(|i| {
+            let (x, y) = (10 * (i % 100), 10 * (i / 100));
+            rect(x, y, x + 1, y + 1)
+        }).collect();
+        rects.push(rect(-5, -5, 1000, 1000));
+        let index = SpatialIndex::new(&rects);
+        let bounds = index.bounds().unwrap();
+        assert_eq!((bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y), (-5, -5, 1000, 1000));
+
+        let found = |query: IntRect| {
+            let mut items: Vec<u32> = index.intersecting(&query).iter().map(|&p| index.order()[p]).collect();
+            items.sort();
+            items
+        };
+        assert_eq!(found(rect(10, 20, 21, 30)), vec![201, 202, 301, 302, 10_000]);
+        assert_eq!(found(rect(-100, -100, -10, -10)), Vec::<u32>::new());
+        assert_eq!(found(rect(-100, -100, 2000, 2000)).len(), 10_001);
+
+        // the ranges cover the found items and far less than all of them
+        let query = rect(400, 400, 420, 420);
+        let ranges = index.ranges(&query);
+        let covered: usize = ranges.iter().map(|r| r.len()).sum();
+        assert!(covered < 1000, "{covered}");
+        assert!(index.intersecting(&query).iter().all(|p| ranges.iter().any(|r| r.contains(p))));
+    }
+
+    #[test]
+    fn test_long_items() {
+        // a 100 x 100 grid of unit squares under 100 wires that each cross the whole grid
+        let mut rects: Vec<IntRect> = (0..10_000).map(|i| {
+            let (x, y) = (10 * (i % 100), 10 * (i / 100));
+            rect(x, y, x + 1, y + 1)
+        }).collect();
+        rects.extend((0..100).map(|i| rect(0, 10 * i + 5, 1000, 10 * i + 6)));
+        let index = SpatialIndex::new(&rects);
+
+        // a small query checks the wires near it only
+        let query = rect(400, 400, 420, 420);
+        let covered: Vec<usize> = index.ranges(&query).into_iter().flatten().collect();
+        let wires = covered.iter().filter(|&&p| index.order()[p] >= 10_000).count();
+        assert!(wires < 50, "{wires}");
+
+        let mut found: Vec<u32> = index.intersecting(&query).iter().map(|&p| index.order()[p]).collect();
+        found.sort();
+        assert_eq!(found, vec![4040, 4041, 4042, 4140, 4141, 4142, 4240, 4241, 4242, 10_040, 10_041]);
+    }
+
+    #[test]
+    fn test_shape() {
+        // a square with a square hole
+        let shape = vec![
+            vec![IntPoint::new(0, 0), IntPoint::new(0, 10), IntPoint::new(10, 10), IntPoint::new(10, 0)],
+            vec![IntPoint::new(3, 3), IntPoint::new(7, 3), IntPoint::new(7, 7), IntPoint::new(3, 7)],
+        ];
+        assert!(shape_contains(&shape, IntPoint::new(1, 5)));
+        assert!(shape_contains(&shape, IntPoint::new(10, 4)));
+        assert!(shape_contains(&shape, IntPoint::new(3, 5)));
+        assert!(!shape_contains(&shape, IntPoint::new(5, 5)));
+        assert!(!shape_contains(&shape, IntPoint::new(11, 5)));
+
+        assert!(shape_intersects(&shape, &rect(-5, -5, 1, 1)));
+        assert!(shape_intersects(&shape, &rect(-5, -5, 20, 20)));
+        assert!(shape_intersects(&shape, &rect(1, 1, 2, 2)));
+        assert!(!shape_intersects(&shape, &rect(4, 4, 6, 6)));
+        assert!(!shape_intersects(&shape, &rect(11, 0, 12, 1)));
+    }
+}
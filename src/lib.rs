@@ -1,5 +1,8 @@
 pub mod app;
+pub mod cli;
 pub(crate) mod draw;
 mod geometry;
 mod eye;
-mod control;
\ No newline at end of file
+mod control;
+mod gds;
+mod lyp;
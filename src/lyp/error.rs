@@ -0,0 +1,17 @@
+use std::fmt;
+
+/// A layer properties file that can not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LypError {
+    /// 1-based line in the file
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for LypError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}", self.message, self.line)
+    }
+}
+
+impl std::error::Error for LypError {}
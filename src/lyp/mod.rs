@@ -0,0 +1,3 @@
+pub(crate) mod error;
+pub(crate) mod properties;
+mod xml;
@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use log::warn;
+use crate::draw::fill::pattern::FillPattern;
+use crate::draw::layer::LayerTable;
+use crate::draw::palette::pattern_alpha;
+use crate::lyp::error::LypError;
+use crate::lyp::xml::{parse_xml, XmlElement};
+
+/// Layer numbers an entry is shown for, None matches any number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LayerSource {
+    pub(crate) layer: Option<u16>,
+    pub(crate) datatype: Option<u16>,
+}
+
+/// A single entry of a KLayout layer properties file, missing values keep the defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LayerProperties {
+    pub(crate) source: LayerSource,
+    pub(crate) name: Option<String>,
+    pub(crate) fill_color: Option<[f32; 3]>,
+    pub(crate) frame_color: Option<[f32; 3]>,
+    pub(crate) dither_pattern: Option<String>,
+    pub(crate) visible: Option<bool>,
+    /// frame width in pixels
+    pub(crate) width: Option<f32>,
+}
+
+/// Entries and custom dither patterns of a `.lyp` file.
+pub(crate) struct LayerPropertiesFile {
+    pub(crate) entries: Vec<LayerProperties>,
+    /// custom patterns by their order number, entries refer to them as `C<order>`
+    pub(crate) patterns: Vec<(u32, FillPattern)>,
+}
+
+impl LayerSource {
+    /// Parses the layer part of a source like `1/0@1`, `metal1 1/0@1` or `*/*@*`.
+    fn parse(value: &str) -> Option<Self> {
+        for token in value.split_whitespace() {
+            let numbers = token.split('@').next().unwrap_or_default();
+            let (layer, datatype) = numbers.split_once('/').unwrap_or((numbers, "*"));
+            if let (Some(layer), Some(datatype)) = (Self::number(layer), Self::number(datatype)) {
+                return Some(Self { layer, datatype });
+            }
+        }
+        None
+    }
+
+    fn number(value: &str) -> Option<Option<u16>> {
+        if value == "*" {
+            Some(None)
+        } else {
+            value.parse().ok().map(Some)
+        }
+    }
+
+    fn is_exact(&self, layer: u16, datatype: u16) -> bool {
+        self.layer == Some(layer) && self.datatype == Some(datatype)
+    }
+
+    fn matches(&self, layer: u16, datatype: u16) -> bool {
+        self.layer.is_none_or(|l| l == layer) && self.datatype.is_none_or(|d| d == datatype)
+    }
+}
+
+impl LayerProperties {
+    // group members inherit what they do not set from the group
+    fn with_element(element: &XmlElement, group: Option<&LayerProperties>) -> Option<Self> {
+        let source = element.child_text("source").and_then(LayerSource::parse);
+
+        Some(Self {
+            source: source.or(group.map(|g| g.source))?,
+            name: element.child_text("name").filter(|n| !n.is_empty()).map(|n| n.to_string())
+                .or_else(|| group.and_then(|g| g.name.clone())),
+            fill_color: element.child_text("fill-color").and_then(parse_color)
+                .or(group.and_then(|g| g.fill_color)),
+            frame_color: element.child_text("frame-color").and_then(parse_color)
+                .or(group.and_then(|g| g.frame_color)),
+            dither_pattern: element.child_text("dither-pattern").filter(|p| !p.is_empty()).map(|p| p.to_string())
+                .or_else(|| group.and_then(|g| g.dither_pattern.clone())),
+            visible: element.child_text("visible").and_then(parse_bool)
+                .or(group.and_then(|g| g.visible)),
+            width: element.child_text("width").and_then(|w| w.parse::<f32>().ok())
+                // KLayout draws a zero width as a single pixel
+                .map(|w| w.max(1.0))
+                .or(group.and_then(|g| g.width)),
+        })
+    }
+}
+
+/// Reads the entries of a `.lyp` file in file order, groups are flattened.
+pub(crate) fn read_layer_properties(text: &str) -> Result<LayerPropertiesFile, LypError> {
+    let root = parse_xml(text)?;
+    if root.name != "layer-properties" {
+        return Err(LypError { line: 1, message: format!("<layer-properties> expected, got <{}>", root.name) });
+    }
+
+    let mut entries = Vec::new();
+    for element in root.children_named("properties") {
+        add_entries(element, None, &mut entries);
+    }
+
+    let mut patterns = Vec::new();
+    for element in root.children_named("custom-dither-pattern") {
+        let order = element.child_text("order").and_then(|o| o.parse().ok());
+        let lines: Vec<&str> = element.child("pattern")
+            .map(|p| p.children_named("line").map(|l| l.text.trim()).collect())
+            .unwrap_or_default();
+        match (order, FillPattern::with_lines(&lines)) {
+            (Some(order), Some(pattern)) => patterns.push((order, pattern)),
+            _ => warn!("bad custom dither pattern {}", element.child_text("name").unwrap_or_default()),
+        }
+    }
+
+    Ok(LayerPropertiesFile { entries, patterns })
+}
+
+fn add_entries(element: &XmlElement, group: Option<&LayerProperties>, entries: &mut Vec<LayerProperties>) {
+    let properties = LayerProperties::with_element(element, group);
+    let mut members = element.children_named("group-members").peekable();
+
+    if members.peek().is_none() {
+        match properties {
+            Some(properties) => entries.push(properties),
+            None => warn!("layer properties without a layer source: {}", element.child_text("source").unwrap_or_default()),
+        }
+        return;
+    }
+
+    for member in members {
+        add_entries(member, properties.as_ref(), entries);
+    }
+}
+
+/// Styles the layer table: every layer takes the first entry with its exact numbers,
+/// otherwise the first one matching by wildcards. Layers keep the palette if nothing matches
+/// and are drawn above the listed ones.
+pub(crate) fn apply_layer_properties(layers: &mut LayerTable, file: &LayerPropertiesFile) {
+    let builtins = layers.patterns().len() as u32;
+    let mut customs = HashMap::new();
+    for &(order, pattern) in file.patterns.iter() {
+        match layers.add_pattern(pattern) {
+            Some(index) => { customs.insert(order, index); }
+            None => warn!("too many fill patterns, C{order} is drawn solid"),
+        }
+    }
+
+    let properties = &file.entries;
+    for (index, layer) in layers.iter_mut().enumerate() {
+        let found = properties.iter().position(|p| p.source.is_exact(layer.layer, layer.datatype))
+            .or_else(|| properties.iter().position(|p| p.source.matches(layer.layer, layer.datatype)));
+
+        let Some(position) = found else {
+            layer.order = properties.len() + index;
+            continue;
+        };
+
+        let entry = &properties[position];
+        layer.order = position;
+        if let Some(name) = &entry.name {
+            layer.name = name.clone();
+        }
+        if let Some([red, green, blue]) = entry.fill_color {
+            layer.brush.red = red;
+            layer.brush.green = green;
+            layer.brush.blue = blue;
+        }
+        if let Some(dither) = &entry.dither_pattern {
+            match pattern_index(dither, builtins, &customs) {
+                Some(pattern) => {
+                    layer.brush.pattern = pattern;
+                    layer.brush.alpha = pattern_alpha(pattern);
+                }
+                None => warn!("unknown dither pattern {dither} of {}", layer.name),
+            }
+        }
+        if let Some([red, green, blue]) = entry.frame_color {
+            layer.pencil.red = red;
+            layer.pencil.green = green;
+            layer.pencil.blue = blue;
+        }
+        if let Some(visible) = entry.visible {
+            layer.visible = visible;
+        }
+        if let Some(width) = entry.width {
+            layer.pencil.width = width;
+        }
+    }
+}
+
+// `I<n>` is a built-in pattern, `C<n>` a custom one
+fn pattern_index(value: &str, builtins: u32, customs: &HashMap<u32, u32>) -> Option<u32> {
+    if let Some(index) = value.strip_prefix('I') {
+        index.parse().ok().filter(|&i| i < builtins)
+    } else {
+        let order = value.strip_prefix('C')?.parse().ok()?;
+        customs.get(&order).copied()
+    }
+}
+
+// "#rrggbb", KLayout may also write "#aarrggbb", the alpha is not used there
+fn parse_color(value: &str) -> Option<[f32; 3]> {
+    let hex = value.strip_prefix('#')?;
+    let rgb = match hex.len() {
+        6 => hex,
+        8 => &hex[2..],
+        _ => return None,
+    };
+    let value = u32::from_str_radix(rgb, 16).ok()?;
+    Some([16, 8, 0].map(|shift| ((value >> shift) & 0xFF) as f32 / 255.0))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::draw::fill::pattern::{builtin_patterns, HOLLOW_PATTERN};
+    use crate::draw::layer::{Layer, LayerTable};
+    use crate::draw::palette::{layer_brush, pattern_alpha};
+    use crate::lyp::properties::{apply_layer_properties, read_layer_properties, LayerSource};
+
+    const LYP: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<layer-properties>
+ <properties>
+  <frame-color>#ff0000</frame-color>
+  <fill-color>#00ff80</fill-color>
+  <dither-pattern>I5</dither-pattern>
+  <visible>true</visible>
+  <width>3</width>
+  <name>metal1</name>
+  <source>1/0@1</source>
+ </properties>
+ <properties>
+  <frame-color>#0000ff</frame-color>
+  <fill-color/>
+  <dither-pattern>I1</dither-pattern>
+  <visible>false</visible>
+  <width>0</width>
+  <name>vias</name>
+  <source>*/*@*</source>
+  <group-members>
+   <fill-color>#101010</fill-color>
+   <name>via1</name>
+   <source>2/*@1</source>
+  </group-members>
+  <group-members>
+   <source>oops</source>
+  </group-members>
+ </properties>
+ <properties>
+  <frame-color>#ffffff</frame-color>
+  <source>1/0@1</source>
+ </properties>
+ <properties>
+  <dither-pattern>C3</dither-pattern>
+  <source>6/0@1</source>
+ </properties>
+ <custom-dither-pattern>
+  <pattern>
+   <line>*.</line>
+   <line>..</line>
+  </pattern>
+  <order>3</order>
+  <name>sparse</name>
+ </custom-dither-pattern>
+ <custom-dither-pattern>
+  <pattern/>
+  <order>4</order>
+ </custom-dither-pattern>
+</layer-properties>
+"#;
+
+    #[test]
+    fn test_read() {
+        let file = read_layer_properties(LYP).unwrap();
+        let entries = &file.entries;
+        assert_eq!(entries.len(), 5);
+
+        assert_eq!(entries[0].source, LayerSource { layer: Some(1), datatype: Some(0) });
+        assert_eq!(entries[0].name.as_deref(), Some("metal1"));
+        assert_eq!(entries[0].fill_color, Some([0.0, 1.0, 128.0 / 255.0]));
+        assert_eq!(entries[0].width, Some(3.0));
+
+        // the group member takes the rest from the group, the broken source falls back to it
+        assert_eq!(entries[1].source, LayerSource { layer: Some(2), datatype: None });
+        assert_eq!(entries[1].name.as_deref(), Some("via1"));
+        assert_eq!(entries[1].fill_color, Some([16.0 / 255.0; 3]));
+        assert_eq!(entries[1].frame_color, Some([0.0, 0.0, 1.0]));
+        assert_eq!(entries[1].visible, Some(false));
+        assert_eq!(entries[1].width, Some(1.0));
+        assert_eq!(entries[2].source, LayerSource { layer: None, datatype: None });
+        assert_eq!(entries[2].name.as_deref(), Some("vias"));
+        assert_eq!(entries[3].source, LayerSource { layer: Some(1), datatype: Some(0) });
+
+        assert_eq!(file.patterns.len(), 1);
+        assert_eq!(file.patterns[0].0, 3);
+        assert_eq!(file.patterns[0].1.rows[0], 0x5555_5555);
+        assert_eq!(file.patterns[0].1.rows[1], 0);
+
+        assert!(read_layer_properties("<layers/>").is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut layers = LayerTable::new(vec![
+            Layer::new(1, 0, 0),
+            Layer::new(2, 7, 1),
+            Layer::new(5, 0, 2),
+            Layer::new(6, 0, 3),
+        ]);
+        let mut file = read_layer_properties(LYP).unwrap();
+        // without the catch-all entry
+        file.entries.remove(2);
+        apply_layer_properties(&mut layers, &file);
+
+        let metal = &layers[0];
+        assert_eq!(metal.name, "metal1");
+        assert_eq!([metal.brush.red, metal.brush.green, metal.brush.blue], [0.0, 1.0, 128.0 / 255.0]);
+        assert_eq!(metal.brush.pattern, 5);
+        assert_eq!(metal.brush.alpha, pattern_alpha(5));
+        assert_eq!([metal.pencil.red, metal.pencil.green, metal.pencil.blue], [1.0, 0.0, 0.0]);
+        assert_eq!(metal.pencil.width, 3.0);
+
+        let via = &layers[1];
+        assert_eq!(via.name, "via1");
+        assert_eq!(via.brush.pattern, HOLLOW_PATTERN);
+        assert!(!via.visible);
+
+        // not listed, keeps the palette and goes on top
+        let other = &layers[2];
+        assert_eq!(other.name, "5/0");
+        assert_eq!(other.brush.red, layer_brush(2).red);
+        assert_eq!(other.brush.pattern, layer_brush(2).pattern);
+
+        let custom = layers[3].brush.pattern as usize;
+        assert_eq!(custom, builtin_patterns().len());
+        assert_eq!(layers.patterns()[custom], file.patterns[0].1);
+
+        assert_eq!(layers.draw_order(), vec![0, 3, 2]);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::lyp::error::LypError;
+
+/// Just enough XML for layer properties files: elements, attributes are skipped,
+/// text with the predefined and numeric entities, comments, CDATA and the prolog.
+pub(crate) struct XmlElement {
+    pub(crate) name: String,
+    pub(crate) children: Vec<XmlElement>,
+    pub(crate) text: String,
+}
+
+impl XmlElement {
+    pub(crate) fn child(&self, name: &str) -> Option<&XmlElement> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub(crate) fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item=&'a XmlElement> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// Trimmed text of a child element, None if there is no such child.
+    pub(crate) fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|c| c.text.trim())
+    }
+}
+
+/// Parses a document and returns its root element.
+pub(crate) fn parse_xml(text: &str) -> Result<XmlElement, LypError> {
+    let mut parser = Parser { text, offset: 0 };
+    parser.skip_misc()?;
+    if !parser.rest().starts_with('<') {
+        return Err(parser.error("root element expected"));
+    }
+    let root = parser.element()?;
+    parser.skip_misc()?;
+    if !parser.rest().is_empty() {
+        return Err(parser.error("unexpected content after the root element"));
+    }
+
+    Ok(root)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    #[inline]
+    fn rest(&self) -> &'a str {
+        &self.text[self.offset..]
+    }
+
+    fn error(&self, message: &str) -> LypError {
+        let line = self.text[..self.offset].matches('\n').count() + 1;
+        LypError { line, message: message.to_string() }
+    }
+
+    // moves past `end`, the error names what was not closed
+    fn skip_past(&mut self, end: &str, what: &str) -> Result<(), LypError> {
+        match self.rest().find(end) {
+            Some(i) => {
+                self.offset += i + end.len();
+                Ok(())
+            }
+            None => Err(self.error(&format!("unterminated {what}"))),
+        }
+    }
+
+    // white space, comments, processing instructions and the doctype around the root
+    fn skip_misc(&mut self) -> Result<(), LypError> {
+        loop {
+            self.offset = self.text.len() - self.rest().trim_start().len();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>", "processing instruction")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->", "comment")?;
+            } else if rest.starts_with("<!") {
+                self.skip_past(">", "declaration")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, LypError> {
+        let rest = self.rest();
+        let length = rest.find(|c: char| c.is_whitespace() || c == '>' || c == '/').unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error("element name expected"));
+        }
+        self.offset += length;
+        Ok(rest[..length].to_string())
+    }
+
+    // the parser stands on '<' of a start tag
+    fn element(&mut self) -> Result<XmlElement, LypError> {
+        self.offset += 1;
+        let name = self.name()?;
+
+        // attributes are not used, quoted values may hold '>'
+        let mut quote = None;
+        let mut closed = false;
+        let mut end = None;
+        for (i, c) in self.rest().char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '/') => closed = true,
+                (None, '>') => {
+                    end = Some(i);
+                    break;
+                }
+                (None, c) if !c.is_whitespace() => closed = false,
+                _ => {}
+            }
+        }
+        let Some(end) = end else {
+            return Err(self.error(&format!("unterminated tag <{name}>")));
+        };
+        self.offset += end + 1;
+
+        let mut element = XmlElement { name, children: Vec::new(), text: String::new() };
+        if closed {
+            return Ok(element);
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(self.error(&format!("element <{}> is not closed", element.name)));
+            }
+            if rest.starts_with("</") {
+                self.offset += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(self.error(&format!("</{name}> closes <{}>", element.name)));
+                }
+                self.skip_past(">", "end tag")?;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->", "comment")?;
+            } else if let Some(data) = rest.strip_prefix("<![CDATA[") {
+                let Some(i) = data.find("]]>") else {
+                    return Err(self.error("unterminated CDATA"));
+                };
+                element.text.push_str(&data[..i]);
+                self.offset += "<![CDATA[".len() + i + 3;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>", "processing instruction")?;
+            } else if rest.starts_with('<') {
+                let child = self.element()?;
+                element.children.push(child);
+            } else {
+                let length = rest.find('<').unwrap_or(rest.len());
+                let text = self.unescape(&rest[..length])?;
+                element.text.push_str(&text);
+                self.offset += length;
+            }
+        }
+    }
+
+    fn unescape(&self, text: &str) -> Result<String, LypError> {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(i) = rest.find('&') {
+            out.push_str(&rest[..i]);
+            let Some(end) = rest[i..].find(';') else {
+                return Err(self.error("unterminated entity"));
+            };
+            let entity = &rest[i + 1..i + end];
+            let c = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => {
+                    let code = if let Some(hex) = entity.strip_prefix("#x") {
+                        u32::from_str_radix(hex, 16).ok()
+                    } else if let Some(decimal) = entity.strip_prefix('#') {
+                        decimal.parse().ok()
+                    } else {
+                        None
+                    };
+                    code.and_then(char::from_u32)
+                }
+            };
+            let Some(c) = c else {
+                return Err(self.error(&format!("unknown entity &{entity};")));
+            };
+            out.push(c);
+            rest = &rest[i + end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lyp::xml::parse_xml;
+
+    #[test]
+    fn test_parse() {
+        let text = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+            <!-- layers -->\n\
+            <root kind=\"a>b\">\n\
+             <item><name>M1 &amp; &lt;via&gt; &#65;&#x42;</name><empty/><empty /></item>\n\
+             <item><name><![CDATA[<raw>]]></name></item>\n\
+            </root>\n";
+
+        let root = parse_xml(text).unwrap();
+        assert_eq!(root.name, "root");
+        let items: Vec<_> = root.children_named("item").collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].child_text("name"), Some("M1 & <via> AB"));
+        assert_eq!(items[0].children_named("empty").count(), 2);
+        assert_eq!(items[1].child_text("name"), Some("<raw>"));
+        assert_eq!(items[1].child_text("missing"), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = parse_xml("<a>\n<b>\n</a>").err().unwrap();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.message, "</a> closes <b>");
+
+        assert!(parse_xml("<a>&bad;</a>").is_err());
+        assert!(parse_xml("<a>").is_err());
+        assert!(parse_xml("<a/><b/>").is_err());
+    }
+}
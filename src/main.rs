@@ -1,14 +1,95 @@
-use log::info;
+use std::path::Path;
+use log::{error, info};
 use winit::event_loop::EventLoop;
 use gds_viewer::app::state::AppState;
+use gds_viewer::cli;
 
 pub fn main() {
     env_logger::Builder::from_default_env()
         .filter(None, log::LevelFilter::Info) // Set log level to Info
         .init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = cli::run(&args) {
+        if let Err(err) = result {
+            error!("{err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut lenient = false;
+    let mut lyp = None;
+    let mut bookmarks = None;
+    let mut path = None;
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--lenient" => lenient = true,
+            "--lyp" => lyp = iter.next(),
+            "--bookmarks" => bookmarks = iter.next(),
+            _ if !arg.starts_with("--") => path = Some(arg),
+            _ => {}
+        }
+    }
+
+    let mut state = match &path {
+        Some(path) => {
+            let data = match std::fs::read(path) {
+                Ok(data) => data,
+                Err(err) => {
+                    error!("can not read {path}: {err}");
+                    std::process::exit(1);
+                }
+            };
+            match AppState::with_gds(&data, lenient) {
+                Ok(state) => state,
+                Err(err) => {
+                    error!("can not load {path}: {err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => AppState::new(),
+    };
+
+    if let Some(lyp) = lyp {
+        let loaded = std::fs::read_to_string(&lyp)
+            .map_err(|err| err.to_string())
+            .and_then(|text| state.load_layer_properties(&text).map_err(|err| err.to_string()));
+        if let Err(err) = loaded {
+            error!("can not load {lyp}: {err}");
+            std::process::exit(1);
+        }
+    }
+
+    // bookmarks are shared next to the GDS file, chip.gds has chip.bookmarks
+    let bookmarks = bookmarks.or_else(|| {
+        path.as_ref().map(|p| Path::new(p).with_extension("bookmarks").to_string_lossy().into_owned())
+    });
+    // the file is written only if the bookmarks changed
+    let mut saved_bookmarks = state.export_bookmarks();
+    if let Some(bookmarks) = &bookmarks {
+        if let Ok(text) = std::fs::read_to_string(bookmarks) {
+            if let Err(err) = state.import_bookmarks(&text) {
+                error!("can not load {bookmarks}: {err}");
+                std::process::exit(1);
+            }
+            saved_bookmarks = state.export_bookmarks();
+        }
+    }
+
     info!("Starting application...");
     let event_loop = EventLoop::new().unwrap();
-    let mut state = AppState::new();
     let _ = event_loop.run_app(&mut state);
     info!("Release application...");
+
+    if let Some(bookmarks) = bookmarks {
+        let text = state.export_bookmarks();
+        if text != saved_bookmarks {
+            match std::fs::write(&bookmarks, text) {
+                Ok(()) => info!("bookmarks saved to {bookmarks}"),
+                Err(err) => error!("can not save {bookmarks}: {err}"),
+            }
+        }
+    }
 }